@@ -1,13 +1,19 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+/// The first map is persisted by `eframe` on shutdown, the second one only lives
+/// for the current session (unlocked secrets etc.) and is never written to disk.
 //#[derive(serde::Deserialize, serde::Serialize,Default)]
-pub struct AppState(pub Arc<Mutex<HashMap<String, String>>>);
+pub struct AppState(pub Arc<Mutex<HashMap<String, String>>>, Arc<Mutex<HashMap<String, String>>>);
 
 
 impl AppState {
     pub fn new() -> Self {
-        AppState(Arc::new(Mutex::new(HashMap::<String, String>::new())))
+        AppState::from_data(HashMap::<String, String>::new())
+    }
+
+    pub fn from_data(data: HashMap<String, String>) -> Self {
+        AppState(Arc::new(Mutex::new(data)), Arc::new(Mutex::new(HashMap::<String, String>::new())))
     }
 
     pub fn set_value(&self, key: String, value: String) {
@@ -25,6 +31,22 @@ impl AppState {
     pub fn exists(&self, key: &str) -> bool {
         self.0.lock().unwrap().contains_key(key)
     }
+
+    pub fn remove_value(&self, key: &str) -> Option<String> {
+        self.0.lock().unwrap().remove(key)
+    }
+
+    pub fn set_session_value(&self, key: String, value: String) {
+        self.1.lock().unwrap().insert(key, value);
+    }
+
+    pub fn get_session_value(&self, key: &str) -> Option<String> {
+        self.1.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn remove_session_value(&self, key: &str) -> Option<String> {
+        self.1.lock().unwrap().remove(key)
+    }
 }
 
 
@@ -34,9 +56,20 @@ mod tests {
 
     #[test]
     fn test() {
-        let state = AppState(Arc::new(Mutex::new(HashMap::<String, String>::new())));
+        let state = AppState::from_data(HashMap::<String, String>::new());
         state.set_value("a".to_owned(), "b".to_owned());
         let option = state.get_value("a").unwrap();
         println!("{}", option);
     }
+
+    #[test]
+    fn test_session() {
+        let state = AppState::new();
+        state.set_session_value("a".to_owned(), "b".to_owned());
+        assert_eq!(state.get_session_value("a"), Some("b".to_owned()));
+        assert!(!state.exists("a"));
+        assert_eq!(state.get_data().unwrap().len(), 0);
+        state.remove_session_value("a");
+        assert_eq!(state.get_session_value("a"), None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use sp_core::crypto::{AccountId32, PublicError, Ss58AddressFormat, Ss58Codec};
+
+/// An account parsed from an SS58 address or a raw public key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Address {
+    pub account: AccountId32,
+    /// Network prefix of the address, `None` for a raw `0x` public key.
+    pub prefix: Option<u16>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    Empty,
+    BadBase58,
+    /// Starts with `0x` but is not 32 bytes of hex.
+    BadHex,
+    /// Decodes to an address that is not a 32 byte account.
+    BadLength,
+    /// Not a valid one or two byte prefix, or one reserved by the SS58 registry.
+    BadPrefix,
+    /// Mistyped or truncated.
+    InvalidChecksum,
+    /// A valid address of another network, with the same account encoded for the expected one.
+    WrongNetwork { prefix: u16, expected: u16, reencoded: String },
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "address is empty"),
+            AddressError::BadBase58 => write!(f, "address has a character that is not base58"),
+            AddressError::BadHex => write!(f, "a public key must be 0x and 64 hex digits"),
+            AddressError::BadLength => write!(f, "address has the wrong length for an account"),
+            AddressError::BadPrefix => write!(f, "address starts with an invalid network prefix"),
+            AddressError::InvalidChecksum => write!(f, "invalid checksum, the address is mistyped or incomplete"),
+            AddressError::WrongNetwork { prefix, expected, .. } => {
+                write!(f, "valid {} address, but you are on {}", NetworkName(*prefix), NetworkName(*expected))
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+impl From<PublicError> for AddressError {
+    fn from(error: PublicError) -> Self {
+        match error {
+            PublicError::BadLength => AddressError::BadLength,
+            PublicError::InvalidChecksum => AddressError::InvalidChecksum,
+            PublicError::InvalidPrefix | PublicError::UnknownSs58AddressFormat(_) | PublicError::FormatNotAllowed => AddressError::BadPrefix,
+            // the others are of derivation paths, not parsed here
+            _ => AddressError::BadBase58,
+        }
+    }
+}
+
+
+impl Address {
+    /// The account encoded for the network with `prefix`.
+    pub fn to_ss58(&self, prefix: u16) -> String {
+        to_ss58(&self.account, prefix)
+    }
+}
+
+/// `account` encoded for the network with `prefix`.
+pub fn to_ss58(account: &AccountId32, prefix: u16) -> String {
+    account.to_ss58check_with_version(Ss58AddressFormat::custom(prefix))
+}
+
+/// Parse an SS58 address of any network or a `0x` public key.
+pub fn parse_address(text: &str) -> Result<Address, AddressError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AddressError::Empty);
+    }
+    if let Some(key) = text.strip_prefix("0x") {
+        let key = hex::decode(key).map_err(|_| AddressError::BadHex)?;
+        let key = <[u8; 32]>::try_from(key).map_err(|_| AddressError::BadHex)?;
+        return Ok(Address { account: AccountId32::new(key), prefix: None });
+    }
+
+    let (account, format) = AccountId32::from_ss58check_with_version(text)?;
+    Ok(Address { account, prefix: Some(format.prefix()) })
+}
+
+/// Parse an address for the network with `prefix`. Raw public keys belong to every network.
+pub fn check_address(text: &str, prefix: u16) -> Result<Address, AddressError> {
+    let address = parse_address(text)?;
+    match address.prefix {
+        Some(other) if other != prefix => Err(AddressError::WrongNetwork {
+            prefix: other,
+            expected: prefix,
+            reencoded: address.to_ss58(prefix),
+        }),
+        _ => Ok(address),
+    }
+}
+
+/// Name of well known networks, `prefix N` otherwise.
+pub struct NetworkName(pub u16);
+
+impl fmt::Display for NetworkName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0 => write!(f, "Polkadot"),
+            2 => write!(f, "Kusama"),
+            5 => write!(f, "Astar"),
+            8 => write!(f, "Karura"),
+            10 => write!(f, "Acala"),
+            42 => write!(f, "Substrate"),
+            prefix => write!(f, "prefix {}", prefix),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_parse_address() {
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        assert_eq!(parse_address(ALICE), Ok(Address { account: alice.clone(), prefix: Some(42) }));
+        for prefix in [0u16, 2, 63, 64, 1_000, 16_383] {
+            let address = alice.to_ss58check_with_version(Ss58AddressFormat::custom(prefix));
+            assert_eq!(parse_address(&address).unwrap().prefix, Some(prefix), "prefix {}", prefix);
+            assert_eq!(check_address(&address, prefix).unwrap().account, alice);
+        }
+        let key = format!(" 0x{} ", hex::encode(&alice));
+        assert_eq!(parse_address(&key), Ok(Address { account: alice.clone(), prefix: None }));
+        assert_eq!(check_address(&key, 2).unwrap().to_ss58(2), alice.to_ss58check_with_version(Ss58AddressFormat::custom(2)));
+
+        let kusama = alice.to_ss58check_with_version(Ss58AddressFormat::custom(2));
+        let polkadot = alice.to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        let error = check_address(&kusama, 0).unwrap_err();
+        assert_eq!(error, AddressError::WrongNetwork { prefix: 2, expected: 0, reencoded: polkadot });
+        assert_eq!(error.to_string(), "valid Kusama address, but you are on Polkadot");
+
+        assert_eq!(parse_address(""), Err(AddressError::Empty));
+        assert_eq!(parse_address("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQ0"), Err(AddressError::BadBase58));
+        assert_eq!(parse_address("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ"), Err(AddressError::InvalidChecksum));
+        assert_eq!(parse_address("1111"), Err(AddressError::BadLength));
+        // reserved by the registry
+        assert_eq!(parse_address(&alice.to_ss58check_with_version(Ss58AddressFormat::custom(46))), Err(AddressError::BadPrefix));
+        assert_eq!(parse_address("0x1234"), Err(AddressError::BadHex));
+        assert_eq!(parse_address("0xzz"), Err(AddressError::BadHex));
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+
+/// Characters accepted between groups of three digits of the whole part.
+const SEPARATORS: [char; 3] = [',', '_', ' '];
+
+/// An amount in plancks with the decimals of its token, parsed and formatted exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount {
+    plancks: u128,
+    decimals: u8,
+}
+
+/// How digits cut by [`Amount::format`] are rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero, never shows more than there is.
+    Down,
+    /// Away from zero, never shows less than there is, e.g. for fees.
+    Up,
+    /// To the nearest, halves away from zero.
+    HalfUp,
+    /// To the nearest, halves to the even digit.
+    HalfEven,
+}
+
+/// Why an amount typed by the user is not an [`Amount`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AmountError {
+    Empty,
+    InvalidCharacter(char),
+    /// More fractional digits than the token has decimals.
+    TooManyDecimals(u8),
+    /// Thousands separators must split the whole part in groups of three.
+    MisplacedSeparator,
+    /// Larger than `u128::MAX` plancks.
+    Overflow,
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::Empty => write!(f, "amount is empty"),
+            AmountError::InvalidCharacter(c) => write!(f, "invalid character '{}' in amount", c),
+            AmountError::TooManyDecimals(0) => write!(f, "amount must be a whole number"),
+            AmountError::TooManyDecimals(decimals) => write!(f, "amount has more than {} decimals", decimals),
+            AmountError::MisplacedSeparator => write!(f, "thousands separators must split the amount in groups of three digits"),
+            AmountError::Overflow => write!(f, "amount is too large"),
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+
+impl Amount {
+    pub fn new(plancks: u128, decimals: u8) -> Self {
+        Amount { plancks, decimals }
+    }
+
+    pub fn plancks(&self) -> u128 {
+        self.plancks
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Parse `1,234.5` or `0.01` in tokens of `decimals` decimals. The whole part may be split
+    /// by `,`, `_` or spaces in groups of three, surrounding whitespace is ignored.
+    pub fn parse(text: &str, decimals: u8) -> Result<Amount, AmountError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(AmountError::Empty);
+        }
+        let (whole, fraction) = match text.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (text, ""),
+        };
+        if let Some(c) = fraction.chars().find(|c| !c.is_ascii_digit()) {
+            return Err(AmountError::InvalidCharacter(c));
+        }
+        let whole = whole_digits(whole)?;
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(AmountError::Empty);
+        }
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(AmountError::TooManyDecimals(decimals));
+        }
+
+        let mut plancks: u128 = 0;
+        let padding = std::iter::repeat('0').take(decimals as usize - fraction.len());
+        for digit in whole.chars().chain(fraction.chars()).chain(padding) {
+            plancks = plancks.checked_mul(10)
+                .and_then(|plancks| plancks.checked_add(digit.to_digit(10).unwrap_or_default() as u128))
+                .ok_or(AmountError::Overflow)?;
+        }
+        Ok(Amount { plancks, decimals })
+    }
+
+    /// At most `precision` decimals with trailing zeros trimmed, `1234.5`.
+    pub fn format(&self, precision: u8, rounding: Rounding) -> String {
+        let (whole, fraction) = self.rounded(precision, rounding);
+        join(whole, &fraction)
+    }
+
+    /// Like [`Amount::format`] with the whole part in groups of three, `1,234.5`.
+    pub fn format_grouped(&self, precision: u8, rounding: Rounding) -> String {
+        let (whole, fraction) = self.rounded(precision, rounding);
+        let mut grouped = String::with_capacity(whole.len() + whole.len() / 3);
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        join(grouped, &fraction)
+    }
+
+    /// Whole and fractional digits, rounded on the decimal string so any number of decimals
+    /// works without overflowing.
+    fn rounded(&self, precision: u8, rounding: Rounding) -> (String, String) {
+        let decimals = self.decimals as usize;
+        let precision = (precision as usize).min(decimals);
+        let digits = format!("{:0width$}", self.plancks, width = decimals + 1);
+        let (kept, cut) = digits.split_at(digits.len() - (decimals - precision));
+
+        let last_odd = kept.bytes().last().map(|digit| (digit - b'0') % 2 == 1).unwrap_or(false);
+        let round_up = match rounding {
+            Rounding::Down => false,
+            Rounding::Up => cut.bytes().any(|digit| digit != b'0'),
+            Rounding::HalfUp => cut.bytes().next().map(|digit| digit >= b'5').unwrap_or(false),
+            Rounding::HalfEven => match cut.as_bytes().split_first() {
+                Some((b'5', rest)) if rest.iter().all(|digit| *digit == b'0') => last_odd,
+                Some((first, _)) => *first >= b'5',
+                None => false,
+            },
+        };
+        let kept = if round_up { increment(kept) } else { kept.to_string() };
+        let (whole, fraction) = kept.split_at(kept.len() - precision);
+        (whole.trim_start_matches('0').to_string(), fraction.trim_end_matches('0').to_string())
+    }
+}
+
+/// Digits of the whole part with thousands separators checked and removed.
+fn whole_digits(whole: &str) -> Result<String, AmountError> {
+    if let Some(c) = whole.chars().find(|c| !c.is_ascii_digit() && !SEPARATORS.contains(c)) {
+        return Err(AmountError::InvalidCharacter(c));
+    }
+    let groups: Vec<&str> = whole.split(&SEPARATORS[..]).collect();
+    if groups.len() > 1 {
+        let first = groups[0].len();
+        if first == 0 || first > 3 || groups[1..].iter().any(|group| group.len() != 3) {
+            return Err(AmountError::MisplacedSeparator);
+        }
+    }
+    Ok(groups.concat())
+}
+
+/// Add one to a string of decimal digits.
+fn increment(digits: &str) -> String {
+    let mut digits = digits.as_bytes().to_vec();
+    for digit in digits.iter_mut().rev() {
+        if *digit == b'9' {
+            *digit = b'0';
+        } else {
+            *digit += 1;
+            return String::from_utf8(digits).unwrap_or_default();
+        }
+    }
+    digits.insert(0, b'1');
+    String::from_utf8(digits).unwrap_or_default()
+}
+
+fn join(whole: String, fraction: &str) -> String {
+    let whole = if whole.is_empty() { "0".to_string() } else { whole };
+    if fraction.is_empty() {
+        whole
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// Every decimal, `0.0000000001`.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(self.decimals, Rounding::Down))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Amount::parse("1.5", 10), Ok(Amount::new(15_000_000_000, 10)));
+        assert_eq!(Amount::parse(" 0.0000000001 ", 10).unwrap().plancks(), 1);
+        assert_eq!(Amount::parse(".5", 1).unwrap().plancks(), 5);
+        assert_eq!(Amount::parse("2.", 1).unwrap().plancks(), 20);
+        assert_eq!(Amount::parse("1,234,567.25", 2).unwrap().plancks(), 123_456_725);
+        assert_eq!(Amount::parse("1 234_567", 0).unwrap().plancks(), 1_234_567);
+        assert_eq!(Amount::parse("1.500", 1).unwrap().plancks(), 15);
+        assert_eq!(Amount::parse("340282366920938463463374607431768211455", 0).unwrap().plancks(), u128::MAX);
+
+        assert_eq!(Amount::parse("", 10), Err(AmountError::Empty));
+        assert_eq!(Amount::parse(".", 10), Err(AmountError::Empty));
+        assert_eq!(Amount::parse("-1", 10), Err(AmountError::InvalidCharacter('-')));
+        assert_eq!(Amount::parse("1.2.3", 10), Err(AmountError::InvalidCharacter('.')));
+        assert_eq!(Amount::parse("1e5", 10), Err(AmountError::InvalidCharacter('e')));
+        assert_eq!(Amount::parse("0.00000000001", 10), Err(AmountError::TooManyDecimals(10)));
+        assert_eq!(Amount::parse("1.5", 0).unwrap_err().to_string(), "amount must be a whole number");
+        assert_eq!(Amount::parse("1,5", 10), Err(AmountError::MisplacedSeparator));
+        assert_eq!(Amount::parse("1234,567", 10), Err(AmountError::MisplacedSeparator));
+        assert_eq!(Amount::parse(",123", 10), Err(AmountError::MisplacedSeparator));
+        assert_eq!(Amount::parse("340282366920938463463374607431768211456", 0), Err(AmountError::Overflow));
+        assert_eq!(Amount::parse("1", 39), Err(AmountError::Overflow));
+    }
+
+    #[test]
+    fn test_format() {
+        let balance = Amount::new(123_456_789_012, 10);
+        assert_eq!(balance.to_string(), "12.3456789012");
+        assert_eq!(balance.format(4, Rounding::Down), "12.3456");
+        assert_eq!(balance.format(4, Rounding::Up), "12.3457");
+        assert_eq!(balance.format(2, Rounding::HalfUp), "12.35");
+        assert_eq!(balance.format(0, Rounding::HalfUp), "12");
+        assert_eq!(Amount::new(1, 10).format(4, Rounding::Down), "0");
+        assert_eq!(Amount::new(1, 10).format(4, Rounding::Up), "0.0001");
+        assert_eq!(Amount::new(0, 10).to_string(), "0");
+        assert_eq!(Amount::new(1_234, 0).to_string(), "1234");
+
+        // halves
+        assert_eq!(Amount::new(125, 2).format(1, Rounding::HalfUp), "1.3");
+        assert_eq!(Amount::new(125, 2).format(1, Rounding::HalfEven), "1.2");
+        assert_eq!(Amount::new(135, 2).format(1, Rounding::HalfEven), "1.4");
+        assert_eq!(Amount::new(1_251, 3).format(1, Rounding::HalfEven), "1.3");
+        assert_eq!(Amount::new(124, 2).format(1, Rounding::HalfUp), "1.2");
+
+        // carries into the whole part
+        assert_eq!(Amount::new(99_999, 5).format(2, Rounding::HalfUp), "1");
+        assert_eq!(Amount::new(999_999, 5).format_grouped(0, Rounding::Up), "10");
+        assert_eq!(Amount::new(u128::MAX, 0).format(0, Rounding::Up), u128::MAX.to_string());
+
+        assert_eq!(Amount::new(123_456_789_500, 2).format_grouped(2, Rounding::Down), "1,234,567,895");
+        assert_eq!(Amount::new(100_000_050, 2).format_grouped(2, Rounding::Down), "1,000,000.5");
+        assert_eq!(Amount::parse(&Amount::new(100_000_050, 2).format_grouped(2, Rounding::Down), 2).unwrap().plancks(), 100_000_050);
+        assert_eq!(Amount::new(5, 40).format(40, Rounding::Down), "0.0000000000000000000000000000000000000005");
+    }
+}
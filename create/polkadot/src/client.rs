@@ -1,189 +1,1796 @@
-use anyhow::{anyhow, Result};
-use codec::{Decode, Encode};
-use log::{debug, info};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use codec::{Compact, Decode, Encode};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
-use sp_core::ed25519;
-use uuid::Uuid;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use sp_core::H256;
+use sp_runtime::generic::Era;
 
+use crate::address;
+use crate::decode::{self, DecodedExtrinsic};
+use crate::error::{Error, Result, RpcError};
+use crate::events::{self, EventRecord};
+use crate::history::History;
+use crate::metadata::Metadata;
+use crate::multisig::{Multisig, MultisigAction, MultisigProposal, PendingMultisig};
+use crate::payout::{self, Exposure, PagedExposureMetadata, PayoutReport, ValidatorPayouts};
+use crate::pools::{self, BondedPool, PoolAccount, PoolAction, PoolMember, PoolMembership, RewardPool};
 use crate::rpc::storage::{*};
 use crate::rpc::types::{*};
+use crate::rpc::failover::FailoverTransport;
+use crate::session::{self, RotatedKeys, SessionKey};
+use crate::rpc::transport::{RpcTransport, Subscription};
+use crate::staking::{ActiveEraInfo, Nominations, RewardDestination, SlashingSpans, StakingAction, StakingInfo, StakingLedger};
+use crate::tracker::{self, TrackedTx, TxStatus};
+use crate::transfer::{self, PreparedTransfer, TransferKind};
+use crate::utility::{self, BatchMode, PreparedCall};
+use crate::value::Value;
+
+use super::{CryptoScheme, EncodedCall, PlainTipExtrinsicParamsBuilder, signed_call};
+
+/// Blocks a transaction stays valid for, counted from the finalized head.
+pub const DEFAULT_ERA_PERIOD: u64 = 64;
+
+/// Longest period an era starts exactly at its anchor block, longer ones are quantized and
+/// would need the hash of an earlier birth block.
+pub const MAX_ERA_PERIOD: u64 = 4096;
+
+/// Runtime api id of `TransactionPaymentApi`, blake2_64 of the name.
+const TRANSACTION_PAYMENT_API: [u8; 8] = [0x37, 0xc8, 0xbb, 0x13, 0x50, 0xa9, 0xa2, 0xa8];
+
+/// Runtime api id of `TransactionPaymentCallApi`, blake2_64 of the name.
+const TRANSACTION_PAYMENT_CALL_API: [u8; 8] = [0xf3, 0xff, 0x14, 0xd5, 0xab, 0x52, 0x70, 0x59];
 
-use super::signed_extrinsic;
+/// Most pending operations read of a multisig account.
+const MAX_PENDING_MULTISIGS: u32 = 100;
 
-pub struct Client {
-    endpoint: String,
-    client: reqwest::Client,
+pub struct Client<T: RpcTransport = FailoverTransport> {
+    transport: T,
+    /// Metadata by `spec_version`, each fetched once at a block of that runtime.
+    metadata: Mutex<HashMap<u32, Arc<Metadata>>>,
+    /// By endpoint.
+    chain: Mutex<HashMap<String, ChainCache>>,
+    /// `None` builds immortal transactions.
+    era_period: Option<u64>,
 }
 
 
 impl Client {
-    pub fn new(endpoint: String) -> Self {
-        let client = reqwest::Client::new();
+    /// Client of the healthiest of `endpoints`, failing over to the others. Websocket for
+    /// `ws://` and `wss://` endpoints, http otherwise.
+    pub fn new(endpoints: Vec<String>) -> Self {
+        Client::with_transport(FailoverTransport::new(endpoints))
+    }
+
+    /// Replace the endpoints, which may serve another chain, so nothing cached of the old
+    /// ones is kept.
+    pub fn set_endpoints(&self, endpoints: Vec<String>) {
+        self.transport.set_endpoints(endpoints);
+        self.clear_cache();
+    }
+}
+
+
+impl<T: RpcTransport> Client<T> {
+    pub fn with_transport(transport: T) -> Self {
         Client {
-            endpoint,
-            client,
+            transport,
+            metadata: Mutex::new(HashMap::new()),
+            chain: Mutex::new(HashMap::new()),
+            era_period: Some(DEFAULT_ERA_PERIOD),
         }
     }
 
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Forget the metadata and chain data, the same spec version on another chain is another
+    /// runtime.
+    pub fn clear_cache(&self) {
+        self.metadata.lock().unwrap().clear();
+        self.chain.lock().unwrap().clear();
+    }
+
+    /// Periods above `MAX_ERA_PERIOD` are clamped to it.
+    pub fn with_era_period(mut self, era_period: Option<u64>) -> Self {
+        self.era_period = era_period.map(|period| period.min(MAX_ERA_PERIOD));
+        self
+    }
+
+    /// Runtime version of the best block, also refreshes the cached one.
     pub async fn runtime_version(&self) -> Result<RuntimeVersion> {
-        let result: String = self.request_no_type::<Vec<String>>("state_getRuntimeVersion".to_string(), Vec::new()).await?;
-        let response: JsonRpcResp<RuntimeVersion> = serde_json::from_str(&result)?;
-        Ok(response.result)
+        let version = self.runtime_version_at(None).await?;
+        self.cache(|cache| cache.runtime_version = Some(version.clone()));
+        Ok(version)
     }
 
+    pub async fn runtime_version_at(&self, hash: Option<String>) -> Result<RuntimeVersion> {
+        let params: Vec<String> = hash.into_iter().collect();
+        self.call("state_getRuntimeVersion", params).await
+    }
+
+
+    pub async fn transfer(&self, scheme: CryptoScheme, seed: String, from: String, to: String, amount: u128) -> Result<String> {
+        let (extrinsic, _) = self.sign_transfer(scheme, seed, &from, to, TransferKind::AllowDeath, amount).await?;
+        self.author_submit_extrinsic(extrinsic).await
+    }
+
+    /// Sign a transfer, estimate its fee and check it against the existential deposit without
+    /// submitting it. For `TransferKind::All` the amount is ignored and estimated.
+    pub async fn prepare_transfer(&self, scheme: CryptoScheme, seed: String, from: String, to: String, kind: TransferKind, amount: u128) -> Result<PreparedTransfer> {
+        let (extrinsic, context) = self.sign_transfer(scheme, seed, &from, to.clone(), kind, amount).await?;
+        let metadata = self.metadata_at(context.runtime_version.spec_version, None).await?;
+        let existential_deposit = metadata.constant_value::<u128>("Balances", "ExistentialDeposit")?;
+        let (fee, recipient) = futures::future::try_join(
+            self.estimate_fee_at(&extrinsic, &context.runtime_version),
+            self.system_account(&to),
+        ).await?;
+        let sender = &context.account.data;
+        let amount = match kind {
+            TransferKind::All { keep_alive } => transfer::transfer_all_amount(sender, fee.total_fee(), keep_alive, existential_deposit),
+            _ => amount,
+        };
+        let issues = transfer::check_transfer(kind, &to, amount, fee.total_fee(), sender, &recipient.data, existential_deposit);
+        Ok(PreparedTransfer {
+            extrinsic,
+            kind,
+            batch: None,
+            recipients: vec![(to, amount)],
+            amount,
+            fee,
+            issues,
+        })
+    }
 
-    pub async fn transfer(&self, seed: String, from: String, to: String, amount: u128) -> Result<Vec<u8>> {
+    /// Sign transfers to all `recipients` as one `Utility` batch of `mode` and check each of
+    /// them like `prepare_transfer`. `TransferKind::All` can not be batched.
+    pub async fn prepare_batch(&self, scheme: CryptoScheme, seed: String, from: String, recipients: Vec<(String, u128)>, kind: TransferKind, mode: BatchMode) -> Result<PreparedTransfer> {
+        if let TransferKind::All { .. } = kind {
+            return Err(anyhow!("{} can not be sent to several recipients", kind).into());
+        }
+        let (extrinsic, context) = self.sign(scheme, seed, &from, |metadata| {
+            let calls = recipients.iter()
+                .map(|(to, amount)| transfer::transfer_call(metadata, kind, to, *amount))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            utility::batch_call(metadata, mode, calls)
+        }).await?;
+        let metadata = self.metadata_at(context.runtime_version.spec_version, None).await?;
+        let existential_deposit = metadata.constant_value::<u128>("Balances", "ExistentialDeposit")?;
+        let addresses: Vec<String> = recipients.iter().map(|(to, _)| to.clone()).collect();
+        let (fee, accounts) = futures::future::try_join(
+            self.estimate_fee_at(&extrinsic, &context.runtime_version),
+            self.system_accounts(&addresses),
+        ).await?;
+        let amount = recipients.iter().fold(0u128, |total, (_, amount)| total.saturating_add(*amount));
+        let mut issues: Vec<_> = transfer::check_sender(kind, amount, fee.total_fee(), &context.account.data, existential_deposit).into_iter().collect();
+        // a recipient listed twice is checked once with both amounts
+        let mut received: Vec<(&str, u128, &AccountData)> = Vec::new();
+        for ((to, amount), account) in recipients.iter().zip(&accounts) {
+            match received.iter_mut().find(|(other, _, _)| other == to) {
+                Some((_, total, _)) => *total = total.saturating_add(*amount),
+                None => received.push((to, *amount, &account.data)),
+            }
+        }
+        issues.extend(received.into_iter().filter_map(|(to, amount, account)| transfer::check_recipient(to, amount, account, existential_deposit)));
+        Ok(PreparedTransfer {
+            extrinsic,
+            kind,
+            batch: Some(mode),
+            recipients,
+            amount,
+            fee,
+            issues,
+        })
+    }
+
+    /// Sign the call `build` makes with the metadata of the current runtime and estimate its fee
+    /// without submitting it.
+    pub async fn prepare_call<F: FnOnce(&Metadata) -> anyhow::Result<EncodedCall>>(&self, scheme: CryptoScheme, seed: String, from: String, build: F) -> Result<PreparedCall> {
+        let (extrinsic, context) = self.sign(scheme, seed, &from, build).await?;
+        let metadata = self.metadata_at(context.runtime_version.spec_version, None).await?;
+        let description = decode::decode_extrinsic(&metadata, &extrinsic)?.to_string();
+        let fee = self.estimate_fee_at(&extrinsic, &context.runtime_version).await?;
+        Ok(PreparedCall { extrinsic, description, fee })
+    }
+
+    /// Sign a `Staking` call of the stash `from`, see `prepare_call`.
+    pub async fn prepare_staking(&self, scheme: CryptoScheme, seed: String, from: String, action: StakingAction) -> Result<PreparedCall> {
+        let stash = from.clone();
+        self.prepare_call(scheme, seed, from, move |metadata| action.call(metadata, &stash)).await
+    }
+
+    /// Sign a `NominationPools` call of the member `from`, see `prepare_call`.
+    pub async fn prepare_pool(&self, scheme: CryptoScheme, seed: String, from: String, action: PoolAction) -> Result<PreparedCall> {
+        let member = from.clone();
+        self.prepare_call(scheme, seed, from, move |metadata| action.call(metadata, &member)).await
+    }
+
+    /// Claim the unpaid eras of validators `stashes` with a `batch_all` of `payout_stakers`
+    /// signed by `from`. A dry run signs the batch and estimates its fee without submitting it.
+    pub async fn payout_stakers(&self, scheme: CryptoScheme, seed: String, from: String, stashes: &[String], dry_run: bool) -> Result<PayoutReport> {
+        let mut report = self.unclaimed_payouts(stashes).await?;
+        if report.items.is_empty() {
+            return Ok(report);
+        }
+        let items = report.items.clone();
+        let prepared = self.prepare_call(scheme, seed, from, move |metadata| {
+            let calls = items.iter().map(|(stash, era)| payout::payout_call(metadata, stash, *era)).collect::<anyhow::Result<Vec<_>>>()?;
+            utility::batch_call(metadata, BatchMode::BatchAll, calls)
+        }).await?;
+        if !dry_run {
+            report.tx = Some(self.track(prepared.extrinsic.clone(), |_: &TrackedTx| {}).await?);
+        }
+        report.prepared = Some(prepared);
+        Ok(report)
+    }
+
+    /// Sign `Session::set_keys` registering the encoded session `keys` of the validator `from`.
+    pub async fn prepare_set_keys(&self, scheme: CryptoScheme, seed: String, from: String, keys: Vec<u8>, proof: Vec<u8>) -> Result<PreparedCall> {
+        self.prepare_call(scheme, seed, from, move |metadata| session::set_keys_call(metadata, &keys, &proof)).await
+    }
+
+    /// A proposal for `signatories` with `threshold` to dispatch the call `build` makes, allowing
+    /// it the weight the current runtime charges for the call.
+    pub async fn propose_multisig<F: FnOnce(&Metadata) -> anyhow::Result<EncodedCall>>(&self, signatories: &[String], threshold: u16, build: F) -> Result<MultisigProposal> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let call = build(&metadata)?;
+        let decoded = decode::decode_call(&metadata, &call.0)?;
+        let max_weight = self.call_weight(&call.0).await?;
         let genesis_hash = self.genesis_hash().await?;
+        Ok(MultisigProposal::new(genesis_hash, decoded.ss58_prefix, signatories, threshold, &call, decoded.to_string(), max_weight)?)
+    }
+
+    /// Sign a `Multisig` call of the signatory `from` for `proposal`, see `prepare_call`. The
+    /// timepoint is read from chain, a file written before the first approval still works.
+    pub async fn prepare_multisig(&self, scheme: CryptoScheme, seed: String, from: String, proposal: MultisigProposal, action: MultisigAction) -> Result<PreparedCall> {
+        let proposal = self.refresh_proposal(proposal).await?;
+        if action == MultisigAction::Approve && proposal.is_approved_by(&from) {
+            return Err(anyhow!("{} already approved this call", from).into());
+        }
+        let signer = from.clone();
+        self.prepare_call(scheme, seed, from, move |metadata| action.call(metadata, &proposal, &signer)).await
+    }
+
+    /// Hex encoded signed transfer and what it was signed with.
+    async fn sign_transfer(&self, scheme: CryptoScheme, seed: String, from: &str, to: String, kind: TransferKind, amount: u128) -> Result<(String, SigningContext)> {
+        self.sign(scheme, seed, from, |metadata| transfer::transfer_call(metadata, kind, &to, amount)).await
+    }
+
+    /// Hex encoded extrinsic signing the call `build` makes with the metadata of the current
+    /// runtime, and what it was signed with.
+    async fn sign<F: FnOnce(&Metadata) -> anyhow::Result<EncodedCall>>(&self, scheme: CryptoScheme, seed: String, from: &str, build: F) -> Result<(String, SigningContext)> {
+        let context = self.signing_context(from).await?;
+        let runtime_version = &context.runtime_version;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let call = build(&metadata)?;
+        let tx_data = signed_call(scheme, context.genesis_hash.clone(), seed, call, context.account.nonce, runtime_version.spec_version, runtime_version.transaction_version, context.tx_params)?;
+        let extrinsic = tx_data.replace("\"", "").trim_start_matches("0x").to_string();
+        Ok((extrinsic, context))
+    }
+
+    /// Everything needed to sign for `from`, fetched in one batch. Genesis hash, runtime version
+    /// and era anchor are cached per endpoint, the runtime version is fetched again when
+    /// `System::LastRuntimeUpgrade` shows another `spec_version`.
+    async fn signing_context(&self, from: &str) -> Result<SigningContext> {
+        let cached = self.cached();
+        let account_key = self.storage_map_key("System", "Account", from).await?;
+        let upgrade_key = hex::encode(StorageValue::new("System", "LastRuntimeUpgrade").key().0);
+
+        let mut batch = self.batch();
+        let account = batch.add::<_, String>("state_getStorage", [format!("0x{}", account_key)]);
+        let upgrade = batch.add::<_, String>("state_getStorage", [format!("0x{}", upgrade_key)]);
+        let genesis = cached.genesis_hash.is_none().then(|| batch.add::<_, String>("chain_getBlockHash", [0]));
+        let version = cached.runtime_version.is_none().then(|| batch.add::<_, RuntimeVersion>("state_getRuntimeVersion", Vec::<String>::new()));
+        let anchor = self.era_period.map(|_| (batch.add::<_, String>("chain_getFinalizedHead", Vec::<String>::new()), batch.add::<_, Header>("chain_getHeader", Vec::<String>::new())));
+        let responses = batch.send().await?;
+
+        let account = match responses.get_opt(&account)? {
+            Some(data) => AccountInfo::decode(&mut decode_hex(&data)?.as_slice())?,
+            None => AccountInfo::default(),
+        };
+        let genesis_hash = match (genesis, cached.genesis_hash) {
+            (Some(genesis), _) => responses.get(&genesis)?,
+            (None, hash) => hash.unwrap_or_default(),
+        };
+        let upgraded_to = match responses.get_opt(&upgrade)? {
+            Some(data) => Some(<(Compact<u32>, String)>::decode(&mut decode_hex(&data)?.as_slice())?.0.0),
+            None => None,
+        };
+        let runtime_version = match (version, cached.runtime_version) {
+            (Some(version), _) => responses.get(&version)?,
+            (None, Some(version)) if upgraded_to == Some(version.spec_version) => version,
+            (None, _) => {
+                info!("runtime upgraded to {:?}, refreshing runtime version", upgraded_to);
+                self.runtime_version_at(None).await?
+            }
+        };
+        let tx_params = match anchor {
+            Some((head, best)) => {
+                let period = self.era_period.unwrap_or(DEFAULT_ERA_PERIOD);
+                let best = responses.get(&best)?.block_number()?;
+                // an older finalized block stays a valid anchor, renewed once a quarter of the
+                // period has passed so the transaction keeps most of its lifetime
+                let anchor = match cached.anchor {
+                    Some((hash, number)) if (best as u64) < number as u64 + period / 4 => (hash, number),
+                    _ => {
+                        let head = responses.get(&head)?;
+                        let number = self.header(Some(head.clone())).await?.block_number()?;
+                        (head, number)
+                    }
+                };
+                let checkpoint = H256::from_str(&anchor.0).map_err(|e| anyhow!("parse hash error {:?}", e))?;
+                let params = PlainTipExtrinsicParamsBuilder::new().era(Era::mortal(period, anchor.1 as u64), checkpoint);
+                self.cache(|cache| cache.anchor = Some(anchor));
+                params
+            }
+            None => PlainTipExtrinsicParamsBuilder::default(),
+        };
+        self.cache(|cache| {
+            cache.genesis_hash = Some(genesis_hash.clone());
+            cache.runtime_version = Some(runtime_version.clone());
+        });
+        Ok(SigningContext { genesis_hash, runtime_version, account, tx_params })
+    }
+
+    fn cached(&self) -> ChainCache {
+        self.chain.lock().unwrap().get(&self.transport.endpoint()).cloned().unwrap_or_default()
+    }
+
+    fn cache<F: FnOnce(&mut ChainCache)>(&self, update: F) {
+        let mut chain = self.chain.lock().unwrap();
+        update(chain.entry(self.transport.endpoint()).or_default());
+    }
+
+    /// Refused while the prepared transfer has an issue the runtime would reject it for.
+    pub async fn submit(&self, prepared: &PreparedTransfer) -> Result<String> {
+        if let Some(issue) = prepared.issues.iter().find(|issue| issue.is_blocking()) {
+            return Err(anyhow!("transfer not submitted, {}", issue).into());
+        }
+        self.author_submit_extrinsic(prepared.extrinsic.clone()).await
+    }
+
+    /// Submit a hex encoded extrinsic and follow it until a final status, calling `on_update`
+    /// on every change. Without a websocket endpoint it is only submitted.
+    pub async fn track<F: FnMut(&TrackedTx) + Send>(&self, extrinsic: String, mut on_update: F) -> Result<TrackedTx> {
+        let mut tx = TrackedTx::new(&extrinsic)?;
+        if !self.transport.can_subscribe() {
+            self.author_submit_extrinsic(extrinsic).await?;
+            on_update(&tx);
+            return Ok(tx);
+        }
+        let mut watch = self.submit_and_watch_extrinsic(extrinsic).await?;
+        on_update(&tx);
+        while let Some(status) = watch.next().await {
+            let status = status?;
+            tx.update(&status);
+            if let TransactionStatus::InBlock(block_hash) | TransactionStatus::Finalized(block_hash) = &status {
+                if let Err(e) = self.resolve_inclusion(&mut tx, block_hash).await {
+                    warn!("resolve {} in block {} error: {}", tx.hash, block_hash, e);
+                }
+            }
+            on_update(&tx);
+            if tx.status.is_final() {
+                return Ok(tx);
+            }
+        }
+        tx.status = TxStatus::Lost;
+        on_update(&tx);
+        Ok(tx)
+    }
+
+    /// Find the extrinsic in the block and read its result from the block events.
+    async fn resolve_inclusion(&self, tx: &mut TrackedTx, block_hash: &str) -> Result<()> {
+        tx.block_hash = Some(block_hash.to_string());
+        let block = self.block(Some(block_hash.to_string())).await?;
+        let index = tracker::extrinsic_index(&block.block, &tx.hash)
+            .ok_or(anyhow!("extrinsic {} not found in block {}", tx.hash, block_hash))?;
+        tx.index = Some(index);
+        let runtime_version = self.runtime_version_at(Some(block_hash.to_string())).await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, Some(block_hash.to_string())).await?;
+        let key = hex::encode(metadata.storage_value_key("System", "Events")?.0);
+        let events = self.storage_at(key, Some(block_hash.to_string())).await?;
+        tx.outcome = tracker::extrinsic_outcome(&metadata, &events, index)?;
+        tx.items = tracker::batch_items(&metadata, &block.block.extrinsics[index as usize], &events, index)?;
+        Ok(())
+    }
+
+    /// `System::Events` of a block, decoded with the metadata of the runtime at that block.
+    pub async fn events_at(&self, block_hash: &str) -> Result<Vec<EventRecord>> {
+        let runtime_version = self.runtime_version_at(Some(block_hash.to_string())).await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, Some(block_hash.to_string())).await?;
+        let key = hex::encode(metadata.storage_value_key("System", "Events")?.0);
+        let events = self.storage_at(key, Some(block_hash.to_string())).await?;
+        Ok(events::decode_events(&metadata, &events)?)
+    }
+
+
+    /// Scan up to `max_blocks` finalized blocks from the checkpoint of `history` for transfers
+    /// of its accounts. Returns the number of blocks scanned, 0 once caught up.
+    pub async fn scan_history(&self, history: &mut History, max_blocks: u32) -> Result<u32> {
+        let finalized = self.header(Some(self.finalize_head().await?)).await?.block_number()?;
+        let start = history.checkpoint;
+        if start > finalized || max_blocks == 0 {
+            return Ok(0);
+        }
+        let end = finalized.min(start.saturating_add(max_blocks - 1));
+
+        let mut batch = self.batch();
+        let items: Vec<BatchItem<String>> = (start..=end).map(|number| batch.add("chain_getBlockHash", json!([number]))).collect();
+        let responses = batch.send().await?;
+        let hashes = items.iter().map(|item| responses.get(item)).collect::<Result<Vec<String>>>()?;
+
+        // spec versions only go up, one runtime for the whole range is the common case
+        let first = self.runtime_version_at(Some(hashes[0].clone())).await?;
+        let last = self.runtime_version_at(Some(hashes[hashes.len() - 1].clone())).await?;
+        let mut metadata = Vec::with_capacity(hashes.len());
+        if first.spec_version == last.spec_version {
+            metadata.resize(hashes.len(), self.metadata_at(last.spec_version, Some(hashes[0].clone())).await?);
+        } else {
+            for hash in &hashes {
+                let spec_version = self.runtime_version_at(Some(hash.clone())).await?.spec_version;
+                // fetched at the first block of each runtime in the range
+                metadata.push(self.metadata_at(spec_version, Some(hash.clone())).await?);
+            }
+        }
+
+        let key = format!("0x{}", hex::encode(metadata[0].storage_value_key("System", "Events")?.0));
+        let mut batch = self.batch();
+        let items: Vec<(BatchItem<SignedBlock>, BatchItem<String>)> = hashes.iter()
+            .map(|hash| (batch.add("chain_getBlock", json!([hash])), batch.add("state_getStorage", json!([key, hash]))))
+            .collect();
+        let responses = batch.send().await?;
+        for (i, (block, events)) in items.iter().enumerate() {
+            let block = responses.get(block)?.block;
+            let events = match responses.get_opt(events)? {
+                Some(events) => events::decode_events(&metadata[i], &decode_hex(&events)?)?,
+                None => Vec::new(),
+            };
+            history.add_block(&metadata[i], start + i as u32, &hashes[i], &block.extrinsics, &events)?;
+        }
+        Ok(end - start + 1)
+    }
+
+    /// Fee of a hex encoded signed extrinsic, as charged by the current runtime.
+    pub async fn estimate_fee(&self, extrinsic: &str) -> Result<FeeEstimate> {
         let runtime_version = self.runtime_version().await?;
-        let account_data = self.system_account(&from).await?;
-        let account_info:AccountInfo = Decode::decode(& mut account_data.as_slice())?;
-        let tx_data = signed_extrinsic(genesis_hash, seed, to, amount, account_info.nonce, runtime_version.spec_version, runtime_version.transaction_version, 0 as u16, 5 as u8, 0 as u8)?;
-        let data = tx_data.replace("\"", "").strip_prefix("0x").unwrap().to_string();
-        let tx_hash = self.author_submit_extrinsic(data).await?;
-        Ok(tx_hash)
+        self.estimate_fee_at(extrinsic, &runtime_version).await
+    }
+
+    async fn estimate_fee_at(&self, extrinsic: &str, runtime_version: &RuntimeVersion) -> Result<FeeEstimate> {
+        let xt = hex::decode(extrinsic.trim_start_matches("0x"))?;
+        // (uxt, len), the extrinsic encoding already carries its length prefix
+        let mut params = xt.clone();
+        (xt.len() as u32).encode_to(&mut params);
+        let api_version = runtime_version.api_version(&TRANSACTION_PAYMENT_API).unwrap_or(1);
+        let params = format!("0x{}", hex::encode(params));
+        let mut batch = self.batch();
+        let info = batch.add::<_, String>("state_call", ["TransactionPaymentApi_query_info", params.as_str()]);
+        let details = batch.add::<_, String>("state_call", ["TransactionPaymentApi_query_fee_details", params.as_str()]);
+        let responses = batch.send().await?;
+        let info = DispatchInfo::decode_versioned(&decode_hex(&responses.get(&info)?)?, api_version)?;
+        let details: FeeDetails = Decode::decode(&mut decode_hex(&responses.get(&details)?)?.as_slice())?;
+        Ok(FeeEstimate { info, details })
+    }
+
+    /// Weight the current runtime charges for dispatching an encoded call, from the
+    /// `TransactionPaymentCallApi_query_call_info` runtime api.
+    pub async fn call_weight(&self, call: &[u8]) -> Result<Weight> {
+        let runtime_version = self.runtime_version().await?;
+        let api_version = runtime_version.api_version(&TRANSACTION_PAYMENT_CALL_API)
+            .ok_or_else(|| anyhow!("the runtime has no TransactionPaymentCallApi"))?;
+        // (call, len), the call is encoded as is
+        let mut params = call.to_vec();
+        (call.len() as u32).encode_to(&mut params);
+        let data = self.state_call("TransactionPaymentCallApi_query_call_info", &params).await?;
+        Ok(DispatchInfo::decode_versioned(&data, api_version)?.weight)
+    }
+
+    /// Call a runtime api function with SCALE encoded arguments.
+    pub async fn state_call(&self, method: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let params = vec![method.to_string(), format!("0x{}", hex::encode(data))];
+        self.http_post::<Vec<String>>("state_call".to_string(), params).await
     }
 
 
+    pub async fn metadata(&self) -> Result<Metadata> {
+        self.block_metadata(None).await
+    }
+
+    /// Metadata of the runtime at a block, the latest block if `hash` is `None`.
+    pub async fn block_metadata(&self, hash: Option<String>) -> Result<Metadata> {
+        let params: Vec<String> = hash.into_iter().collect();
+        let data = self.http_post("state_getMetadata".to_string(), params).await?;
+        Ok(Metadata::decode(&data)?)
+    }
+
+    /// Metadata for `spec_version`, fetched at `block_hash` the first time, which must be a block
+    /// of that runtime. `None` is the latest block, for the current `spec_version`.
+    pub async fn metadata_at(&self, spec_version: u32, block_hash: Option<String>) -> Result<Arc<Metadata>> {
+        if let Some(metadata) = self.metadata.lock().unwrap().get(&spec_version) {
+            return Ok(metadata.clone());
+        }
+        let metadata = Arc::new(self.block_metadata(block_hash).await?);
+        self.metadata.lock().unwrap().insert(spec_version, metadata.clone());
+        Ok(metadata)
+    }
+
+
+    /// Decode a hex encoded extrinsic with the metadata of the current runtime.
+    pub async fn decode_extrinsic(&self, extrinsic: &str) -> Result<DecodedExtrinsic> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        Ok(decode::decode_extrinsic(&metadata, extrinsic)?)
+    }
+
     pub async fn storage_map_key(&self, module_prefix: &str, storage_prefix: &str, addr: &str) -> Result<String> {
-        let public = ed25519::Public::from_ss58check(addr)?;
+        let account = address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account;
         let storage_map = StorageMap::new(module_prefix, storage_prefix, StorageHasher::Blake2_128Concat);
-        let storage_key = storage_map.key(public);
+        let storage_key = storage_map.key(account);
         let key = hex::encode(storage_key.0);
         Ok(key)
     }
 
 
 
-    pub async fn author_submit_extrinsic(&self, data: String) -> Result<Vec<u8>> {
+    /// Submit a hex encoded extrinsic, returns its hash.
+    pub async fn author_submit_extrinsic(&self, data: String) -> Result<String> {
         let mut params = Vec::<String>::new();
         params.push(data);
-        let result: String = self.request_no_type::<Vec<String>>("author_submitExtrinsic".to_string(), params).await?;
-        let response: JsonRpcResp<String> = serde_json::from_str(&result)?;
-        Ok(response.result.into_bytes())
+        self.call("author_submitExtrinsic", params).await
     }
 
 
-    pub async fn system_account(&self, addr: &str) -> Result<Vec<u8>> {
+    /// Account info of `addr`, the default for accounts that never received anything.
+    pub async fn system_account(&self, addr: &str) -> Result<AccountInfo> {
         let key = self.storage_map_key("System", "Account", addr).await?;
-        let mut params = Vec::<String>::new();
-        params.push(key);
-        let account = self.http_post::<Vec<String>>("state_getStorage".to_string(), params).await?;
-        Ok(account)
+        match self.storage(key, None).await? {
+            Some(data) => Ok(AccountInfo::decode(&mut data.as_slice())?),
+            None => Ok(AccountInfo::default()),
+        }
+    }
+
+    /// Account info of every address, read in one round-trip.
+    pub async fn system_accounts(&self, addrs: &[String]) -> Result<Vec<AccountInfo>> {
+        let mut keys = Vec::new();
+        for addr in addrs {
+            keys.push(self.storage_map_key("System", "Account", addr).await?);
+        }
+        let mut batch = self.batch();
+        let items: Vec<BatchItem<String>> = keys.iter().map(|key| batch.add("state_getStorage", [format!("0x{}", key)])).collect();
+        let responses = batch.send().await?;
+        items.iter().map(|item| match responses.get_opt(item)? {
+            Some(data) => Ok(AccountInfo::decode(&mut decode_hex(&data)?.as_slice())?),
+            None => Ok(AccountInfo::default()),
+        }).collect()
+    }
+
+
+    /// Bonded, unlocking and nominated funds of `stash` and where its rewards go.
+    pub async fn staking_info(&self, stash: &str) -> Result<StakingInfo> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(stash).map_err(|e| anyhow!("invalid address {}: {}", stash, e))?.account;
+        let key = |entry| metadata.storage_map_key("Staking", entry, &account).map(|key| format!("0x{}", hex::encode(key.0)));
+
+        let mut batch = self.batch();
+        let bonded = batch.add::<_, String>("state_getStorage", [key("Bonded")?]);
+        let payee = batch.add::<_, String>("state_getStorage", [key("Payee")?]);
+        let nominators = batch.add::<_, String>("state_getStorage", [key("Nominators")?]);
+        let slashing_spans = batch.add::<_, String>("state_getStorage", [key("SlashingSpans")?]);
+        let active_era = batch.add::<_, String>("state_getStorage", [format!("0x{}", hex::encode(metadata.storage_value_key("Staking", "ActiveEra")?.0))]);
+        let responses = batch.send().await?;
+
+        let controller: Option<AccountId32> = decode_storage(&responses, &bonded)?;
+        let ledger = match &controller {
+            Some(controller) => {
+                let key = hex::encode(metadata.storage_map_key("Staking", "Ledger", controller)?.0);
+                match self.storage(key, None).await? {
+                    Some(data) => Some(StakingLedger::decode(&mut data.as_slice())?),
+                    None => None,
+                }
+            }
+            None => None,
+        };
+        Ok(StakingInfo {
+            controller,
+            ledger,
+            payee: decode_storage(&responses, &payee)?.unwrap_or_default(),
+            nominations: decode_storage(&responses, &nominators)?,
+            active_era: decode_storage::<ActiveEraInfo>(&responses, &active_era)?.map(|era| era.index),
+            bonding_duration: metadata.constant_value("Staking", "BondingDuration")?,
+            slashing_spans: decode_storage::<SlashingSpans>(&responses, &slashing_spans)?.map(|spans| spans.count()).unwrap_or(0),
+        })
+    }
+
+    /// Controller of a bonded stash, `Staking::Bonded`.
+    pub async fn staking_bonded(&self, stash: &str) -> Result<Option<AccountId32>> {
+        self.account_storage("Staking", "Bonded", stash).await
+    }
+
+    /// `Staking::Ledger` of a controller.
+    pub async fn staking_ledger(&self, controller: &str) -> Result<Option<StakingLedger>> {
+        self.account_storage("Staking", "Ledger", controller).await
+    }
+
+    /// `Staking::Payee` of a stash.
+    pub async fn staking_payee(&self, stash: &str) -> Result<RewardDestination> {
+        Ok(self.account_storage("Staking", "Payee", stash).await?.unwrap_or_default())
+    }
+
+    /// `Staking::Nominators` of a stash, `None` when it does not nominate.
+    pub async fn staking_nominators(&self, stash: &str) -> Result<Option<Nominations>> {
+        self.account_storage("Staking", "Nominators", stash).await
+    }
+
+    /// `Staking::ActiveEra`, `None` before the first era started.
+    pub async fn staking_active_era(&self) -> Result<Option<ActiveEraInfo>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let key = hex::encode(metadata.storage_value_key("Staking", "ActiveEra")?.0);
+        match self.storage(key, None).await? {
+            Some(data) => Ok(Some(ActiveEraInfo::decode(&mut data.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Eras each of `stashes` was elected in and not paid for, from `HistoryDepth` eras back up
+    /// to the era before the active one, and the calls that would claim them.
+    pub async fn unclaimed_payouts(&self, stashes: &[String]) -> Result<PayoutReport> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let active_era = self.staking_active_era().await?.ok_or_else(|| anyhow!("staking has no active era"))?.index;
+        let history_depth: u32 = match metadata.constant_value("Staking", "HistoryDepth") {
+            Ok(depth) => depth,
+            // a storage value before it became a constant
+            Err(_) => {
+                let key = hex::encode(metadata.storage_value_key("Staking", "HistoryDepth")?.0);
+                self.storage_at(key, None).await.and_then(|data| Ok(Decode::decode(&mut data.as_slice())?))?
+            }
+        };
+        let first_era = active_era.saturating_sub(history_depth);
+        // runtimes paging exposures keep an overview and the claimed pages per era
+        let paged = metadata.storage_entry("Staking", "ErasStakersOverview").is_ok();
+        let has_claimed = metadata.storage_entry("Staking", "ClaimedRewards").is_ok();
+        // and later ones drop the unpaged exposures
+        let has_exposures = metadata.storage_entry("Staking", "ErasStakers").is_ok();
+        let ledger_ty = metadata.storage_entry("Staking", "Ledger")?.value_ty;
+
+        let mut payouts = Vec::new();
+        for stash in stashes {
+            let account = address::parse_address(stash).map_err(|e| anyhow!("invalid address {}: {}", stash, e))?.account;
+            let key = |entry, era: u32| metadata.storage_double_map_key("Staking", entry, era, &account).map(|key| format!("0x{}", hex::encode(key.0)));
+            let mut batch = self.batch();
+            let bonded = batch.add::<_, String>("state_getStorage", [format!("0x{}", hex::encode(metadata.storage_map_key("Staking", "Bonded", &account)?.0))]);
+            let mut eras = Vec::new();
+            for era in first_era..active_era {
+                let overview = if paged { Some(batch.add::<_, String>("state_getStorage", [key("ErasStakersOverview", era)?])) } else { None };
+                let exposure = if has_exposures { Some(batch.add::<_, String>("state_getStorage", [key("ErasStakers", era)?])) } else { None };
+                let claimed = if has_claimed { Some(batch.add::<_, String>("state_getStorage", [key("ClaimedRewards", era)?])) } else { None };
+                eras.push((era, overview, exposure, claimed));
+            }
+            let responses = batch.send().await?;
+
+            let legacy_claimed = match decode_storage::<AccountId32>(&responses, &bonded)? {
+                Some(controller) => {
+                    let key = hex::encode(metadata.storage_map_key("Staking", "Ledger", &controller)?.0);
+                    match self.storage(key, None).await? {
+                        Some(data) => payout::legacy_claimed(&Value::decode(&metadata.types, ledger_ty, &mut data.as_slice())?),
+                        None => vec![],
+                    }
+                }
+                None => vec![],
+            };
+            let mut unclaimed = Vec::new();
+            for (era, overview, exposure, claimed) in &eras {
+                let overview = match overview {
+                    Some(item) => decode_storage::<PagedExposureMetadata>(&responses, item)?,
+                    None => None,
+                };
+                let claimed: Vec<u32> = match claimed {
+                    Some(item) => decode_storage(&responses, item)?.unwrap_or_default(),
+                    None => vec![],
+                };
+                let exposure = match exposure {
+                    Some(item) => decode_storage::<Exposure>(&responses, item)?,
+                    None => None,
+                };
+                unclaimed.extend(payout::unclaimed_era(*era, overview, exposure, &claimed, &legacy_claimed));
+            }
+            payouts.push(ValidatorPayouts { stash: stash.clone(), unclaimed });
+        }
+        Ok(PayoutReport {
+            first_era,
+            active_era,
+            items: payout::payout_items(&payouts),
+            payouts,
+            prepared: None,
+            tx: None,
+        })
+    }
+
+    /// Generate new session keys on the node at `endpoint`, which need not be one this client
+    /// queries, and decode them with the runtime of this client's chain.
+    pub async fn rotate_keys(&self, endpoint: &str) -> Result<RotatedKeys> {
+        let keys = Client::new(vec![endpoint.to_string()]).author_rotate_keys().await?;
+        let decoded = self.decode_session_keys(&keys).await?;
+        Ok(RotatedKeys { endpoint: endpoint.to_string(), keys, decoded })
+    }
+
+    /// Encoded `SessionKeys` of new keys in the node's keystore. An unsafe method, only served
+    /// by a node that allows it, like a validator's own.
+    pub async fn author_rotate_keys(&self) -> Result<Vec<u8>> {
+        let keys: String = self.call("author_rotateKeys", Vec::<String>::new()).await?;
+        decode_hex(&keys)
+    }
+
+    /// Public key and key type of each of the encoded session `keys`, with the
+    /// `SessionKeys_decode_session_keys` runtime api.
+    pub async fn decode_session_keys(&self, keys: &[u8]) -> Result<Vec<SessionKey>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let data = self.state_call("SessionKeys_decode_session_keys", &keys.to_vec().encode()).await?;
+        let key_types: Option<Vec<(Vec<u8>, [u8; 4])>> = Decode::decode(&mut data.as_slice())?;
+        let key_types = key_types.ok_or_else(|| anyhow!("the runtime can not decode these session keys"))?;
+        Ok(session::decode_keys(&metadata, keys, &key_types)?)
+    }
+
+    /// Encoded `Session::NextKeys` of a validator stash, `None` when it set no keys.
+    pub async fn session_next_keys(&self, validator: &str) -> Result<Option<Vec<u8>>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(validator).map_err(|e| anyhow!("invalid address {}: {}", validator, e))?.account;
+        let key = hex::encode(metadata.storage_map_key("Session", "NextKeys", &account)?.0);
+        self.storage(key, None).await
+    }
+
+    /// Every pool, by id up to `NominationPools::LastPoolId`, destroyed ones left out.
+    pub async fn nomination_pools(&self) -> Result<Vec<BondedPool>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let key = hex::encode(metadata.storage_value_key("NominationPools", "LastPoolId")?.0);
+        let last: u32 = match self.storage(key, None).await? {
+            Some(data) => Decode::decode(&mut data.as_slice())?,
+            None => 0,
+        };
+        self.bonded_pools(&(1..=last).collect::<Vec<_>>()).await
+    }
+
+    /// `NominationPools::BondedPools` and names of the pools `ids`, read in one round-trip.
+    /// Pools that do not exist are left out.
+    pub async fn bonded_pools(&self, ids: &[u32]) -> Result<Vec<BondedPool>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let value_ty = metadata.storage_entry("NominationPools", "BondedPools")?.value_ty;
+        let key = |entry, id: u32| metadata.storage_map_key("NominationPools", entry, id).map(|key| format!("0x{}", hex::encode(key.0)));
+
+        let mut batch = self.batch();
+        let mut items = Vec::with_capacity(ids.len());
+        for id in ids {
+            let pool = batch.add::<_, String>("state_getStorage", [key("BondedPools", *id)?]);
+            let name = batch.add::<_, String>("state_getStorage", [key("Metadata", *id)?]);
+            items.push((*id, pool, name));
+        }
+        let responses = batch.send().await?;
+        let mut pools = Vec::new();
+        for (id, pool, name) in &items {
+            let data = match responses.get_opt(pool)? {
+                Some(data) => decode_hex(&data)?,
+                None => continue,
+            };
+            let value = Value::decode(&metadata.types, value_ty, &mut data.as_slice())?;
+            let name: Vec<u8> = decode_storage(&responses, name)?.unwrap_or_default();
+            pools.push(BondedPool::from_value(*id, &name, &value)?);
+        }
+        Ok(pools)
+    }
+
+    /// `NominationPools::PoolMembers` of `addr`, `None` when it is in no pool.
+    pub async fn pool_member(&self, addr: &str) -> Result<Option<PoolMember>> {
+        self.account_storage("NominationPools", "PoolMembers", addr).await
+    }
+
+    /// The pool `addr` is a member of, its share, unbonding points and pending rewards.
+    pub async fn pool_membership(&self, addr: &str) -> Result<Option<PoolMembership>> {
+        let member = match self.pool_member(addr).await? {
+            Some(member) => member,
+            None => return Ok(None),
+        };
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account;
+        let pallet_id: [u8; 8] = metadata.constant_value("NominationPools", "PalletId")?;
+        let bonded_account = pools::pool_account(pallet_id, PoolAccount::Bonded, member.pool_id);
+
+        let mut batch = self.batch();
+        let reward_pool = batch.add::<_, String>("state_getStorage", [format!("0x{}", hex::encode(metadata.storage_map_key("NominationPools", "RewardPools", member.pool_id)?.0))]);
+        let slashing_spans = batch.add::<_, String>("state_getStorage", [format!("0x{}", hex::encode(metadata.storage_map_key("Staking", "SlashingSpans", &bonded_account)?.0))]);
+        let active_era = batch.add::<_, String>("state_getStorage", [format!("0x{}", hex::encode(metadata.storage_value_key("Staking", "ActiveEra")?.0))]);
+        let pending_rewards = batch.add::<_, String>("state_call", ["NominationPoolsApi_pending_rewards".to_string(), format!("0x{}", hex::encode(account.encode()))]);
+        let responses = batch.send().await?;
+
+        let pool = self.bonded_pools(&[member.pool_id]).await?.pop()
+            .ok_or_else(|| anyhow!("pool {} of {} not found", member.pool_id, addr))?;
+        Ok(Some(PoolMembership {
+            pool,
+            reward_pool: decode_storage::<RewardPool>(&responses, &reward_pool)?,
+            pending_rewards: Decode::decode(&mut decode_hex(&responses.get(&pending_rewards)?)?.as_slice())?,
+            active_era: decode_storage::<ActiveEraInfo>(&responses, &active_era)?.map(|era| era.index),
+            slashing_spans: decode_storage::<SlashingSpans>(&responses, &slashing_spans)?.map(|spans| spans.count()).unwrap_or(0),
+            member,
+        }))
+    }
+
+    /// `Multisig::Multisigs` of the operation of `multisig` approving the call `call_hash`, `None`
+    /// when it is not pending.
+    pub async fn pending_multisig(&self, multisig: &str, call_hash: [u8; 32]) -> Result<Option<Multisig>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(multisig).map_err(|e| anyhow!("invalid address {}: {}", multisig, e))?.account;
+        let key = hex::encode(metadata.storage_double_map_key("Multisig", "Multisigs", &account, call_hash)?.0);
+        match self.storage(key, None).await? {
+            Some(data) => Ok(Some(Multisig::decode(&mut data.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Operations of `multisig` waiting for approvals, oldest first. At most
+    /// `MAX_PENDING_MULTISIGS` are read.
+    pub async fn pending_multisigs(&self, multisig: &str) -> Result<Vec<PendingMultisig>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(multisig).map_err(|e| anyhow!("invalid address {}: {}", multisig, e))?.account;
+        let prefix = format!("0x{}", hex::encode(metadata.storage_map_key("Multisig", "Multisigs", &account)?.0));
+        let keys: Vec<String> = self.call("state_getKeysPaged", json!([prefix, MAX_PENDING_MULTISIGS])).await?;
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut batch = self.batch();
+        let items: Vec<_> = keys.iter().map(|key| batch.add::<_, String>("state_getStorage", [key.as_str()])).collect();
+        let responses = batch.send().await?;
+        let mut pending = Vec::new();
+        for (key, item) in keys.iter().zip(&items) {
+            // the call hash is the end of the key, hashed with `Blake2_128Concat`
+            let key = decode_hex(key)?;
+            let call_hash = <[u8; 32]>::try_from(&key[key.len().saturating_sub(32)..])
+                .map_err(|_| anyhow!("invalid Multisigs key 0x{}", hex::encode(&key)))?;
+            if let Some(multisig) = decode_storage::<Multisig>(&responses, item)? {
+                pending.push(PendingMultisig { call_hash, multisig });
+            }
+        }
+        pending.sort_by_key(|pending| (pending.multisig.when.height, pending.multisig.when.index));
+        Ok(pending)
+    }
+
+    /// `proposal` with its call decoded again by this chain's runtime, and the timepoint and
+    /// approvals of the operation on chain. A proposal for another chain is an error.
+    pub async fn refresh_proposal(&self, mut proposal: MultisigProposal) -> Result<MultisigProposal> {
+        proposal.check()?;
+        if proposal.genesis_hash != self.genesis_hash().await? {
+            return Err(anyhow!("the proposal is for another chain, genesis {}", proposal.genesis_hash).into());
+        }
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        proposal.description = decode::decode_call(&metadata, &proposal.call_data()?)?.to_string();
+        let pending = self.pending_multisig(&proposal.multisig, proposal.call_hash()?).await?;
+        proposal.update(pending.as_ref());
+        Ok(proposal)
+    }
+
+    /// Value of a storage map keyed by the account of `addr`, hashed as the current runtime
+    /// declares. `None` when it is not set.
+    async fn account_storage<V: Decode>(&self, pallet: &str, entry: &str, addr: &str) -> Result<Option<V>> {
+        let runtime_version = self.runtime_version().await?;
+        let metadata = self.metadata_at(runtime_version.spec_version, None).await?;
+        let account = address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account;
+        let key = hex::encode(metadata.storage_map_key(pallet, entry, &account)?.0);
+        match self.storage(key, None).await? {
+            Some(data) => Ok(Some(V::decode(&mut data.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+
+    /// Raw storage value at a block, the latest block if `hash` is `None`.
+    pub async fn storage(&self, key: String, hash: Option<String>) -> Result<Option<Vec<u8>>> {
+        let mut params = vec![format!("0x{}", key.trim_start_matches("0x"))];
+        params.extend(hash);
+        match self.call_opt::<_, String>("state_getStorage", params).await? {
+            Some(data) => Ok(Some(decode_hex(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Like `storage`, a missing value is an error.
+    pub async fn storage_at(&self, key: String, hash: Option<String>) -> Result<Vec<u8>> {
+        match self.storage(key.clone(), hash).await? {
+            Some(data) => Ok(data),
+            None => Err(Error::NullStorage(key)),
+        }
     }
 
+    pub async fn block(&self, hash: Option<String>) -> Result<SignedBlock> {
+        let params: Vec<String> = hash.into_iter().collect();
+        self.call("chain_getBlock", params).await
+    }
 
     pub async fn finalize_head(&self) -> Result<String> {
-        let result: String = self.request_no_type::<Vec<String>>("chain_getFinalizedHead".to_string(), Vec::new()).await?;
-        let response: JsonRpcResp<String> = serde_json::from_str(&result)?;
-        Ok(response.result)
+        self.call("chain_getFinalizedHead", Vec::<String>::new()).await
+    }
+
+    pub async fn header(&self, hash: Option<String>) -> Result<Header> {
+        let params: Vec<String> = hash.into_iter().collect();
+        self.call("chain_getHeader", params).await
     }
 
+    /// Hash of block 0, cached per endpoint.
     pub async fn genesis_hash(&self) -> Result<String> {
+        if let Some(hash) = self.cached().genesis_hash {
+            return Ok(hash);
+        }
         let mut params = Vec::<u64>::new();
         params.push(0);
-        let result: String = self.request_no_type::<Vec<u64>>("chain_getBlockHash".to_string(), params).await?;
-        let response: JsonRpcResp<String> = serde_json::from_str(&result)?;
-        Ok(response.result)
+        let hash: String = self.call("chain_getBlockHash", params).await?;
+        self.cache(|cache| cache.genesis_hash = Some(hash.clone()));
+        Ok(hash)
+    }
+
+
+    pub async fn system_properties(&self) -> Result<SystemProperties> {
+        self.call("system_properties", json!([])).await
+    }
+
+    pub async fn system_chain(&self) -> Result<String> {
+        self.call("system_chain", json!([])).await
+    }
+
+    pub async fn system_name(&self) -> Result<String> {
+        self.call("system_name", json!([])).await
+    }
+
+    /// Chain, node name and properties in one round-trip.
+    pub async fn chain_info(&self) -> Result<ChainInfo> {
+        let mut batch = self.batch();
+        let chain = batch.add("system_chain", json!([]));
+        let name = batch.add("system_name", json!([]));
+        let properties = batch.add("system_properties", json!([]));
+        let responses = batch.send().await?;
+        Ok(ChainInfo {
+            chain: responses.get(&chain)?,
+            name: responses.get(&name)?,
+            properties: responses.get(&properties)?,
+        })
+    }
+
+
+    pub async fn subscribe_new_heads(&self) -> Result<Subscription<Header>> {
+        self.subscribe("chain_subscribeNewHeads", json!([]), "chain_unsubscribeNewHeads", true).await
     }
 
+    pub async fn subscribe_finalized_heads(&self) -> Result<Subscription<Header>> {
+        self.subscribe("chain_subscribeFinalizedHeads", json!([]), "chain_unsubscribeFinalizedHeads", true).await
+    }
 
-    pub async fn request_no_type<T: Serialize>(&self, method: String, params: T) -> Result<String> {
-        let req: JsonRpcReq<T> = JsonRpcReq::new(method, params);
-        let body = serde_json::to_string(&req)?;
-        let res = self.client.post(&self.endpoint)
-            .header("Content-Type", "application/json")
-            .body(body)
-            .send().await?.text().await?;
+    /// Changes of the given hex encoded storage keys, starting with their current values.
+    pub async fn subscribe_storage(&self, keys: Vec<String>) -> Result<Subscription<StorageChangeSet>> {
+        let keys: Vec<String> = keys.into_iter().map(|key| format!("0x{}", key.trim_start_matches("0x"))).collect();
+        self.subscribe("state_subscribeStorage", json!([keys]), "state_unsubscribeStorage", true).await
+    }
+
+    pub async fn subscribe_system_account(&self, addr: &str) -> Result<Subscription<StorageChangeSet>> {
+        let key = self.storage_map_key("System", "Account", addr).await?;
+        self.subscribe_storage(vec![key]).await
+    }
+
+    /// Submit a hex encoded extrinsic and follow it through the pool. Not resubmitted after a
+    /// reconnect, the subscription just ends.
+    pub async fn submit_and_watch_extrinsic(&self, data: String) -> Result<Subscription<TransactionStatus>> {
+        let data = format!("0x{}", data.trim_start_matches("0x"));
+        self.subscribe("author_submitAndWatchExtrinsic", json!([data]), "author_unwatchExtrinsic", false).await
+    }
+
+    async fn subscribe<R: DeserializeOwned>(&self, method: &str, params: serde_json::Value, unsubscribe: &str, resubscribe: bool) -> Result<Subscription<R>> {
+        let subscription = self.transport.subscribe(method, params, unsubscribe, resubscribe).await?;
+        Ok(subscription.cast())
+    }
+
+
+    pub async fn request_no_type<P: Serialize>(&self, method: String, params: P) -> Result<String> {
+        let res = self.transport.request(&method, serde_json::to_value(params)?).await?;
         info!("result: {}",res);
         Ok(res)
     }
 
-    pub async fn http_post<T: Serialize>(&self, method: String, params: T) -> Result<Vec<u8>> {
-        let req: JsonRpcReq<T> = JsonRpcReq::new(method, params);
-        let body = serde_json::to_string(&req)?;
-        let result = self.client.post(&self.endpoint)
-            .header("Content-Type", "application/json")
-            .body(body)
-            .send().await?.text().await?;
-        let response: JsonRpcResp<String> = serde_json::from_str(&result)?;
-        debug!("result: {}",response.result);
-        let storage = hex::decode(response.result.strip_prefix("0x").unwrap()).unwrap();
-        Ok(storage)
-    }
-
-    pub async fn post<T: Serialize, R: Decode>(&self, method: String, params: T) -> Result<R> {
-        let req: JsonRpcReq<T> = JsonRpcReq::new(method, params);
-        let body = serde_json::to_string(&req)?;
-        let result = self.client.post(&self.endpoint)
-            .header("Content-Type", "application/json")
-            .body(body)
-            .send().await?.text().await?;
-        let response: JsonRpcResp<String> = serde_json::from_str(&result)?;
-        debug!("result: {}",response.result);
-        let storage = hex::decode(response.result.strip_prefix("0x").unwrap()).unwrap();
-        let result: R = Decode::decode(&mut storage.as_slice()).unwrap();
-        Ok(result)
+    /// The `result` of a response, `None` when it is `null`.
+    pub async fn call_opt<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<Option<R>> {
+        let result = self.request_no_type(method.to_string(), params).await?;
+        parse_response(&result)
+    }
+
+    /// Collect requests to send in one round-trip.
+    pub fn batch(&self) -> Batch<'_, T> {
+        Batch {
+            client: self,
+            requests: Vec::new(),
+        }
+    }
+
+    pub async fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: P) -> Result<R> {
+        self.call_opt(method, params).await?.ok_or_else(|| Error::NullResult(method.to_string()))
+    }
+
+    /// Hex encoded bytes returned by `method`.
+    pub async fn http_post<P: Serialize>(&self, method: String, params: P) -> Result<Vec<u8>> {
+        let result: String = self.call(&method, params).await?;
+        debug!("result: {}", result);
+        decode_hex(&result)
+    }
+
+    pub async fn post<P: Serialize, R: Decode>(&self, method: String, params: P) -> Result<R> {
+        let data = self.http_post(method, params).await?;
+        Ok(R::decode(&mut data.as_slice())?)
     }
 }
 
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-struct JsonRpcReq<T> {
-    id: u64,
-    #[serde(rename = "jsonrpc")]
-    json_rpc: String,
-    method: String,
-    params: T,
+/// Requests sent together as one JSON-RPC batch, built with `Client::batch`.
+pub struct Batch<'a, T: RpcTransport> {
+    client: &'a Client<T>,
+    requests: Vec<(String, Result<serde_json::Value>)>,
 }
 
+/// A request in a `Batch`, typed by its result.
+pub struct BatchItem<R> {
+    index: usize,
+    _marker: PhantomData<fn() -> R>,
+}
 
-impl<T> JsonRpcReq<T> {
-    fn new(method: String, params: T) -> Self {
-        let id = Uuid::new_v4();
-        JsonRpcReq {
-            id: id.to_u128_le() as u64,
-            json_rpc: "2.0".to_string(),
-            method,
-            params,
+impl<'a, T: RpcTransport> Batch<'a, T> {
+    pub fn add<P: Serialize, R: DeserializeOwned>(&mut self, method: &str, params: P) -> BatchItem<R> {
+        self.requests.push((method.to_string(), serde_json::to_value(params).map_err(Error::from)));
+        BatchItem { index: self.requests.len() - 1, _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub async fn send(self) -> Result<BatchResponses> {
+        let mut requests = Vec::with_capacity(self.requests.len());
+        let mut methods = Vec::with_capacity(self.requests.len());
+        for (method, params) in self.requests {
+            requests.push((method.clone(), params?));
+            methods.push(method);
         }
+        let responses = self.client.transport.batch(requests).await?;
+        debug!("batch results: {:?}", responses);
+        Ok(BatchResponses { methods, responses })
+    }
+}
+
+/// Raw responses of a `Batch`, each read with the item `add` returned.
+pub struct BatchResponses {
+    methods: Vec<String>,
+    responses: Vec<String>,
+}
+
+impl BatchResponses {
+    /// `None` when the result is `null`, an error object is returned as `Error::Rpc`.
+    pub fn get_opt<R: DeserializeOwned>(&self, item: &BatchItem<R>) -> Result<Option<R>> {
+        let response = self.responses.get(item.index).ok_or_else(|| anyhow!("no response for batch item {}", item.index))?;
+        parse_response(response)
+    }
+
+    pub fn get<R: DeserializeOwned>(&self, item: &BatchItem<R>) -> Result<R> {
+        self.get_opt(item)?.ok_or_else(|| Error::NullResult(self.methods[item.index].clone()))
+    }
+}
+
+
+/// Cached per endpoint, see `Client::signing_context`.
+#[derive(Clone, Default)]
+struct ChainCache {
+    genesis_hash: Option<String>,
+    runtime_version: Option<RuntimeVersion>,
+    /// Finalized block mortal eras start at, hash and number.
+    anchor: Option<(String, u32)>,
+}
+
+struct SigningContext {
+    genesis_hash: String,
+    runtime_version: RuntimeVersion,
+    account: AccountInfo,
+    tx_params: PlainTipExtrinsicParamsBuilder,
+}
+
+
+/// The `result` of a raw response, its error object as `Error::Rpc`.
+pub(crate) fn parse_response<R: DeserializeOwned>(response: &str) -> Result<Option<R>> {
+    let response: JsonRpcResp<R> = serde_json::from_str(response)?;
+    if let Some(error) = response.error {
+        return Err(Error::Rpc(error));
+    }
+    Ok(response.result)
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>> {
+    Ok(hex::decode(data.trim_start_matches("0x"))?)
+}
+
+/// A `state_getStorage` result of a batch, `None` when the value is not set.
+fn decode_storage<V: Decode>(responses: &BatchResponses, item: &BatchItem<String>) -> Result<Option<V>> {
+    match responses.get_opt(item)? {
+        Some(data) => Ok(Some(V::decode(&mut decode_hex(&data)?.as_slice())?)),
+        None => Ok(None),
     }
 }
 
 
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 struct JsonRpcResp<T> {
-    id: u64,
+    /// `null` when the node could not read the request id.
+    #[serde(default)]
+    id: Option<u64>,
     #[serde(rename = "jsonrpc")]
     json_rpc: String,
-    result: T,
-
+    #[serde(default = "Option::default")]
+    result: Option<T>,
+    #[serde(default)]
+    error: Option<RpcError>,
 }
 
-//const ENDPOINT: &str = "https://rpc.polkadot.io";
-const ENDPOINT: &str = "http://127.0.0.1:9933";
-
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
     use std::f32::consts::E;
     use std::future::Future;
 
+    use codec::Compact;
+
+    use crate::{GenericAddress, PlainTip, SubstrateDefaultSignedExtra, UncheckedExtrinsicV4};
+    use crate::rpc::mock::MockNode;
+    use crate::rpc::transport::JsonRpcReq;
+
     use super::*;
 
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    fn mock_client() -> Client<MockNode> {
+        Client::with_transport(MockNode::new().with_metadata(&crate::metadata::fixture::metadata()))
+    }
+
+    #[tokio::test]
+    async fn test_mock_transfer() {
+        let client = mock_client();
+        let mut alice = AccountInfo::default();
+        alice.nonce = 7;
+        alice.data.free = 100_000_000_000;
+        client.transport().set_account(ALICE, &alice).unwrap();
+        let mut bob = AccountInfo::default();
+        bob.data.free = 10_000_000_000;
+        client.transport().set_account(BOB, &bob).unwrap();
+
+        let prepared = client.prepare_transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), TransferKind::AllowDeath, 12_345).await.unwrap();
+        assert_eq!(prepared.fee.partial_fee(), crate::rpc::mock::PARTIAL_FEE);
+        assert_eq!(prepared.issues, vec![]);
+        assert!(client.transport().submitted().is_empty());
+
+        let hash = client.submit(&prepared).await.unwrap();
+        let submitted = client.transport().submitted();
+        assert_eq!(submitted, vec![prepared.extrinsic.clone()]);
+        assert_eq!(hash, crate::tracker::extrinsic_hash(&submitted[0]).unwrap());
+
+        let raw = hex::decode(&submitted[0]).unwrap();
+        let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, Compact<u128>), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+        let (signer, _, extra) = xt.signature.unwrap();
+        let alice_id = AccountId32::from_ss58check(ALICE).unwrap();
+        let bob_id = AccountId32::from_ss58check(BOB).unwrap();
+        assert_eq!(signer, GenericAddress::Id(alice_id));
+        assert_eq!(xt.function, ([5, 0], GenericAddress::Id(bob_id), Compact(12_345)));
+        assert_eq!(extra.nonce, 7);
+        assert_eq!(extra.era, Era::mortal(DEFAULT_ERA_PERIOD, crate::rpc::mock::FINALIZED_NUMBER as u64));
+
+        let decoded = client.decode_extrinsic(&submitted[0]).await.unwrap();
+        assert_eq!(decoded.call, "transfer");
+        assert_eq!(decoded.signed.unwrap().nonce, 7);
+    }
+
+    #[tokio::test]
+    async fn test_mock_transfer_checks() {
+        use crate::metadata::fixture::EXISTENTIAL_DEPOSIT;
+        use crate::transfer::TransferIssue;
+
+        let client = mock_client();
+        let mut alice = AccountInfo::default();
+        alice.data.free = EXISTENTIAL_DEPOSIT * 3;
+        client.transport().set_account(ALICE, &alice).unwrap();
+        let prepare = |kind, amount| client.prepare_transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), kind, amount);
+
+        // dust to an empty account is rejected by the runtime
+        let prepared = prepare(TransferKind::KeepAlive, 1).await.unwrap();
+        assert_eq!(prepared.issues, vec![TransferIssue::RecipientBelowDeposit { recipient: BOB.to_string(), balance: 1, existential_deposit: EXISTENTIAL_DEPOSIT }]);
+        assert!(client.submit(&prepared).await.unwrap_err().to_string().contains("below the existential deposit"));
+        assert!(client.transport().submitted().is_empty());
+
+        let prepared = prepare(TransferKind::KeepAlive, EXISTENTIAL_DEPOSIT * 5 / 2).await.unwrap();
+        assert!(matches!(prepared.issues[..], [TransferIssue::KeepAliveFails { .. }]));
+        let prepared = prepare(TransferKind::AllowDeath, EXISTENTIAL_DEPOSIT * 5 / 2).await.unwrap();
+        assert!(matches!(prepared.issues[..], [TransferIssue::SenderReaped { .. }]));
+        assert!(!prepared.is_blocked());
+        client.submit(&prepared).await.unwrap();
+
+        let prepared = prepare(TransferKind::All { keep_alive: true }, 0).await.unwrap();
+        assert_eq!(prepared.amount, EXISTENTIAL_DEPOSIT * 2 - prepared.fee.total_fee());
+        assert_eq!(prepared.issues, vec![]);
+        let raw = hex::decode(&prepared.extrinsic).unwrap();
+        let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, bool), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+        assert_eq!((xt.function.0, xt.function.2), ([5, 4], true));
+    }
+
+    #[tokio::test]
+    async fn test_mock_batch() {
+        use crate::metadata::fixture::EXISTENTIAL_DEPOSIT;
+        use crate::transfer::TransferIssue;
+
+        let client = mock_client();
+        let mut alice = AccountInfo::default();
+        alice.data.free = EXISTENTIAL_DEPOSIT * 10;
+        client.transport().set_account(ALICE, &alice).unwrap();
+        let charlie = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+        let recipients = vec![(BOB.to_string(), EXISTENTIAL_DEPOSIT / 2), (charlie.to_string(), EXISTENTIAL_DEPOSIT), (BOB.to_string(), EXISTENTIAL_DEPOSIT / 4)];
+
+        let prepared = client.prepare_batch(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), recipients.clone(), TransferKind::KeepAlive, BatchMode::BatchAll).await.unwrap();
+        assert_eq!(prepared.amount, EXISTENTIAL_DEPOSIT * 7 / 4);
+        assert_eq!(prepared.recipients, recipients);
+        // bob is checked once with both amounts
+        assert_eq!(prepared.issues, vec![TransferIssue::RecipientBelowDeposit { recipient: BOB.to_string(), balance: EXISTENTIAL_DEPOSIT * 3 / 4, existential_deposit: EXISTENTIAL_DEPOSIT }]);
+        let decoded = client.decode_extrinsic(&prepared.extrinsic).await.unwrap();
+        assert_eq!((decoded.pallet.as_str(), decoded.call.as_str()), ("Utility", "batch_all"));
+        assert_eq!(utility::batch_len(&decoded.pallet, &decoded.call, decoded.arg("calls")), Some(3));
+
+        let all = client.prepare_batch(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), recipients, TransferKind::All { keep_alive: true }, BatchMode::Batch).await;
+        assert!(all.unwrap_err().to_string().contains("can not be sent to several recipients"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_staking() {
+        use crate::metadata::fixture::BONDING_DURATION;
+        use crate::staking::UnlockChunk;
+
+        let client = mock_client();
+        let metadata = client.metadata().await.unwrap();
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        let key = |entry| hex::encode(metadata.storage_map_key("Staking", entry, &alice).unwrap().0);
+        assert_eq!(client.staking_info(ALICE).await.unwrap(), StakingInfo { bonding_duration: BONDING_DURATION, ..Default::default() });
+
+        let ledger = StakingLedger {
+            stash: alice.clone(),
+            total: 300,
+            active: 100,
+            unlocking: vec![UnlockChunk { value: 50, era: 9 }, UnlockChunk { value: 150, era: 30 }],
+            claimed_rewards: vec![8],
+        };
+        client.transport().set_storage(&key("Bonded"), Some(&alice.encode()));
+        client.transport().set_storage(&key("Ledger"), Some(&ledger.encode()));
+        client.transport().set_storage(&key("Payee"), Some(&RewardDestination::Stash.encode()));
+        client.transport().set_storage(&hex::encode(metadata.storage_value_key("Staking", "ActiveEra").unwrap().0), Some(&ActiveEraInfo { index: 10, start: None }.encode()));
+        let info = client.staking_info(ALICE).await.unwrap();
+        assert_eq!(info.controller, Some(alice.clone()));
+        assert_eq!((info.bonded(), info.redeemable(), info.unlocking()), (100, 50, vec![(30, 150)]));
+        assert_eq!(info.payee, RewardDestination::Stash);
+        assert_eq!(info.nominations, None);
+        assert_eq!(info.slashing_spans, 0);
+        assert_eq!(client.staking_ledger(ALICE).await.unwrap(), Some(ledger));
+        assert_eq!(client.staking_bonded(ALICE).await.unwrap(), Some(alice));
+        assert_eq!(client.staking_payee(BOB).await.unwrap(), RewardDestination::Staked);
+        assert_eq!(client.staking_active_era().await.unwrap().map(|era| era.index), Some(10));
+
+        let prepared = client.prepare_staking(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), StakingAction::Unbond { value: 40 }).await.unwrap();
+        assert!(prepared.description.starts_with("Staking.unbond(value: 40)"), "{}", prepared.description);
+        assert_eq!(prepared.fee.partial_fee(), crate::rpc::mock::PARTIAL_FEE);
+        assert!(client.transport().submitted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_payouts() {
+        use crate::staking::UnlockChunk;
+
+        let client = mock_client();
+        let metadata = client.metadata().await.unwrap();
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        let stashes = vec![ALICE.to_string(), BOB.to_string()];
+        assert!(client.unclaimed_payouts(&stashes).await.unwrap_err().to_string().contains("no active era"));
+
+        let key = |entry, era: u32| hex::encode(metadata.storage_double_map_key("Staking", entry, era, &alice).unwrap().0);
+        let exposure = Exposure { total: 100, own: 100, others: vec![] };
+        client.transport().set_storage(&hex::encode(metadata.storage_value_key("Staking", "ActiveEra").unwrap().0), Some(&ActiveEraInfo { index: 10, start: None }.encode()));
+        client.transport().set_storage(&hex::encode(metadata.storage_map_key("Staking", "Bonded", &alice).unwrap().0), Some(&alice.encode()));
+        let ledger = StakingLedger { stash: alice.clone(), total: 100, active: 100, unlocking: Vec::<UnlockChunk>::new(), claimed_rewards: vec![8] };
+        client.transport().set_storage(&hex::encode(metadata.storage_map_key("Staking", "Ledger", &alice).unwrap().0), Some(&ledger.encode()));
+        // 6 was claimed, 7 has one of two pages claimed, 8 was claimed before paging
+        for era in [6, 8, 9] {
+            client.transport().set_storage(&key("ErasStakers", era), Some(&exposure.encode()));
+        }
+        client.transport().set_storage(&key("ClaimedRewards", 6), Some(&vec![0u32].encode()));
+        client.transport().set_storage(&key("ErasStakersOverview", 7), Some(&PagedExposureMetadata { total: 50, own: 10, nominator_count: 600, page_count: 2 }.encode()));
+        client.transport().set_storage(&key("ClaimedRewards", 7), Some(&vec![0u32].encode()));
+        // the active era is not paid yet
+        client.transport().set_storage(&key("ErasStakers", 10), Some(&exposure.encode()));
+
+        let report = client.unclaimed_payouts(&stashes).await.unwrap();
+        assert_eq!((report.first_era, report.active_era), (0, 10));
+        assert_eq!(report.payouts[0].unclaimed.iter().map(|unclaimed| (unclaimed.era, unclaimed.pages)).collect::<Vec<_>>(), vec![(7, 1), (9, 1)]);
+        assert_eq!(report.payouts[1].unclaimed, vec![]);
+        assert_eq!(report.items, vec![(ALICE.to_string(), 7), (ALICE.to_string(), 9)]);
+
+        let dry_run = client.payout_stakers(CryptoScheme::Sr25519, "//Bob".to_string(), BOB.to_string(), &stashes, true).await.unwrap();
+        assert!(dry_run.is_dry_run());
+        let decoded = client.decode_extrinsic(&dry_run.prepared.unwrap().extrinsic).await.unwrap();
+        assert_eq!((decoded.pallet.as_str(), decoded.call.as_str()), ("Utility", "batch_all"));
+        assert_eq!(utility::batch_len(&decoded.pallet, &decoded.call, decoded.arg("calls")), Some(2));
+        assert!(client.transport().submitted().is_empty());
+
+        let report = client.payout_stakers(CryptoScheme::Sr25519, "//Bob".to_string(), BOB.to_string(), &stashes, false).await.unwrap();
+        assert_eq!(client.transport().submitted().len(), 1);
+        assert_eq!(report.tx.map(|tx| tx.status), Some(TxStatus::Submitted));
+    }
+
+    #[tokio::test]
+    async fn test_mock_session_keys() {
+        use crate::metadata::fixture::SessionKeys;
+
+        let client = mock_client();
+        let metadata = client.metadata().await.unwrap();
+        let keys = SessionKeys { grandpa: [1; 32], babe: [2; 32] }.encode();
+        let operator = mock_client();
+        operator.transport().set_result("author_rotateKeys", json!(format!("0x{}", hex::encode(&keys))));
+        assert_eq!(operator.author_rotate_keys().await.unwrap(), keys);
+
+        assert!(client.decode_session_keys(&keys).await.is_err());
+        client.transport().set_state_call("SessionKeys_decode_session_keys", &Some(vec![([1u8; 32].to_vec(), *b"gran"), ([2u8; 32].to_vec(), *b"babe")]).encode());
+        let decoded = client.decode_session_keys(&keys).await.unwrap();
+        assert_eq!(decoded.iter().map(|key| (key.name.as_str(), key.key_type.as_str())).collect::<Vec<_>>(), vec![("grandpa", "gran"), ("babe", "babe")]);
+        client.transport().set_state_call("SessionKeys_decode_session_keys", &None::<Vec<(Vec<u8>, [u8; 4])>>.encode());
+        assert!(client.decode_session_keys(&keys).await.unwrap_err().to_string().contains("can not decode"));
+
+        let prepared = client.prepare_set_keys(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), keys.clone(), vec![]).await.unwrap();
+        assert!(prepared.description.starts_with("Session.set_keys("), "{}", prepared.description);
+
+        assert_eq!(client.session_next_keys(ALICE).await.unwrap(), None);
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        client.transport().set_storage(&hex::encode(metadata.storage_map_key("Session", "NextKeys", &alice).unwrap().0), Some(&keys));
+        assert_eq!(client.session_next_keys(ALICE).await.unwrap(), Some(keys));
+    }
+
+    #[tokio::test]
+    async fn test_mock_multisig() {
+        use crate::multisig::{self, Timepoint};
+
+        let client = mock_client();
+        let metadata = client.metadata().await.unwrap();
+        let signatories = vec![ALICE.to_string(), BOB.to_string()];
+        let proposal = client.propose_multisig(&signatories, 2, |metadata| transfer::transfer_call(metadata, TransferKind::KeepAlive, BOB, 50)).await.unwrap();
+        assert!(proposal.description.starts_with("Balances.transfer_keep_alive("), "{}", proposal.description);
+        assert_eq!(proposal.max_weight, Weight { ref_time: 190_949_000, proof_size: 3_593 });
+        assert_eq!(proposal.genesis_hash, crate::rpc::mock::GENESIS_HASH);
+        let multisig = AccountId32::from_ss58check(&proposal.multisig).unwrap();
+        assert_eq!(multisig, multisig::multi_account_id(&multisig::signatory_accounts(&signatories).unwrap(), 2));
+        assert!(client.propose_multisig(&signatories, 3, |metadata| transfer::transfer_call(metadata, TransferKind::KeepAlive, BOB, 50)).await.is_err());
+
+        // the first approval carries no timepoint
+        let prepared = client.prepare_multisig(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), proposal.clone(), MultisigAction::Approve).await.unwrap();
+        assert!(prepared.description.starts_with("Multisig.approve_as_multi(threshold: 2"), "{}", prepared.description);
+        let decoded = client.decode_extrinsic(&prepared.extrinsic).await.unwrap();
+        assert_eq!(decoded.arg("maybe_timepoint").and_then(Value::variant_name), Some("None"));
+        assert!(client.prepare_multisig(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), proposal.clone(), MultisigAction::Cancel).await.is_err());
+
+        assert_eq!(client.pending_multisigs(&proposal.multisig).await.unwrap(), vec![]);
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        let pending = Multisig { when: Timepoint { height: 12, index: 2 }, deposit: 100, depositor: alice.clone(), approvals: vec![alice] };
+        let call_hash = proposal.call_hash().unwrap();
+        client.transport().set_storage(&hex::encode(metadata.storage_double_map_key("Multisig", "Multisigs", &multisig, call_hash).unwrap().0), Some(&pending.encode()));
+        assert_eq!(client.pending_multisig(&proposal.multisig, call_hash).await.unwrap(), Some(pending.clone()));
+        assert_eq!(client.pending_multisigs(&proposal.multisig).await.unwrap(), vec![PendingMultisig { call_hash, multisig: pending.clone() }]);
+        assert_eq!(client.pending_multisigs(BOB).await.unwrap(), vec![]);
+
+        let mut edited = proposal.clone();
+        edited.description = "System.remark()".to_string();
+        let refreshed = client.refresh_proposal(edited).await.unwrap();
+        assert_eq!((refreshed.description.as_str(), refreshed.timepoint, refreshed.approvals_needed()), (proposal.description.as_str(), Some(pending.when), 1));
+        assert!(client.prepare_multisig(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), proposal.clone(), MultisigAction::Approve).await.unwrap_err().to_string().contains("already approved"));
+
+        // the final approval executes the call at the first approval's timepoint
+        let prepared = client.prepare_multisig(CryptoScheme::Sr25519, "//Bob".to_string(), BOB.to_string(), proposal.clone(), MultisigAction::Execute).await.unwrap();
+        let decoded = client.decode_extrinsic(&prepared.extrinsic).await.unwrap();
+        assert_eq!(decoded.call, "as_multi");
+        assert_eq!(decoded.arg("maybe_timepoint").and_then(|timepoint| timepoint.at(0)).and_then(|timepoint| timepoint.field("height")).and_then(Value::as_u128), Some(12));
+
+        let mut other_chain = proposal.clone();
+        other_chain.genesis_hash = format!("0x{}", hex::encode([0u8; 32]));
+        assert!(client.refresh_proposal(other_chain).await.unwrap_err().to_string().contains("another chain"));
+        assert!(client.transport().submitted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_pools() {
+        use crate::metadata::fixture;
+        use crate::pools::PoolState;
+
+        let client = mock_client();
+        let metadata = client.metadata().await.unwrap();
+        let alice = AccountId32::from_ss58check(ALICE).unwrap();
+        let key = |entry, id: u32| hex::encode(metadata.storage_map_key("NominationPools", entry, id).unwrap().0);
+        assert_eq!(client.nomination_pools().await.unwrap(), vec![]);
+        assert_eq!(client.pool_membership(ALICE).await.unwrap(), None);
+
+        // pool 2 was destroyed
+        client.transport().set_storage(&hex::encode(metadata.storage_value_key("NominationPools", "LastPoolId").unwrap().0), Some(&3u32.encode()));
+        client.transport().set_storage(&key("BondedPools", 1), Some(&fixture::bonded_pool(alice.clone(), 500).encode()));
+        client.transport().set_storage(&key("BondedPools", 3), Some(&fixture::bonded_pool(alice.clone(), 80).encode()));
+        client.transport().set_storage(&key("Metadata", 3), Some(&b"third".to_vec().encode()));
+        let pools = client.nomination_pools().await.unwrap();
+        assert_eq!(pools.iter().map(|pool| (pool.id, pool.name.as_str(), pool.points)).collect::<Vec<_>>(), vec![(1, "", 500), (3, "third", 80)]);
+        assert_eq!(pools[0].state, PoolState::Open);
+
+        let member = PoolMember { pool_id: 3, points: 60, last_recorded_reward_counter: 0, unbonding_eras: [(12, 20)].into() };
+        client.transport().set_storage(&hex::encode(metadata.storage_map_key("NominationPools", "PoolMembers", &alice).unwrap().0), Some(&member.encode()));
+        client.transport().set_storage(&hex::encode(metadata.storage_value_key("Staking", "ActiveEra").unwrap().0), Some(&ActiveEraInfo { index: 12, start: None }.encode()));
+        client.transport().set_state_call("NominationPoolsApi_pending_rewards", &7u128.encode());
+        let membership = client.pool_membership(ALICE).await.unwrap().unwrap();
+        assert_eq!(membership.member, member);
+        assert_eq!((membership.pool.id, membership.pending_rewards, membership.redeemable()), (3, 7, 20));
+        assert_eq!((membership.reward_pool, membership.slashing_spans), (None, 0));
+
+        let prepared = client.prepare_pool(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), PoolAction::Join { amount: 40, pool_id: 3 }).await.unwrap();
+        assert!(prepared.description.starts_with("NominationPools.join(amount: 40, pool_id: 3)"), "{}", prepared.description);
+    }
+
+    #[tokio::test]
+    async fn test_long_era_period() {
+        let client = Client::with_transport(MockNode::new().with_metadata(&crate::metadata::fixture::metadata())).with_era_period(Some(8192));
+        let mut alice = AccountInfo::default();
+        alice.data.free = 100_000_000_000;
+        client.transport().set_account(ALICE, &alice).unwrap();
+        let prepared = client.prepare_transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), TransferKind::AllowDeath, 12_345).await.unwrap();
+
+        let raw = hex::decode(prepared.extrinsic.trim_start_matches("0x")).unwrap();
+        let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, Compact<u128>), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+        let (_, _, extra) = xt.signature.unwrap();
+        // signed with the anchor hash as checkpoint, so the era must be born at the anchor
+        let anchor = crate::rpc::mock::FINALIZED_NUMBER as u64;
+        assert_eq!(extra.era, Era::mortal(MAX_ERA_PERIOD, anchor));
+        assert_eq!(extra.era.birth(anchor), anchor);
+    }
+
+    #[tokio::test]
+    async fn test_clear_cache() {
+        let client = mock_client();
+        let metadata = client.metadata_at(9370, None).await.unwrap();
+        assert_eq!(metadata.call_index("Balances", "transfer_keep_alive").unwrap()[0], 5);
+        // another chain with the same spec version
+        client.transport().set_result("state_getMetadata", serde_json::json!(format!("0x{}", hex::encode(crate::metadata::fixture::metadata_with(10)))));
+        assert_eq!(client.metadata_at(9370, None).await.unwrap().call_index("Balances", "transfer_keep_alive").unwrap()[0], 5);
+        client.clear_cache();
+        assert_eq!(client.metadata_at(9370, None).await.unwrap().call_index("Balances", "transfer_keep_alive").unwrap()[0], 10);
+    }
+
+    #[tokio::test]
+    async fn test_mock_events() {
+        let client = mock_client();
+        let block_hash = crate::rpc::mock::FINALIZED_HEAD;
+        let metadata = client.metadata().await.unwrap();
+        let key = hex::encode(metadata.storage_value_key("System", "Events").unwrap().0);
+        client.transport().set_storage(&key, Some(&crate::metadata::fixture::events()));
+        let events = client.events_at(block_hash).await.unwrap();
+        assert_eq!(events.iter().filter_map(EventRecord::transfer).count(), 1);
+        let failed = events.iter().find_map(|e| e.extrinsic_failed(&metadata)).unwrap();
+        assert_eq!(failed.error.to_string(), "Balances.InsufficientBalance");
+        let requests = client.transport().requests();
+        assert!(requests.contains(&("state_getStorage".to_string(), serde_json::json!([format!("0x{}", key), block_hash]))));
+    }
+
+    #[tokio::test]
+    async fn test_mock_events_before_upgrade() {
+        use crate::metadata::fixture;
+
+        // Balances moved from index 5 to 10 in the current runtime
+        let client = Client::with_transport(MockNode::new().with_metadata(&fixture::metadata_with(10)));
+        let node = client.transport();
+        let block_hash = format!("0x{}", "11".repeat(32));
+        node.set_result_for("state_getRuntimeVersion", serde_json::json!([block_hash]), serde_json::json!({
+            "specVersion": 9360, "authoringVersion": 0, "implName": "parity-polkadot", "specName": "polkadot", "stateVersion": 0, "transactionVersion": 20,
+        }));
+        node.set_result_for("state_getMetadata", serde_json::json!([block_hash]), serde_json::json!(format!("0x{}", hex::encode(fixture::metadata()))));
+        let current = client.metadata_at(9370, None).await.unwrap();
+        let key = hex::encode(current.storage_value_key("System", "Events").unwrap().0);
+        node.set_storage(&key, Some(&fixture::events()));
+
+        let events = client.events_at(&block_hash).await.unwrap();
+        assert_eq!(events.iter().filter_map(EventRecord::transfer).count(), 1);
+        let old = client.metadata_at(9360, None).await.unwrap();
+        assert_eq!(old.call_index("Balances", "transfer_keep_alive").unwrap()[0], 5);
+        assert_eq!(current.call_index("Balances", "transfer_keep_alive").unwrap()[0], 10);
+        let failed = events.iter().find_map(|e| e.extrinsic_failed(&old)).unwrap();
+        assert_eq!(failed.error.to_string(), "Balances.InsufficientBalance");
+        let requests = node.requests();
+        assert!(requests.contains(&("state_getMetadata".to_string(), serde_json::json!([block_hash]))));
+    }
+
+    #[tokio::test]
+    async fn test_scan_history() {
+        use crate::history::{Direction, History};
+
+        let client = mock_client();
+        let node = client.transport();
+        let metadata = client.metadata().await.unwrap();
+        let key = format!("0x{}", hex::encode(metadata.storage_value_key("System", "Events").unwrap().0));
+        for number in 99..=100u32 {
+            let hash = format!("0x{:064x}", number);
+            node.set_result_for("chain_getBlockHash", serde_json::json!([number]), serde_json::json!(hash));
+            node.set_result_for("chain_getBlock", serde_json::json!([hash]), serde_json::json!({"block": {
+                "header": {"parentHash": "0x00", "number": format!("0x{:x}", number), "stateRoot": "0x00", "extrinsicsRoot": "0x00"},
+                "extrinsics": [],
+            }}));
+        }
+        // only block 100 has the fixture transfer
+        let events = format!("0x{}", hex::encode(crate::metadata::fixture::events()));
+        node.set_result_for("state_getStorage", serde_json::json!([key, crate::rpc::mock::FINALIZED_HEAD]), serde_json::json!(events));
+
+        let from = AccountId32::new([1u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        let mut history = History::new(vec![from], 99);
+        assert_eq!(client.scan_history(&mut history, 10).await.unwrap(), 2);
+        assert_eq!(history.checkpoint, 101);
+        assert_eq!(history.entries.len(), 1);
+        assert_eq!((history.entries[0].block_number, history.entries[0].direction), (100, Direction::Out));
+        assert_eq!(history.entries[0].block_hash, crate::rpc::mock::FINALIZED_HEAD);
+        // caught up with the finalized head
+        assert_eq!(client.scan_history(&mut history, 10).await.unwrap(), 0);
+        assert_eq!(history.entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_history_across_upgrade() {
+        use crate::history::{Direction, History};
+        use crate::metadata::fixture;
+
+        // block 99 is the last of runtime 9360, Balances moved from index 5 to 10 in 9370
+        let client = Client::with_transport(MockNode::new().with_metadata(&fixture::metadata_with(10)));
+        let node = client.transport();
+        let metadata = client.metadata().await.unwrap();
+        let key = format!("0x{}", hex::encode(metadata.storage_value_key("System", "Events").unwrap().0));
+        for number in 99..=100u32 {
+            let hash = format!("0x{:064x}", number);
+            node.set_result_for("chain_getBlockHash", serde_json::json!([number]), serde_json::json!(hash));
+            node.set_result_for("chain_getBlock", serde_json::json!([hash]), serde_json::json!({"block": {
+                "header": {"parentHash": "0x00", "number": format!("0x{:x}", number), "stateRoot": "0x00", "extrinsicsRoot": "0x00"},
+                "extrinsics": [],
+            }}));
+        }
+        let old_hash = format!("0x{:064x}", 99);
+        node.set_result_for("state_getRuntimeVersion", serde_json::json!([old_hash]), serde_json::json!({
+            "specVersion": 9360, "authoringVersion": 0, "implName": "parity-polkadot", "specName": "polkadot", "stateVersion": 0, "transactionVersion": 20,
+        }));
+        node.set_result_for("state_getMetadata", serde_json::json!([old_hash]), serde_json::json!(format!("0x{}", hex::encode(fixture::metadata()))));
+        let events = format!("0x{}", hex::encode(fixture::events()));
+        node.set_result_for("state_getStorage", serde_json::json!([key, old_hash]), serde_json::json!(events));
+
+        let from = AccountId32::new([1u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        let mut history = History::new(vec![from], 99);
+        assert_eq!(client.scan_history(&mut history, 10).await.unwrap(), 2);
+        assert_eq!(history.entries.len(), 1);
+        assert_eq!((history.entries[0].block_number, history.entries[0].direction), (99, Direction::Out));
+        // once per runtime, each at a block of its own
+        let requests = node.requests();
+        let fetched: Vec<_> = requests.iter().filter(|(method, _)| method == "state_getMetadata").map(|(_, params)| params.clone()).collect();
+        assert_eq!(fetched, vec![serde_json::json!([]), serde_json::json!([old_hash]), serde_json::json!([format!("0x{:064x}", 100)])]);
+    }
+
+    #[tokio::test]
+    async fn test_chain_info() {
+        let client = mock_client();
+        let info = client.chain_info().await.unwrap();
+        assert_eq!(info.label(), "Polkadot (DOT)");
+        assert_eq!(info.name, "Parity Polkadot");
+        assert_eq!(info.properties.ss58_prefix(), 0);
+        assert_eq!(client.transport().round_trips(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_balance() {
+        let client = mock_client();
+        assert!(client.system_account(BOB).await.unwrap() == AccountInfo::default());
+        let mut bob = AccountInfo::default();
+        bob.data.free = 42;
+        client.transport().set_account(BOB, &bob).unwrap();
+        let account = client.system_account(BOB).await.unwrap();
+        assert_eq!(account.data.free, 42);
+        let key = client.storage_map_key("System", "Account", BOB).await.unwrap();
+        let requests = client.transport().requests();
+        assert_eq!(requests.last().unwrap(), &("state_getStorage".to_string(), serde_json::json!([format!("0x{}", key)])));
+    }
+
+    #[tokio::test]
+    async fn test_mock_errors() {
+        let client = mock_client();
+        client.transport().set_error("author_submitExtrinsic", 1010, "Invalid Transaction");
+        let error = client.transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), 1).await.unwrap_err();
+        assert_eq!(error.rpc_code(), Some(1010));
+        assert!(client.transport().submitted().is_empty());
+
+        // no metadata served
+        let client = Client::with_transport(MockNode::new());
+        let error = client.prepare_transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), TransferKind::KeepAlive, 1).await.unwrap_err();
+        assert_eq!(error.rpc_code(), Some(-32601));
+        assert!(matches!(client.transfer(CryptoScheme::Sr25519, "//Alice".to_string(), "alice".to_string(), BOB.to_string(), 1).await, Err(Error::Other(_))));
+        assert!(matches!(client.subscribe_new_heads().await, Err(Error::Other(_))));
+        let client = Client::with_transport(MockNode::new());
+        client.transport().set_result("chain_getBlockHash", serde_json::Value::Null);
+        assert!(matches!(client.genesis_hash().await, Err(Error::NullResult(_))));
+    }
+
+    #[tokio::test]
+    async fn test_warm_transfer() {
+        let client = mock_client();
+        let upgrade = StorageValue::new("System", "LastRuntimeUpgrade").key();
+        client.transport().set_storage(&hex::encode(&upgrade.0), Some(&(Compact(9370u32), "polkadot".to_string()).encode()));
+        let transfer = || client.transfer(CryptoScheme::Sr25519, "//Alice".to_string(), ALICE.to_string(), BOB.to_string(), 1);
+
+        // batch, era anchor header, metadata and the submission
+        transfer().await.unwrap();
+        assert_eq!(client.transport().round_trips(), 4);
+        let requests = client.transport().requests().len();
+        transfer().await.unwrap();
+        assert_eq!(client.transport().round_trips(), 6);
+        let methods: Vec<String> = client.transport().requests()[requests..].iter().map(|(method, _)| method.clone()).collect();
+        assert_eq!(methods, vec!["state_getStorage", "state_getStorage", "chain_getFinalizedHead", "chain_getHeader", "author_submitExtrinsic"]);
+
+        // a runtime upgrade brings the new version and its metadata
+        client.transport().set_storage(&hex::encode(&upgrade.0), Some(&(Compact(9380u32), "polkadot".to_string()).encode()));
+        let mut version = client.cached().runtime_version.unwrap();
+        version.spec_version = 9380;
+        client.transport().set_result("state_getRuntimeVersion", serde_json::to_value(&version).unwrap());
+        transfer().await.unwrap();
+        assert_eq!(client.transport().round_trips(), 10);
+        assert_eq!(client.cached().runtime_version.unwrap().spec_version, 9380);
+        let submitted = client.transport().submitted();
+        let raw = hex::decode(&submitted[2]).unwrap();
+        let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, Compact<u128>), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+        assert!(xt.signature.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let client = Client::with_transport(MockNode::new());
+        let mut batch = client.batch();
+        let genesis = batch.add::<_, String>("chain_getBlockHash", [0]);
+        let version = batch.add::<_, RuntimeVersion>("state_getRuntimeVersion", Vec::<String>::new());
+        let storage = batch.add::<_, String>("state_getStorage", ["0x01"]);
+        let missing = batch.add::<_, String>("missing", Vec::<String>::new());
+        assert_eq!(batch.len(), 4);
+        let responses = batch.send().await.unwrap();
+        assert_eq!(client.transport().round_trips(), 1);
+        assert_eq!(responses.get(&genesis).unwrap(), crate::rpc::mock::GENESIS_HASH);
+        assert_eq!(responses.get(&version).unwrap().spec_version, 9370);
+        assert_eq!(responses.get_opt(&storage).unwrap(), None);
+        assert!(matches!(responses.get(&storage), Err(Error::NullResult(_))));
+        assert_eq!(responses.get(&missing).unwrap_err().rpc_code(), Some(-32601));
+    }
+
+
     #[test]
-    fn test001() {
-        let client = Client::new("https://rpc.polkadot.io".to_string());
-        futures::executor::block_on(async {
-            match client.system_account("15QFBQY6TF6Abr6vA1r6opRh6RbRSMWgBC1PcCMDDzRSEXfn").await {
-                Ok(account) => {
-                    // println!("{}", account);
-                }
-                Err(e) => {
-                    println!("ddd {}", e.to_string());
-                }
-            }
-        });
+    fn test_fee_info() {
+        assert_eq!(sp_core::hashing::blake2_64(b"TransactionPaymentApi"), TRANSACTION_PAYMENT_API);
+        let version: RuntimeVersion = serde_json::from_str(r#"{"specVersion":9370,"authoringVersion":0,"implName":"parity-polkadot","specName":"polkadot","stateVersion":0,"transactionVersion":20,"apis":[["0xdf6acb689907609b",4],["0x37c8bb1350a9a2a8",2]]}"#).unwrap();
+        assert_eq!(version.api_version(&TRANSACTION_PAYMENT_API), Some(2));
+        assert_eq!(sp_core::hashing::blake2_64(b"TransactionPaymentCallApi"), TRANSACTION_PAYMENT_CALL_API);
+
+        let info = DispatchInfo {
+            weight: Weight { ref_time: 190_949_000, proof_size: 3_593 },
+            class: DispatchClass::Normal,
+            partial_fee: 159_154_001,
+        };
+        assert_eq!(DispatchInfo::decode_versioned(&info.encode(), 2).unwrap(), info);
+        // weights v1: plain u64
+        let old = (190_949_000u64, DispatchClass::Operational, 159_154_001u128).encode();
+        let decoded = DispatchInfo::decode_versioned(&old, 1).unwrap();
+        assert_eq!(decoded.weight, Weight { ref_time: 190_949_000, proof_size: 0 });
+        assert_eq!(decoded.class, DispatchClass::Operational);
+
+        let details = FeeDetails {
+            inclusion_fee: Some(InclusionFee { base_fee: 100, len_fee: 20, adjusted_weight_fee: 3 }),
+            tip: 7,
+        };
+        let fee = FeeEstimate { info, details: FeeDetails::decode(&mut details.encode().as_slice()).unwrap() };
+        assert_eq!(fee.total_fee(), 159_154_008);
+    }
+
+
+    #[tokio::test]
+    async fn test_ws() {
+        let mut responses = HashMap::new();
+        responses.insert("state_getRuntimeVersion".to_string(), serde_json::json!({"specVersion": 9370, "authoringVersion": 0, "implName": "parity-polkadot", "specName": "polkadot", "stateVersion": 0, "transactionVersion": 20}));
+        let node = crate::rpc::ws::stand_in::start(responses).await;
+        let client = Client::new(vec![node.url.clone()]);
+        assert_eq!(client.runtime_version().await.unwrap().spec_version, 9370);
+        let mut heads = client.subscribe_new_heads().await.unwrap();
+        assert_eq!(heads.next().await.unwrap().unwrap().block_number().unwrap(), 10);
+
+        let client = Client::new(vec!["http://127.0.0.1:9933".to_string()]);
+        assert!(client.subscribe_new_heads().await.is_err());
+    }
+
+
+    #[tokio::test]
+    async fn test_errors() {
+        let mut responses = HashMap::new();
+        responses.insert("state_getStorage".to_string(), serde_json::Value::Null);
+        let node = crate::rpc::ws::stand_in::start(responses).await;
+        let client = Client::new(vec![node.url.clone()]);
+        // a new account has no storage yet
+        let account = client.system_account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").await.unwrap();
+        assert!(account == AccountInfo::default());
+        match client.storage_at("0x01".to_string(), None).await {
+            Err(Error::NullStorage(key)) => assert_eq!(key, "0x01"),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        let error = client.genesis_hash().await.unwrap_err();
+        assert_eq!(error.rpc_code(), Some(-32601));
+        assert!(error.to_string().contains("Method not found"));
+        assert!(matches!(client.system_account("not an address").await, Err(Error::Other(_))));
+
+        let response: JsonRpcResp<String> = serde_json::from_str(r#"{"jsonrpc":"2.0","id":null,"error":{"code":1010,"message":"Invalid Transaction","data":"Inability to pay some fees"}}"#).unwrap();
+        let error = response.error.unwrap();
+        assert_eq!(error.code, 1010);
+        assert_eq!(error.data, Some(serde_json::json!("Inability to pay some fees")));
+
+        let client = Client::new(vec!["http://127.0.0.1:1".to_string()]);
+        assert!(matches!(client.genesis_hash().await, Err(Error::Transport(_))));
+    }
+
+
+    #[tokio::test]
+    async fn test_track() {
+        let extrinsic = "0c0304".to_string();
+        let block_hash = "0x01";
+        let mut responses = HashMap::new();
+        responses.insert("state_getRuntimeVersion".to_string(), serde_json::json!({"specVersion": 1, "authoringVersion": 0, "implName": "node", "specName": "node", "stateVersion": 0, "transactionVersion": 1}));
+        responses.insert("state_getMetadata".to_string(), serde_json::json!(format!("0x{}", hex::encode(crate::metadata::fixture::metadata()))));
+        responses.insert("state_getStorage".to_string(), serde_json::json!(format!("0x{}", hex::encode(crate::metadata::fixture::events()))));
+        // ours is the second extrinsic, the transfer in the fixture events
+        responses.insert("chain_getBlock".to_string(), serde_json::json!({"block": {
+            "header": {"parentHash": "0x00", "number": "0x1", "stateRoot": "0x00", "extrinsicsRoot": "0x00"},
+            "extrinsics": ["0x0c0102", "0x0c0304"],
+        }}));
+        let mut notifications = HashMap::new();
+        notifications.insert("author_submitAndWatchExtrinsic".to_string(), vec![
+            serde_json::json!("ready"),
+            serde_json::json!({"broadcast": ["peer"]}),
+            serde_json::json!({"inBlock": block_hash}),
+            serde_json::json!({"finalized": block_hash}),
+        ]);
+        let node = crate::rpc::ws::stand_in::start_with(responses, notifications).await;
+        let client = Client::new(vec![node.url.clone()]);
+        let mut statuses = Vec::new();
+        let tx = client.track(extrinsic, |tx| statuses.push(tx.status.clone())).await.unwrap();
+        assert_eq!(statuses, vec![TxStatus::Submitted, TxStatus::Ready, TxStatus::Broadcast(1), TxStatus::InBlock, TxStatus::Finalized]);
+        assert_eq!(tx.block_hash.as_deref(), Some(block_hash));
+        assert_eq!(tx.index, Some(1));
+        assert_eq!(tx.outcome, Some(crate::tracker::TxOutcome::Success));
     }
 
 
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use codec::{Compact, Decode};
+use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use sp_runtime::generic::Era;
+
+use crate::metadata::Metadata;
+use crate::value::Value;
+
+/// Used when the runtime has no `System::SS58Prefix` constant.
+const DEFAULT_SS58_PREFIX: u16 = 42;
+
+/// An extrinsic decoded with the runtime metadata, see [`decode_extrinsic`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedExtrinsic {
+    pub version: u8,
+    /// `None` for unsigned extrinsics like inherents.
+    pub signed: Option<SignedDetails>,
+    pub pallet: String,
+    pub call: String,
+    /// Call arguments by name, positional ones are named by their index.
+    pub args: Vec<(String, Value)>,
+    /// `System::SS58Prefix` of the runtime, addresses are shown with it.
+    pub ss58_prefix: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedDetails {
+    /// SS58 address for account ids, the decoded address otherwise.
+    pub signer: String,
+    pub signature: Value,
+    pub era: Era,
+    pub nonce: u32,
+    pub tip: u128,
+    /// Every signed extension with its extra data, in runtime order.
+    pub extensions: Vec<(String, Value)>,
+}
+
+impl DecodedExtrinsic {
+    pub fn arg(&self, name: &str) -> Option<&Value> {
+        self.args.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+/// A call without an extrinsic around it, see [`decode_call`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedCall {
+    pub pallet: String,
+    pub call: String,
+    pub args: Vec<(String, Value)>,
+    pub ss58_prefix: u16,
+}
+
+
+/// Decode a hex encoded extrinsic, as submitted or as found in a block, into its signer, signed
+/// extensions and call. Quotes and surrounding whitespace from a pasted blob are ignored.
+pub fn decode_extrinsic(metadata: &Metadata, extrinsic: &str) -> Result<DecodedExtrinsic> {
+    let extrinsic = extrinsic.trim().trim_matches('"');
+    let bytes = hex::decode(extrinsic.trim_start_matches("0x")).map_err(|e| anyhow!("invalid hex: {}", e))?;
+    let input = &mut &bytes[..];
+
+    let len = Compact::<u32>::decode(input)?.0 as usize;
+    if len != input.len() {
+        bail!("extrinsic length is {} but {} bytes follow", len, input.len());
+    }
+    let version = u8::decode(input)?;
+    let is_signed = version & 0b1000_0000 != 0;
+    let version = version & 0b0111_1111;
+    if version != metadata.extrinsic_version {
+        bail!("unsupported extrinsic version {}, the runtime uses {}", version, metadata.extrinsic_version);
+    }
+
+    let ss58_prefix = metadata.constant_value::<u16>("System", "SS58Prefix").unwrap_or(DEFAULT_SS58_PREFIX);
+    let signed = if is_signed {
+        Some(decode_signed(metadata, ss58_prefix, input)?)
+    } else {
+        None
+    };
+
+    let DecodedCall { pallet, call, args, .. } = read_call(metadata, ss58_prefix, input)?;
+    Ok(DecodedExtrinsic {
+        version,
+        signed,
+        pallet,
+        call,
+        args,
+        ss58_prefix,
+    })
+}
+
+/// Decode an encoded call on its own, like the call multisig signatories approve.
+pub fn decode_call(metadata: &Metadata, call: &[u8]) -> Result<DecodedCall> {
+    let ss58_prefix = metadata.constant_value::<u16>("System", "SS58Prefix").unwrap_or(DEFAULT_SS58_PREFIX);
+    read_call(metadata, ss58_prefix, &mut &call[..])
+}
+
+fn read_call(metadata: &Metadata, ss58_prefix: u16, input: &mut &[u8]) -> Result<DecodedCall> {
+    let pallet_index = u8::decode(input)?;
+    let pallet = metadata.pallet_by_index(pallet_index)?;
+    let call_ty = pallet.call_ty.ok_or(anyhow!("pallet {} has no calls", pallet.name))?;
+    let (call, fields) = match Value::decode(&metadata.types, call_ty, input)? {
+        Value::Variant { name, fields, .. } => (name, fields),
+        _ => bail!("calls of pallet {} are not an enum", pallet.name),
+    };
+    if !input.is_empty() {
+        bail!("{} bytes left after the call", input.len());
+    }
+    let args = fields.into_iter().enumerate()
+        .map(|(i, (name, value))| (name.unwrap_or_else(|| i.to_string()), value))
+        .collect();
+    Ok(DecodedCall { pallet: pallet.name.clone(), call, args, ss58_prefix })
+}
+
+fn decode_signed(metadata: &Metadata, ss58_prefix: u16, input: &mut &[u8]) -> Result<SignedDetails> {
+    let address_ty = metadata.address_ty.ok_or(anyhow!("metadata has no address type"))?;
+    let signature_ty = metadata.signature_ty.ok_or(anyhow!("metadata has no signature type"))?;
+    let address = Value::decode(&metadata.types, address_ty, input)?;
+    let signature = Value::decode(&metadata.types, signature_ty, input)?;
+
+    let mut era = Era::Immortal;
+    let mut nonce = 0;
+    let mut tip = 0;
+    let mut extensions = Vec::new();
+    for extension in &metadata.signed_extensions {
+        let start = *input;
+        let value = Value::decode(&metadata.types, extension.ty, input)?;
+        match extension.identifier.as_str() {
+            // the era is a custom encoding, decoded again from the same bytes
+            "CheckMortality" | "CheckEra" => era = Era::decode(&mut &start[..start.len() - input.len()])?,
+            "CheckNonce" => nonce = value.as_u128().ok_or(anyhow!("nonce is not an integer"))? as u32,
+            "ChargeTransactionPayment" => tip = value.as_u128().ok_or(anyhow!("tip is not an integer"))?,
+            // the asset tip is a struct with the tip and an optional asset id
+            "ChargeAssetTxPayment" => tip = value.field("tip").and_then(Value::as_u128).unwrap_or_default(),
+            _ => {}
+        }
+        extensions.push((extension.identifier.clone(), value));
+    }
+
+    Ok(SignedDetails {
+        signer: address_string(&address, ss58_prefix).unwrap_or_else(|| address.to_string()),
+        signature,
+        era,
+        nonce,
+        tip,
+        extensions,
+    })
+}
+
+/// SS58 of a `MultiAddress::Id` or a plain account id.
+fn address_string(address: &Value, prefix: u16) -> Option<String> {
+    let id = match address.variant_name() {
+        Some("Id") => address.at(0).and_then(Value::as_bytes),
+        Some(_) => None,
+        None => address.as_bytes(),
+    };
+    let id = <[u8; 32]>::try_from(id?).ok()?;
+    Some(AccountId32::new(id).to_ss58check_with_version(Ss58AddressFormat::custom(prefix)))
+}
+
+
+/// `Pallet.call(name: value, ..)`.
+fn write_call(f: &mut fmt::Formatter<'_>, pallet: &str, call: &str, args: &[(String, Value)], ss58_prefix: u16) -> fmt::Result {
+    write!(f, "{}.{}(", pallet, call)?;
+    for (i, (name, value)) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        // a 32 byte `Id` is the account of a `MultiAddress`, shown like the signer
+        match value.variant_name() {
+            Some("Id") => match address_string(value, ss58_prefix) {
+                Some(address) => write!(f, "{}: {}", name, address)?,
+                None => write!(f, "{}: {}", name, value)?,
+            },
+            _ => write!(f, "{}: {}", name, value)?,
+        }
+    }
+    write!(f, ")")
+}
+
+impl fmt::Display for DecodedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_call(f, &self.pallet, &self.call, &self.args, self.ss58_prefix)
+    }
+}
+
+impl fmt::Display for DecodedExtrinsic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_call(f, &self.pallet, &self.call, &self.args, self.ss58_prefix)?;
+        match &self.signed {
+            Some(signed) => {
+                write!(f, " signed by {}, nonce {}, tip {}, ", signed.signer, signed.nonce, signed.tip)?;
+                match signed.era {
+                    Era::Immortal => write!(f, "immortal"),
+                    Era::Mortal(period, phase) => write!(f, "mortal for {} blocks from phase {}", period, phase),
+                }
+            }
+            None => write!(f, " unsigned"),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use codec::Encode;
+
+    use crate::metadata::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_unsigned() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        // System.remark(0x0102)
+        let call = [0u8, 0, 8, 1, 2];
+        let mut xt = vec![4u8];
+        xt.extend(call);
+        let decoded = decode_extrinsic(&metadata, &format!("0x{}", hex::encode(xt.encode()))).unwrap();
+        assert_eq!(decoded.signed, None);
+        assert_eq!((decoded.pallet.as_str(), decoded.call.as_str()), ("System", "remark"));
+        assert_eq!(decoded.arg("remark"), Some(&Value::Bytes(vec![1, 2])));
+        assert_eq!(decoded.to_string(), "System.remark(remark: 0x0102) unsigned");
+
+        let mut long = xt.clone();
+        long.push(0);
+        assert!(decode_extrinsic(&metadata, &hex::encode(long.encode())).unwrap_err().to_string().contains("1 bytes left"));
+        assert!(decode_extrinsic(&metadata, &hex::encode(&xt)).is_err());
+        xt[0] = 3;
+        assert!(decode_extrinsic(&metadata, &hex::encode(xt.encode())).unwrap_err().to_string().contains("version 3"));
+    }
+
+    #[test]
+    fn test_decode_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let decoded = decode_call(&metadata, &[0u8, 0, 8, 1, 2]).unwrap();
+        assert_eq!(decoded.to_string(), "System.remark(remark: 0x0102)");
+        assert!(decode_call(&metadata, &[0u8, 0, 8, 1]).is_err());
+        assert!(decode_call(&metadata, &[0u8, 0, 8, 1, 2, 3]).unwrap_err().to_string().contains("1 bytes left"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error object of a JSON-RPC response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)?;
+        if let Some(data) = &self.data {
+            write!(f, ": {}", data)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Errors of `Client` calls.
+#[derive(Debug)]
+pub enum Error {
+    /// The request did not reach the node or the connection failed while waiting.
+    Transport(String),
+    /// The node answered with an error object.
+    Rpc(RpcError),
+    /// No value is stored at this key.
+    NullStorage(String),
+    /// The method returned `null` where a value was expected.
+    NullResult(String),
+    Hex(hex::FromHexError),
+    /// SCALE decoding of a node response failed.
+    Decode(codec::Error),
+    Json(serde_json::Error),
+    /// Everything else, like signing, metadata lookups or address parsing.
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// Code of a node error, `None` for other errors.
+    pub fn rpc_code(&self) -> Option<i64> {
+        match self {
+            Error::Rpc(error) => Some(error.code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "transport error: {}", e),
+            Error::Rpc(e) => write!(f, "rpc error: {}", e),
+            Error::NullStorage(key) => write!(f, "no storage value at {}", key),
+            Error::NullResult(method) => write!(f, "{} returned null", method),
+            Error::Hex(e) => write!(f, "hex error: {}", e),
+            Error::Decode(e) => write!(f, "decode error: {}", e),
+            Error::Json(e) => write!(f, "json error: {}", e),
+            Error::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Hex(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e.to_string())
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(e: hex::FromHexError) -> Self {
+        Error::Hex(e)
+    }
+}
+
+impl From<codec::Error> for Error {
+    fn from(e: codec::Error) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Error::Other(e)
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use sp_core::crypto::AccountId32;
+
+use crate::metadata::{self, Metadata};
+use crate::value::Value;
+
+/// When an event was emitted within its block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// While applying the extrinsic at this index.
+    ApplyExtrinsic(u32),
+    Finalization,
+    Initialization,
+}
+
+/// One record of `System::Events`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventRecord {
+    pub phase: Phase,
+    pub pallet: String,
+    pub variant: String,
+    /// Unnamed for events declared with tuple fields.
+    pub fields: Vec<(Option<String>, Value)>,
+    pub topics: Vec<Vec<u8>>,
+}
+
+/// `Balances::Transfer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: AccountId32,
+    pub to: AccountId32,
+    pub amount: u128,
+}
+
+/// `System::ExtrinsicFailed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtrinsicFailed {
+    pub error: DispatchError,
+}
+
+/// `TransactionPayment::TransactionFeePaid`, the fee includes the tip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionFeePaid {
+    pub who: AccountId32,
+    pub actual_fee: u128,
+    pub tip: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DispatchError {
+    /// An error of a pallet, named from the pallet's error enum.
+    Module { pallet: String, error: String },
+    /// Errors outside of pallets like `BadOrigin`, as decoded.
+    Other(String),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::Module { pallet, error } => write!(f, "{}.{}", pallet, error),
+            DispatchError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+
+/// Decode the SCALE encoded `System::Events` of a block.
+pub fn decode_events(metadata: &Metadata, events: &[u8]) -> Result<Vec<EventRecord>> {
+    let entry = metadata.storage_entry("System", "Events")?;
+    let records = match Value::decode(&metadata.types, entry.value_ty, &mut &events[..])? {
+        Value::Sequence(records) => records,
+        _ => bail!("System::Events is not a sequence"),
+    };
+    records.iter().map(event_record).collect()
+}
+
+fn event_record(record: &Value) -> Result<EventRecord> {
+    let phase = record.field("phase").ok_or(anyhow!("event record without phase"))?;
+    let phase = match phase.variant_name() {
+        Some("ApplyExtrinsic") => {
+            let index = phase.at(0).and_then(Value::as_u128).ok_or(anyhow!("phase without extrinsic index"))?;
+            Phase::ApplyExtrinsic(index as u32)
+        }
+        Some("Finalization") => Phase::Finalization,
+        Some("Initialization") => Phase::Initialization,
+        _ => bail!("unknown phase {}", phase),
+    };
+    // the runtime event enum has a variant per pallet wrapping the pallet's own event enum
+    let event = record.field("event").ok_or(anyhow!("event record without event"))?;
+    let pallet = event.variant_name().ok_or(anyhow!("event is not an enum"))?;
+    let (variant, fields) = match event.at(0) {
+        Some(Value::Variant { name, fields, .. }) => (name.clone(), fields.clone()),
+        _ => bail!("{} event is not an enum", pallet),
+    };
+    let topics = match record.field("topics") {
+        Some(Value::Sequence(topics)) => topics.iter().filter_map(|t| t.as_bytes().map(<[u8]>::to_vec)).collect(),
+        _ => Vec::new(),
+    };
+    Ok(EventRecord {
+        phase,
+        pallet: pallet.to_string(),
+        variant,
+        fields,
+        topics,
+    })
+}
+
+
+impl EventRecord {
+    pub fn is(&self, pallet: &str, variant: &str) -> bool {
+        self.pallet == pallet && self.variant == variant
+    }
+
+    /// Index of the extrinsic that emitted the event.
+    pub fn extrinsic(&self) -> Option<u32> {
+        match self.phase {
+            Phase::ApplyExtrinsic(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Field by name, or by position for events with tuple fields.
+    pub fn field(&self, name: &str, position: usize) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| n.as_deref() == Some(name)).map(|(_, v)| v)
+            .or_else(|| self.fields.get(position).filter(|(n, _)| n.is_none()).map(|(_, v)| v))
+    }
+
+    fn account(&self, name: &str, position: usize) -> Option<AccountId32> {
+        let bytes = self.field(name, position)?.as_bytes()?;
+        Some(AccountId32::new(<[u8; 32]>::try_from(bytes).ok()?))
+    }
+
+    fn amount(&self, name: &str, position: usize) -> Option<u128> {
+        self.field(name, position)?.as_u128()
+    }
+
+    pub fn transfer(&self) -> Option<Transfer> {
+        if !self.is("Balances", "Transfer") {
+            return None;
+        }
+        Some(Transfer {
+            from: self.account("from", 0)?,
+            to: self.account("to", 1)?,
+            amount: self.amount("amount", 2)?,
+        })
+    }
+
+    /// Module errors are looked up by name in `metadata`.
+    pub fn extrinsic_failed(&self, metadata: &Metadata) -> Option<ExtrinsicFailed> {
+        if !self.is("System", "ExtrinsicFailed") {
+            return None;
+        }
+        let error = self.field("dispatch_error", 0)?;
+        Some(ExtrinsicFailed { error: dispatch_error(metadata, error) })
+    }
+
+    pub fn fee_paid(&self) -> Option<TransactionFeePaid> {
+        if !self.is("TransactionPayment", "TransactionFeePaid") {
+            return None;
+        }
+        Some(TransactionFeePaid {
+            who: self.account("who", 0)?,
+            actual_fee: self.amount("actual_fee", 1)?,
+            tip: self.amount("tip", 2)?,
+        })
+    }
+}
+
+impl fmt::Display for EventRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.pallet, self.variant)?;
+        if !self.fields.is_empty() {
+            write!(f, "{}", Value::Composite(self.fields.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Name a `DispatchError::Module` with the error enum of its pallet.
+pub fn dispatch_error(metadata: &Metadata, error: &Value) -> DispatchError {
+    if error.variant_name() != Some("Module") {
+        return DispatchError::Other(error.to_string());
+    }
+    let module = match error.at(0) {
+        Some(module) => module,
+        None => return DispatchError::Other(error.to_string()),
+    };
+    let index = module.field("index").and_then(Value::as_u128);
+    // `error` is a `u8` in older runtimes, 4 bytes with the index first in newer ones
+    let error_index = match module.field("error") {
+        Some(Value::Bytes(bytes)) => bytes.first().map(|b| *b as u128),
+        Some(value) => value.as_u128(),
+        None => None,
+    };
+    let (index, error_index) = match (index, error_index) {
+        (Some(index), Some(error_index)) => (index as u8, error_index as u8),
+        _ => return DispatchError::Other(error.to_string()),
+    };
+    let pallet = match metadata.pallet_by_index(index) {
+        Ok(pallet) => pallet,
+        Err(_) => return DispatchError::Other(error.to_string()),
+    };
+    let name = pallet.error_ty
+        .and_then(|ty| metadata::variants(&metadata.types, ty).ok())
+        .and_then(|variants| variants.iter().find(|v| v.index() == error_index))
+        .map(|v| v.name().clone())
+        .unwrap_or_else(|| format!("Error{}", error_index));
+    DispatchError::Module { pallet: pallet.name.clone(), error: name }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_events() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let events = decode_events(&metadata, &fixture::events()).unwrap();
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[0].phase, Phase::ApplyExtrinsic(0));
+        assert!(events[0].is("System", "ExtrinsicSuccess"));
+        assert_eq!(events[5].phase, Phase::Finalization);
+        assert_eq!(events[5].extrinsic(), None);
+
+        let transfers: Vec<_> = events.iter().filter_map(EventRecord::transfer).collect();
+        assert_eq!(transfers, vec![Transfer { from: [1u8; 32].into(), to: [2u8; 32].into(), amount: 10 }]);
+        assert_eq!(events[1].to_string(), format!("Balances.Transfer(from: 0x{}, to: 0x{}, amount: 10)", hex::encode([1u8; 32]), hex::encode([2u8; 32])));
+
+        let fee = events.iter().find_map(EventRecord::fee_paid).unwrap();
+        assert_eq!(fee, TransactionFeePaid { who: [1u8; 32].into(), actual_fee: 159_154_001, tip: 0 });
+
+        let failed = events.iter().find_map(|e| e.extrinsic_failed(&metadata)).unwrap();
+        assert_eq!(failed.error, DispatchError::Module { pallet: "Balances".to_string(), error: "InsufficientBalance".to_string() });
+        assert_eq!(failed.error.to_string(), "Balances.InsufficientBalance");
+        assert_eq!(dispatch_error(&metadata, &Value::Variant { name: "BadOrigin".to_string(), index: 2, fields: vec![] }), DispatchError::Other("BadOrigin".to_string()));
+    }
+}
@@ -33,6 +33,21 @@ pub type CallIndex = [u8; 2];
 
 pub type Balance = u128;
 
+/// A call already encoded as `[pallet_index, call_index]` and its arguments, e.g. one of the
+/// calls of a `Utility` batch. Encodes as is, without a length prefix.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncodedCall(pub Vec<u8>);
+
+impl Encode for EncodedCall {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
 /// Mirrors the currently used Extrinsic format (V4) from substrate. Has less traits and methods though.
 /// The SingedExtra used does not need to implement SingedExtension here.
 #[derive(Clone, Eq, PartialEq)]
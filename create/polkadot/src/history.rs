@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+use crate::decode::decode_extrinsic;
+use crate::events::EventRecord;
+use crate::metadata::Metadata;
+
+/// Blocks scanned before the first block a new history is started at, one day of 6s blocks.
+pub const INITIAL_LOOKBACK: u32 = 14_400;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// A `Balances::Transfer` from or to one of the accounts of a `History`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub block_number: u32,
+    pub block_hash: String,
+    pub extrinsic_index: Option<u32>,
+    /// Milliseconds since the epoch, from the `Timestamp::set` of the block.
+    pub timestamp: Option<u64>,
+    pub direction: Direction,
+    /// The wallet account, as it was added to the history.
+    pub account: String,
+    pub counterparty: String,
+    pub amount: u128,
+    /// Fee paid by the account for the extrinsic, only on the first outgoing transfer of it.
+    pub fee: Option<u128>,
+}
+
+/// Transfers of a set of accounts, filled block by block by `Client::scan_history` and
+/// serialized as a whole to resume from `checkpoint`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    /// SS58 addresses, counterparties are shown with the same prefix.
+    pub accounts: Vec<String>,
+    /// Next block to scan.
+    pub checkpoint: u32,
+    /// Oldest first.
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// One page of entries, newest first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub page: usize,
+    pub pages: usize,
+    pub total: usize,
+}
+
+impl History {
+    pub fn new(accounts: Vec<String>, checkpoint: u32) -> Self {
+        History { accounts, checkpoint, entries: Vec::new() }
+    }
+
+    /// Accounts added later are only followed from the current checkpoint on. An account already
+    /// followed under another SS58 prefix is renamed, with its entries.
+    pub fn add_account(&mut self, address: &str) {
+        let id = AccountId32::from_ss58check_with_version(address).ok().map(|(id, _)| id);
+        let same = |account: &String| account == address
+            || id.is_some() && AccountId32::from_ss58check_with_version(account).ok().map(|(id, _)| id) == id;
+        match self.accounts.iter().position(same) {
+            Some(i) if self.accounts[i] == address => {}
+            Some(i) => {
+                let old = std::mem::replace(&mut self.accounts[i], address.to_string());
+                for entry in self.entries.iter_mut().filter(|entry| entry.account == old) {
+                    entry.account = address.to_string();
+                }
+            }
+            None => self.accounts.push(address.to_string()),
+        }
+    }
+
+    /// Page `page` of the entries of `account`, or of all accounts for `None`.
+    pub fn page(&self, account: Option<&str>, page: usize, per_page: usize) -> HistoryPage {
+        let per_page = per_page.max(1);
+        let entries: Vec<&HistoryEntry> = self.entries.iter().rev()
+            .filter(|entry| account.map(|account| entry.account == account).unwrap_or(true))
+            .collect();
+        let total = entries.len();
+        let pages = (total + per_page - 1) / per_page;
+        HistoryPage {
+            entries: entries.into_iter().skip(page * per_page).take(per_page).cloned().collect(),
+            page,
+            pages,
+            total,
+        }
+    }
+
+    /// Record the transfers of block `number` and move the checkpoint past it.
+    pub fn add_block(&mut self, metadata: &Metadata, number: u32, hash: &str, extrinsics: &[String], events: &[EventRecord]) -> Result<()> {
+        let mut accounts = Vec::with_capacity(self.accounts.len());
+        for address in &self.accounts {
+            let (id, format) = AccountId32::from_ss58check_with_version(address)
+                .map_err(|e| anyhow!("invalid address {}: {:?}", address, e))?;
+            accounts.push((address, id, format));
+        }
+        let mut timestamp = None;
+        // a batch has several transfers but pays one fee
+        let mut charged = HashSet::new();
+        for event in events {
+            let transfer = match event.transfer() {
+                Some(transfer) => transfer,
+                None => continue,
+            };
+            for (address, id, format) in &accounts {
+                let (direction, counterparty) = if transfer.from == *id {
+                    (Direction::Out, &transfer.to)
+                } else if transfer.to == *id {
+                    (Direction::In, &transfer.from)
+                } else {
+                    continue;
+                };
+                if timestamp.is_none() {
+                    timestamp = block_timestamp(metadata, extrinsics);
+                }
+                let fee = match direction {
+                    Direction::Out if charged.insert((event.extrinsic(), *address)) => events.iter()
+                        .filter(|e| e.extrinsic().is_some() && e.extrinsic() == event.extrinsic())
+                        .filter_map(EventRecord::fee_paid)
+                        .find(|fee| fee.who == *id)
+                        .map(|fee| fee.actual_fee),
+                    _ => None,
+                };
+                self.entries.push(HistoryEntry {
+                    block_number: number,
+                    block_hash: hash.to_string(),
+                    extrinsic_index: event.extrinsic(),
+                    timestamp,
+                    direction,
+                    account: address.to_string(),
+                    counterparty: counterparty.to_ss58check_with_version(*format),
+                    amount: transfer.amount,
+                    fee,
+                });
+            }
+        }
+        self.checkpoint = number + 1;
+        Ok(())
+    }
+}
+
+/// `now` of the `Timestamp::set` inherent among the extrinsics of a block.
+pub fn block_timestamp(metadata: &Metadata, extrinsics: &[String]) -> Option<u64> {
+    extrinsics.iter()
+        .filter_map(|extrinsic| decode_extrinsic(metadata, extrinsic).ok())
+        .find(|extrinsic| extrinsic.pallet == "Timestamp" && extrinsic.call == "set")
+        .and_then(|set| set.arg("now").and_then(|now| now.as_u128()))
+        .map(|now| now as u64)
+}
+
+
+#[cfg(test)]
+mod test {
+    use codec::{Compact, Encode};
+    use sp_core::crypto::Ss58AddressFormat;
+
+    use crate::events::decode_events;
+    use crate::metadata::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_add_block() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let events = decode_events(&metadata, &fixture::events()).unwrap();
+        let from = AccountId32::new([1u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        let to = AccountId32::new([2u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        // Timestamp.set(now)
+        let mut set = vec![4u8, 2, 0];
+        set.extend(Compact(1_700_000_000_000u64).encode());
+        let extrinsics = vec![format!("0x{}", hex::encode(set.encode()))];
+
+        let mut history = History::new(vec![from.clone()], 10);
+        history.add_block(&metadata, 10, "0x0a", &extrinsics, &events).unwrap();
+        history.add_account(&to);
+        history.add_block(&metadata, 11, "0x0b", &[], &events).unwrap();
+        assert_eq!(history.checkpoint, 12);
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.entries[0], HistoryEntry {
+            block_number: 10,
+            block_hash: "0x0a".to_string(),
+            extrinsic_index: Some(1),
+            timestamp: Some(1_700_000_000_000),
+            direction: Direction::Out,
+            account: from.clone(),
+            counterparty: to.clone(),
+            amount: 10,
+            fee: Some(159_154_001),
+        });
+        assert_eq!((history.entries[2].direction, history.entries[2].fee, history.entries[2].timestamp), (Direction::In, None, None));
+
+        let page = history.page(Some(&from), 0, 1);
+        assert_eq!((page.total, page.pages), (2, 2));
+        assert_eq!(page.entries[0].block_number, 11);
+        assert_eq!(history.page(Some(&from), 1, 1).entries[0].block_number, 10);
+        assert!(history.page(Some(&from), 2, 1).entries.is_empty());
+        assert_eq!(history.page(None, 0, 10).total, 3);
+
+        // the same account under the Kusama prefix replaces the Polkadot address
+        let kusama = AccountId32::new([1u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(2));
+        history.add_account(&kusama);
+        assert_eq!(history.accounts, vec![kusama.clone(), to.clone()]);
+        assert_eq!(history.page(Some(&kusama), 0, 10).total, 2);
+    }
+
+    #[test]
+    fn test_batch_fee() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let mut events = decode_events(&metadata, &fixture::events()).unwrap();
+        // a second transfer in extrinsic 1, like a batch to two recipients
+        events.insert(2, events[1].clone());
+        let from = AccountId32::new([1u8; 32]).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+
+        let mut history = History::new(vec![from], 10);
+        history.add_block(&metadata, 10, "0x0a", &[], &events).unwrap();
+        let fees: Vec<Option<u128>> = history.entries.iter().map(|entry| entry.fee).collect();
+        assert_eq!(fees, vec![Some(159_154_001), None]);
+        assert_eq!(history.entries.iter().map(|entry| entry.amount).sum::<u128>(), 20);
+    }
+}
@@ -1,11 +1,190 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
 use bip39::{Language, Mnemonic, Seed};
 pub use bip39::MnemonicType;
-use sp_core::{ed25519, Pair};
-use sp_core::crypto::Ss58Codec;
+use serde::{Deserialize, Serialize};
+use sp_core::{ecdsa, ed25519, Pair, sr25519};
+use sp_core::crypto::{AccountId32, DeriveJunction, Ss58Codec};
+use sp_runtime::{MultiSignature, MultiSigner};
 use sp_runtime::traits::IdentifyAccount;
 
 use crate::Ss58AddressFormat;
 
+/// Signature scheme of an account. Polkadot.js, Talisman and Subkey default to sr25519.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum CryptoScheme {
+    Sr25519,
+    Ed25519,
+    Ecdsa,
+}
+
+impl CryptoScheme {
+    pub fn all() -> [CryptoScheme; 3] {
+        [CryptoScheme::Sr25519, CryptoScheme::Ed25519, CryptoScheme::Ecdsa]
+    }
+}
+
+impl Default for CryptoScheme {
+    fn default() -> Self {
+        CryptoScheme::Sr25519
+    }
+}
+
+impl Display for CryptoScheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoScheme::Sr25519 => write!(f, "sr25519"),
+            CryptoScheme::Ed25519 => write!(f, "ed25519"),
+            CryptoScheme::Ecdsa => write!(f, "ecdsa"),
+        }
+    }
+}
+
+impl FromStr for CryptoScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sr25519" => Ok(CryptoScheme::Sr25519),
+            "ed25519" => Ok(CryptoScheme::Ed25519),
+            "ecdsa" => Ok(CryptoScheme::Ecdsa),
+            _ => Err(anyhow!("unknown crypto scheme {}", s)),
+        }
+    }
+}
+
+
+/// A key pair of any supported [`CryptoScheme`], signing into the matching `MultiSignature` variant.
+#[derive(Clone)]
+pub enum KeyPair {
+    Sr25519(sr25519::Pair),
+    Ed25519(ed25519::Pair),
+    Ecdsa(ecdsa::Pair),
+}
+
+impl KeyPair {
+    /// Accepts a hex seed (`0x...`), a phrase or a secret uri.
+    pub fn from_string(scheme: CryptoScheme, s: &str, password: Option<&str>) -> Result<Self> {
+        let pair = match scheme {
+            CryptoScheme::Sr25519 => KeyPair::Sr25519(sr25519::Pair::from_string(s, password).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+            CryptoScheme::Ed25519 => KeyPair::Ed25519(ed25519::Pair::from_string(s, password).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+            CryptoScheme::Ecdsa => KeyPair::Ecdsa(ecdsa::Pair::from_string(s, password).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+        };
+        Ok(pair)
+    }
+
+    /// Returns the pair together with its seed.
+    pub fn from_phrase(scheme: CryptoScheme, phrase: &str, password: Option<&str>) -> Result<(Self, Vec<u8>)> {
+        let result = match scheme {
+            CryptoScheme::Sr25519 => {
+                let (pair, seed) = sr25519::Pair::from_phrase(phrase, password).map_err(|e| anyhow!("gen pair error {:?}", e))?;
+                (KeyPair::Sr25519(pair), seed.to_vec())
+            }
+            CryptoScheme::Ed25519 => {
+                let (pair, seed) = ed25519::Pair::from_phrase(phrase, password).map_err(|e| anyhow!("gen pair error {:?}", e))?;
+                (KeyPair::Ed25519(pair), seed.to_vec())
+            }
+            CryptoScheme::Ecdsa => {
+                let (pair, seed) = ecdsa::Pair::from_phrase(phrase, password).map_err(|e| anyhow!("gen pair error {:?}", e))?;
+                (KeyPair::Ecdsa(pair), seed.to_vec())
+            }
+        };
+        Ok(result)
+    }
+
+    pub fn from_seed_slice(scheme: CryptoScheme, seed: &[u8]) -> Result<Self> {
+        let pair = match scheme {
+            CryptoScheme::Sr25519 => KeyPair::Sr25519(sr25519::Pair::from_seed_slice(seed).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+            CryptoScheme::Ed25519 => KeyPair::Ed25519(ed25519::Pair::from_seed_slice(seed).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+            CryptoScheme::Ecdsa => KeyPair::Ecdsa(ecdsa::Pair::from_seed_slice(seed).map_err(|e| anyhow!("gen pair error {:?}", e))?),
+        };
+        Ok(pair)
+    }
+
+    pub fn scheme(&self) -> CryptoScheme {
+        match self {
+            KeyPair::Sr25519(_) => CryptoScheme::Sr25519,
+            KeyPair::Ed25519(_) => CryptoScheme::Ed25519,
+            KeyPair::Ecdsa(_) => CryptoScheme::Ecdsa,
+        }
+    }
+
+    pub fn public(&self) -> MultiSigner {
+        match self {
+            KeyPair::Sr25519(pair) => pair.public().into(),
+            KeyPair::Ed25519(pair) => pair.public().into(),
+            KeyPair::Ecdsa(pair) => pair.public().into(),
+        }
+    }
+
+    /// Ecdsa accounts are the blake2 hash of the public key, the others are the public key itself.
+    pub fn account_id(&self) -> AccountId32 {
+        self.public().into_account()
+    }
+
+    pub fn sign(&self, msg: &[u8]) -> MultiSignature {
+        match self {
+            KeyPair::Sr25519(pair) => pair.sign(msg).into(),
+            KeyPair::Ed25519(pair) => pair.sign(msg).into(),
+            KeyPair::Ecdsa(pair) => pair.sign(msg).into(),
+        }
+    }
+}
+
+
+/// A secret uri split into its parts: `phrase//hard/soft///password`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecretUri {
+    pub phrase: String,
+    /// The junctions as written, e.g. `//polkadot//0`.
+    pub path: String,
+    pub junctions: Vec<DeriveJunction>,
+    pub password: Option<String>,
+}
+
+impl SecretUri {
+    /// The derivation path with the password masked, for display.
+    pub fn display_path(&self) -> String {
+        match &self.password {
+            Some(_) => format!("{}///***", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+impl FromStr for SecretUri {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (rest, password) = match s.find("///") {
+            Some(index) => (&s[..index], Some(s[index + 3..].to_string())),
+            None => (s, None),
+        };
+        let (phrase, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, ""),
+        };
+        if phrase.trim().is_empty() {
+            return Err(anyhow!("secret uri without phrase"));
+        }
+        if let Some(password) = &password {
+            if password.is_empty() {
+                return Err(anyhow!("empty derivation password"));
+            }
+        }
+        Ok(SecretUri {
+            phrase: phrase.trim().to_string(),
+            path: path.to_string(),
+            junctions: Key::parse_path(path)?,
+            password,
+        })
+    }
+}
+
+
 pub struct Key {}
 
 
@@ -18,30 +197,59 @@ impl Key {
         phrase.to_string()
     }
 
-    pub fn generate_seed(phrase: &str, password: Option<&str>) -> String {
-        let (_, seed) = ed25519::Pair::from_phrase(phrase, password).unwrap();
+    pub fn generate_seed(scheme: CryptoScheme, phrase: &str, password: Option<&str>) -> String {
+        let (_, seed) = KeyPair::from_phrase(scheme, phrase, password).unwrap();
         let seed_hex = hex::encode(&seed);
         seed_hex
     }
 
-    pub fn address_from_phrase(phrase: &str, password: Option<&str>) -> String {
-        let seed = Key::generate_seed(phrase, password);
-        let address = Key::address(&seed, 0);
+    /// `network_id` is the SS58 prefix, `ss58Format` of `system_properties`.
+    pub fn address_from_phrase(scheme: CryptoScheme, phrase: &str, password: Option<&str>, network_id: u16) -> String {
+        let seed = Key::generate_seed(scheme, phrase, password);
+        let address = Key::address(scheme, &seed, network_id);
         address
     }
 
 
-    pub fn address(seed: &str, network_id: u16) -> String {
+    /// Address of a full secret uri such as `phrase//polkadot//0` or `phrase/soft///pwd`.
+    /// Ed25519 and ecdsa only support hard junctions.
+    pub fn address_from_suri(scheme: CryptoScheme, suri: &str, network_id: u16) -> Result<String> {
+        let pair = KeyPair::from_string(scheme, suri, None)?;
+        Ok(pair.account_id().to_ss58check_with_version(Ss58AddressFormat::custom(network_id)))
+    }
+
+    /// Parse a derivation path (`//hard/soft`) without phrase and password.
+    pub fn parse_path(path: &str) -> Result<Vec<DeriveJunction>> {
+        let mut junctions = Vec::new();
+        let mut rest = path;
+        while !rest.is_empty() {
+            let stripped = rest.strip_prefix('/').ok_or(anyhow!("invalid derivation path {}", path))?;
+            let start = if stripped.starts_with('/') { 1 } else { 0 };
+            let end = stripped[start..].find('/').map(|i| i + start).unwrap_or(stripped.len());
+            if end == start {
+                return Err(anyhow!("empty junction in derivation path {}", path));
+            }
+            junctions.push(DeriveJunction::from(&stripped[..end]));
+            rest = &stripped[end..];
+        }
+        Ok(junctions)
+    }
+
+    pub fn address(scheme: CryptoScheme, seed: &str, network_id: u16) -> String {
         let result = hex::decode(seed).unwrap();
-        let pair: ed25519::Pair = ed25519::Pair::from_seed_slice(&result).unwrap();
-        let address = pair.public().into_account().to_ss58check_with_version(Ss58AddressFormat::custom(network_id));
+        let pair = KeyPair::from_seed_slice(scheme, &result).unwrap();
+        let address = pair.account_id().to_ss58check_with_version(Ss58AddressFormat::custom(network_id));
         address
     }
 
-    pub fn sign(phrase: &str, msg: &str, password: Option<&str>) -> String {
-        let (pair, _) = ed25519::Pair::from_phrase(phrase, password).unwrap();
+    pub fn sign(scheme: CryptoScheme, phrase: &str, msg: &str, password: Option<&str>) -> String {
+        let (pair, _) = KeyPair::from_phrase(scheme, phrase, password).unwrap();
         let hex_msg = hex::decode(msg).unwrap();
-        let signature = pair.sign(&hex_msg);
+        let signature = match pair.sign(&hex_msg) {
+            MultiSignature::Sr25519(signature) => signature.0.to_vec(),
+            MultiSignature::Ed25519(signature) => signature.0.to_vec(),
+            MultiSignature::Ecdsa(signature) => signature.0.to_vec(),
+        };
         hex::encode(signature)
     }
 }
@@ -49,22 +257,92 @@ impl Key {
 
 #[cfg(test)]
 mod test {
+    use sp_runtime::traits::Verify;
+
     use super::*;
 
+    const PHRASE: &str = "palace utility secret spice retire air notice wage shove alcohol render sphere";
+
     #[test]
     fn test_key() {
         let phrase = Key::generate_phrase(MnemonicType::Words12);
         let phrase = "palace utility secret spice retire air notice wage shove alcohol render sphere".to_string();
         println!("{:?}", phrase);
-        let seed = Key::generate_seed(&phrase, None);
+        let seed = Key::generate_seed(CryptoScheme::Ed25519, &phrase, None);
         let seed = format!("{}", seed);
         println!("{:?}", seed);
-        let address = Key::address(&seed, 0);
+        let address = Key::address(CryptoScheme::Ed25519, &seed, 0);
         println!("{:?}", address);
-        let signature = Key::sign(&phrase, "b23226e4e2bc629fc5e22ac16d0ee3ae68df31210e0871047f054caeff9d00db", None);
+        let signature = Key::sign(CryptoScheme::Ed25519, &phrase, "b23226e4e2bc629fc5e22ac16d0ee3ae68df31210e0871047f054caeff9d00db", None);
         println!("{:?}", signature);
     }
 
+    #[test]
+    fn test_schemes() {
+        let sr = Key::address_from_phrase(CryptoScheme::Sr25519, PHRASE, None, 0);
+        let ed = Key::address_from_phrase(CryptoScheme::Ed25519, PHRASE, None, 0);
+        let ec = Key::address_from_phrase(CryptoScheme::Ecdsa, PHRASE, None, 0);
+        assert_ne!(sr, ed);
+        assert_ne!(sr, ec);
+        assert_ne!(ed, ec);
+        // same key as sp_core would derive for the same scheme
+        let (pair, _) = sr25519::Pair::from_phrase(PHRASE, None).unwrap();
+        let expect = AccountId32::from(pair.public()).to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        assert_eq!(sr, expect);
+        let kusama = Key::address_from_phrase(CryptoScheme::Sr25519, PHRASE, None, 2);
+        assert_eq!(kusama, AccountId32::from(pair.public()).to_ss58check_with_version(Ss58AddressFormat::custom(2)));
+    }
+
+    #[test]
+    fn test_suri() {
+        let dev = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+        let alice = Key::address_from_suri(CryptoScheme::Sr25519, &format!("{}//Alice", dev), 42).unwrap();
+        assert_eq!(alice, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+        let alice = Key::address_from_suri(CryptoScheme::Ed25519, &format!("{}//Alice", dev), 42).unwrap();
+        assert_eq!(alice, "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu");
+
+        let root = Key::address_from_suri(CryptoScheme::Sr25519, PHRASE, 0).unwrap();
+        assert_eq!(root, Key::address_from_phrase(CryptoScheme::Sr25519, PHRASE, None, 0));
+        let hard = Key::address_from_suri(CryptoScheme::Sr25519, &format!("{}//polkadot//0", PHRASE), 0).unwrap();
+        let soft = Key::address_from_suri(CryptoScheme::Sr25519, &format!("{}/polkadot/0", PHRASE), 0).unwrap();
+        let pwd = Key::address_from_suri(CryptoScheme::Sr25519, &format!("{}/soft///pwd", PHRASE), 0).unwrap();
+        assert_ne!(root, hard);
+        assert_ne!(hard, soft);
+        assert_ne!(soft, pwd);
+        // soft derivation is sr25519 only
+        assert!(Key::address_from_suri(CryptoScheme::Ed25519, &format!("{}/soft", PHRASE), 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_suri() {
+        let suri = SecretUri::from_str(&format!("{}//polkadot/0///pwd", PHRASE)).unwrap();
+        assert_eq!(suri.phrase, PHRASE);
+        assert_eq!(suri.path, "//polkadot/0");
+        assert_eq!(suri.password, Some("pwd".to_string()));
+        assert_eq!(suri.junctions, vec![DeriveJunction::hard("polkadot"), DeriveJunction::soft(0u64)]);
+        assert_eq!(suri.display_path(), "//polkadot/0///***");
+
+        let suri = SecretUri::from_str(PHRASE).unwrap();
+        assert_eq!(suri.path, "");
+        assert!(suri.junctions.is_empty());
+        assert!(Key::parse_path("polkadot").is_err());
+        assert!(Key::parse_path("//").is_err());
+        assert!(SecretUri::from_str("//Alice").is_err());
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        for scheme in CryptoScheme::all() {
+            let (pair, seed) = KeyPair::from_phrase(scheme, PHRASE, None).unwrap();
+            let from_seed = KeyPair::from_seed_slice(scheme, &seed).unwrap();
+            assert_eq!(pair.account_id(), from_seed.account_id());
+            let signature = pair.sign(b"uwallet");
+            assert!(signature.verify(&b"uwallet"[..], &pair.account_id()));
+            assert_eq!(scheme, pair.scheme());
+            assert_eq!(scheme, CryptoScheme::from_str(&scheme.to_string()).unwrap());
+        }
+    }
+
 
     #[test]
     fn test() {
@@ -86,4 +364,4 @@ mod test {
         // print the HD wallet seed as a hex string
         println!("{:X}", seed);
     }
-}
\ No newline at end of file
+}
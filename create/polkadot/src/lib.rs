@@ -7,13 +7,17 @@ use codec::{Compact, Decode, Encode};
 use hex::FromHex;
 use mainnet_runtime::{SignedExtra, UncheckedExtrinsic};
 use serde::{Deserialize, Serialize};
-use sp_core::{Pair, ed25519};
+use sp_core::Pair;
 use sp_core::crypto::Ss58AddressFormat;
 use sp_runtime::AnySignature;
 use sp_runtime::generic::Era;
 
 pub use extrinsic::*;
 pub use extrinsic_params::*;
+pub use error::{Error, RpcError};
+pub use keys::{CryptoScheme, KeyPair};
+pub use decode::{decode_extrinsic, DecodedExtrinsic};
+pub use balance::{Amount, AmountError, Rounding};
 
 pub mod extrinsic;
 pub mod compose;
@@ -21,26 +25,42 @@ pub mod extrinsic_params;
 pub mod keys;
 pub mod rpc;
 pub mod client;
-
-
-pub fn signed_extrinsic(hash: String, seed: String, to: String, amount: u128, nonce: u32, spec_version: u32, transaction_version: u32, network_id: u16, module_index: u8, call_index: u8) -> Result<String> {
+pub mod error;
+pub mod metadata;
+pub mod value;
+pub mod tracker;
+pub mod decode;
+pub mod events;
+pub mod history;
+pub mod balance;
+pub mod address;
+pub mod transfer;
+pub mod utility;
+pub mod staking;
+pub mod pools;
+pub mod payout;
+pub mod session;
+pub mod multisig;
+
+
+/// `seed` is either a hex seed (`0x...`) or a full secret uri (`phrase//hard/soft///password`).
+/// `tx_params` carries the era (see [`BaseExtrinsicParamsBuilder::era`]) and the tip.
+/// `to` is an address of any network or a `0x` public key, see [`address::check_address`] to
+/// hold it to one network.
+pub fn signed_extrinsic(scheme: CryptoScheme, hash: String, seed: String, to: String, amount: u128, nonce: u32, spec_version: u32, transaction_version: u32, module_index: u8, call_index: u8, tx_params: PlainTipExtrinsicParamsBuilder) -> Result<String> {
     // println!("{:?},{:?},{:?},{:?},{:?},{:?},{:?},{:?}",hash,seed,to,amount,nonce,spec_version,transaction_version,network_id);
 
-    let pair = ed25519::Pair::from_string(seed.as_str(), None).map_err(|e| anyhow!("gen pair error {:?}",e))?;
-    sp_core::crypto::set_default_ss58_version(Ss58AddressFormat::custom(network_id));
-    let to_addr = AccountId::from_str(to.as_str()).map_err(|e| anyhow!("gen to addr error {:?}",e))?;
-    let address = GenericAddress::Id(to_addr);
-
-    let genesis_hash = sp_core::H256::from_str(hash.as_str()).map_err(|e| anyhow!("parse hash error {:?}",e))?;
-
+    let to_addr = address::parse_address(&to).map_err(|e| anyhow!("invalid destination {}: {}", to, e))?;
+    let address = GenericAddress::Id(to_addr.account);
     let call = ([module_index, call_index], address, Compact(amount));
+    signed_call(scheme, hash, seed, call, nonce, spec_version, transaction_version, tx_params)
+}
 
-    let tx_params = PlainTipExtrinsicParamsBuilder::default();
+/// Sign any call encoded like `([pallet_index, call_index], args..)`.
+pub fn signed_call<C: Encode + Clone>(scheme: CryptoScheme, hash: String, seed: String, call: C, nonce: u32, spec_version: u32, transaction_version: u32, tx_params: PlainTipExtrinsicParamsBuilder) -> Result<String> {
+    let pair = KeyPair::from_string(scheme, seed.as_str(), None)?;
+    let genesis_hash = sp_core::H256::from_str(hash.as_str()).map_err(|e| anyhow!("parse hash error {:?}",e))?;
 
-    // other
-    // let tx_params = PlainTipExtrinsicParamsBuilder::new()
-    //     .era(Era::mortal(period, h.number.into()), head)
-    //     .tip(0);
     let extrinsic_params = PlainTipExtrinsicParams::new(
         spec_version,
         transaction_version,
@@ -68,15 +88,70 @@ mod test {
         let seed = "0x6b9e9bcc6c7f5a2a351bb81a848855899f4e481da3cca78046fefbed6bd11789".to_string();
         let to = "14E5nqKAp3oAJcmzgZhUD2RcptBeUBScxKHgJKU4HPNcKVf3".to_string();
 
-        let result = signed_extrinsic(hash, seed, to, 100,0,9360,19,0,5,0).unwrap();
+        let result = signed_extrinsic(CryptoScheme::Ed25519, hash.clone(), seed.clone(), to.clone(), 100,0,9360,19,5,0, PlainTipExtrinsicParamsBuilder::default()).unwrap();
         println!("{:?}", result);
 
+        for scheme in CryptoScheme::all() {
+            let result = signed_extrinsic(scheme, hash.clone(), seed.clone(), to.clone(), 100, 0, 9360, 19, 5, 0, PlainTipExtrinsicParamsBuilder::default()).unwrap();
+            let raw = hex::decode(result.replace("\"", "").strip_prefix("0x").unwrap()).unwrap();
+            let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, Compact<u128>), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+            let (signer, signature, _) = xt.signature.unwrap();
+            let pair = KeyPair::from_string(scheme, &seed, None).unwrap();
+            assert_eq!(signer, GenericAddress::Id(pair.account_id()));
+            match (scheme, signature) {
+                (CryptoScheme::Sr25519, sp_runtime::MultiSignature::Sr25519(_)) => {}
+                (CryptoScheme::Ed25519, sp_runtime::MultiSignature::Ed25519(_)) => {}
+                (CryptoScheme::Ecdsa, sp_runtime::MultiSignature::Ecdsa(_)) => {}
+                _ => panic!("signature does not match scheme {}", scheme),
+            }
+        }
+
+    }
+
+    #[test]
+    fn test_mortal_ext() {
+        let hash = "0x0221153ac1bfb2f2dd3e7f753b02962ae4bd6822d836db65ad65acd4579b7b92".to_string();
+        let seed = "0x6b9e9bcc6c7f5a2a351bb81a848855899f4e481da3cca78046fefbed6bd11789".to_string();
+        let to = "14E5nqKAp3oAJcmzgZhUD2RcptBeUBScxKHgJKU4HPNcKVf3".to_string();
+        let checkpoint = sp_core::H256::from([7u8; 32]);
+        let tx_params = PlainTipExtrinsicParamsBuilder::new().era(Era::mortal(64, 1000), checkpoint);
+
+        let result = signed_extrinsic(CryptoScheme::Sr25519, hash, seed, to, 100, 0, 9360, 19, 5, 0, tx_params).unwrap();
+        let raw = hex::decode(result.replace("\"", "").strip_prefix("0x").unwrap()).unwrap();
+        let xt: UncheckedExtrinsicV4<([u8; 2], GenericAddress, Compact<u128>), SubstrateDefaultSignedExtra<PlainTip>> = Decode::decode(&mut raw.as_slice()).unwrap();
+        let (_, _, extra) = xt.signature.unwrap();
+        assert_eq!(extra.era, Era::mortal(64, 1000));
+        assert_eq!(extra.era.birth(1000), 1000);
+        assert!(!extra.era.is_immortal());
     }
 
     #[test]
     fn test_decode_extrinsic() {
-        // let raw = "c1018400fadda0af24a7e6d0bba8d3dd0615915b654d68e8735e213fb37577fc681b3d27010c788166b53ce53f0ec566c950d098b47fc7545ab8b9479dd10f48c84222cd722d5e4d2b3463f1995a336cd0684e2bd16c7979641c5c4a54913de9d8826bf98f4703040009130b0030ef7dba02".to_string();
-        // let res = decode_extrinsic(raw).unwrap();
-        // println!("{:?}", res);
+        use sp_core::crypto::Ss58Codec;
+
+        use crate::metadata::{fixture, Metadata};
+        use crate::value::Value;
+
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let hash = "0x0221153ac1bfb2f2dd3e7f753b02962ae4bd6822d836db65ad65acd4579b7b92".to_string();
+        let seed = "0x6b9e9bcc6c7f5a2a351bb81a848855899f4e481da3cca78046fefbed6bd11789".to_string();
+        let to = "14E5nqKAp3oAJcmzgZhUD2RcptBeUBScxKHgJKU4HPNcKVf3".to_string();
+        let params = PlainTipExtrinsicParamsBuilder::new()
+            .era(Era::mortal(64, 1000), sp_core::H256::from_str(&hash).unwrap())
+            .tip(PlainTip::new(500));
+        let raw = signed_extrinsic(CryptoScheme::Sr25519, hash, seed.clone(), to.clone(), 12_345, 7, 9360, 19, 5, 0, params).unwrap();
+
+        let res = decode_extrinsic(&metadata, &raw).unwrap();
+        let signed = res.signed.clone().unwrap();
+        let from = KeyPair::from_string(CryptoScheme::Sr25519, &seed, None).unwrap().account_id().to_ss58check_with_version(Ss58AddressFormat::custom(0));
+        assert_eq!(signed.signer, from);
+        assert_eq!((signed.nonce, signed.tip, signed.era), (7, 500, Era::mortal(64, 1000)));
+        assert_eq!(signed.signature.variant_name(), Some("Sr25519"));
+        assert_eq!(signed.extensions.len(), 8);
+        assert_eq!((res.pallet.as_str(), res.call.as_str()), ("Balances", "transfer"));
+        let dest = AccountId::from_str(&to).unwrap();
+        assert_eq!(res.arg("dest").unwrap().at(0).and_then(Value::as_bytes), Some(dest.as_ref()));
+        assert_eq!(res.arg("value"), Some(&Value::U128(12_345)));
+        assert_eq!(res.to_string(), format!("Balances.transfer(dest: {}, value: 12345) signed by {}, nonce 7, tip 500, mortal for 64 blocks from phase 40", to, from));
     }
 }
@@ -0,0 +1,944 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, bail, Result};
+use codec::{Decode, Encode};
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed, META_RESERVED};
+use frame_metadata::v14::{self, StorageEntryType};
+use scale_info::form::PortableForm;
+use scale_info::{PortableRegistry, TypeDef};
+
+use crate::rpc::storage::{StorageHasher, StorageKey, StorageMap, StorageValue};
+
+/// Runtime metadata decoded from `state_getMetadata`, indexed by pallet name so calls,
+/// storage keys and constants are resolved by name instead of hard-coded indices.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    /// Metadata version, 14 or 15.
+    pub version: u32,
+    pub types: PortableRegistry,
+    pub extrinsic_version: u8,
+    /// Types of the signer address and signature of signed extrinsics.
+    pub address_ty: Option<u32>,
+    pub signature_ty: Option<u32>,
+    /// Signed extensions in the order the runtime expects them.
+    pub signed_extensions: Vec<SignedExtension>,
+    pallets: BTreeMap<String, PalletMetadata>,
+    pallet_names: HashMap<u8, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PalletMetadata {
+    pub name: String,
+    pub index: u8,
+    /// Call name to call index.
+    pub calls: HashMap<String, u8>,
+    pub call_ty: Option<u32>,
+    pub event_ty: Option<u32>,
+    pub error_ty: Option<u32>,
+    pub storage_prefix: String,
+    pub storage: HashMap<String, StorageEntry>,
+    pub constants: HashMap<String, Constant>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageModifier {
+    Optional,
+    Default,
+}
+
+#[derive(Clone, Debug)]
+pub struct StorageEntry {
+    pub name: String,
+    pub modifier: StorageModifier,
+    /// Empty for plain storage values.
+    pub hashers: Vec<StorageHasher>,
+    pub key_ty: Option<u32>,
+    pub value_ty: u32,
+    pub default: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SignedExtension {
+    pub identifier: String,
+    /// Type of the extra data in the extrinsic, like the era or the nonce.
+    pub ty: u32,
+    /// Type of the data only included in the signed payload, like the genesis hash.
+    pub additional_signed: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Constant {
+    pub name: String,
+    pub ty: u32,
+    pub value: Vec<u8>,
+}
+
+
+impl Metadata {
+    pub fn from_hex(data: &str) -> Result<Self> {
+        let bytes = hex::decode(data.trim_start_matches("0x"))?;
+        Metadata::decode(&bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let prefixed = RuntimeMetadataPrefixed::decode(&mut &bytes[..])?;
+        Metadata::try_from(prefixed)
+    }
+
+    pub fn pallet(&self, name: &str) -> Result<&PalletMetadata> {
+        self.pallets.get(name).ok_or(anyhow!("pallet {} not found in metadata", name))
+    }
+
+    pub fn pallet_by_index(&self, index: u8) -> Result<&PalletMetadata> {
+        let name = self.pallet_names.get(&index).ok_or(anyhow!("pallet index {} not found in metadata", index))?;
+        self.pallet(name)
+    }
+
+    pub fn pallets(&self) -> impl Iterator<Item=&PalletMetadata> {
+        self.pallets.values()
+    }
+
+    /// `[pallet_index, call_index]` of a call, as the first two bytes of an encoded call.
+    pub fn call_index(&self, pallet: &str, call: &str) -> Result<[u8; 2]> {
+        let pallet = self.pallet(pallet)?;
+        let call_index = pallet.calls.get(call).ok_or(anyhow!("call {}::{} not found in metadata", pallet.name, call))?;
+        Ok([pallet.index, *call_index])
+    }
+
+    /// Argument names of a call, to tell apart runtimes that changed its signature.
+    pub fn call_args(&self, pallet: &str, call: &str) -> Result<Vec<String>> {
+        let variant = self.call_variant(pallet, call)?;
+        Ok(variant.fields().iter().filter_map(|field| field.name().cloned()).collect())
+    }
+
+    /// Type of the argument `arg` of a call, for arguments typed by the runtime like session keys.
+    pub fn call_arg_ty(&self, pallet: &str, call: &str, arg: &str) -> Result<u32> {
+        let variant = self.call_variant(pallet, call)?;
+        let field = variant.fields().iter().find(|field| field.name().map(String::as_str) == Some(arg))
+            .ok_or(anyhow!("call {}::{} has no argument {}", pallet, call, arg))?;
+        Ok(field.ty().id())
+    }
+
+    fn call_variant(&self, pallet: &str, call: &str) -> Result<&scale_info::Variant<PortableForm>> {
+        let pallet = self.pallet(pallet)?;
+        let call_ty = pallet.call_ty.ok_or(anyhow!("pallet {} has no calls", pallet.name))?;
+        variants(&self.types, call_ty)?.iter().find(|variant| variant.name() == call)
+            .ok_or(anyhow!("call {}::{} not found in metadata", pallet.name, call))
+    }
+
+    pub fn storage_entry(&self, pallet: &str, entry: &str) -> Result<&StorageEntry> {
+        let pallet = self.pallet(pallet)?;
+        pallet.storage.get(entry).ok_or(anyhow!("storage {}::{} not found in metadata", pallet.name, entry))
+    }
+
+    /// Key of a plain storage value.
+    pub fn storage_value_key(&self, pallet: &str, entry: &str) -> Result<StorageKey> {
+        let pallet = self.pallet(pallet)?;
+        let storage = pallet.storage.get(entry).ok_or(anyhow!("storage {}::{} not found in metadata", pallet.name, entry))?;
+        Ok(StorageValue::new(&pallet.storage_prefix, &storage.name).key())
+    }
+
+    /// Key of a single-key storage map, hashed with the hasher the runtime declares.
+    pub fn storage_map_key<K: Encode>(&self, pallet: &str, entry: &str, key: K) -> Result<StorageKey> {
+        let pallet = self.pallet(pallet)?;
+        let storage = pallet.storage.get(entry).ok_or(anyhow!("storage {}::{} not found in metadata", pallet.name, entry))?;
+        let hasher = storage.hashers.first().ok_or(anyhow!("storage {}::{} is not a map", pallet.name, entry))?;
+        Ok(StorageMap::new(&pallet.storage_prefix, &storage.name, hasher.clone()).key(key))
+    }
+
+    /// Key of a double map, each key hashed with its own hasher.
+    pub fn storage_double_map_key<K1: Encode, K2: Encode>(&self, pallet: &str, entry: &str, key1: K1, key2: K2) -> Result<StorageKey> {
+        let pallet = self.pallet(pallet)?;
+        let storage = pallet.storage.get(entry).ok_or(anyhow!("storage {}::{} not found in metadata", pallet.name, entry))?;
+        if storage.hashers.len() != 2 {
+            bail!("storage {}::{} is not a double map", pallet.name, entry);
+        }
+        let mut key = StorageMap::new(&pallet.storage_prefix, &storage.name, storage.hashers[0].clone()).key(key1);
+        key.0.extend(crate::rpc::storage::key_hash(&key2, &storage.hashers[1]));
+        Ok(key)
+    }
+
+    pub fn constant(&self, pallet: &str, name: &str) -> Result<&Constant> {
+        let pallet = self.pallet(pallet)?;
+        pallet.constants.get(name).ok_or(anyhow!("constant {}::{} not found in metadata", pallet.name, name))
+    }
+
+    pub fn constant_value<T: Decode>(&self, pallet: &str, name: &str) -> Result<T> {
+        let constant = self.constant(pallet, name)?;
+        Ok(T::decode(&mut constant.value.as_slice())?)
+    }
+}
+
+
+impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
+    type Error = anyhow::Error;
+
+    fn try_from(prefixed: RuntimeMetadataPrefixed) -> Result<Self> {
+        if prefixed.0 != META_RESERVED {
+            bail!("invalid metadata magic number");
+        }
+        let version = prefixed.1.version();
+        let (types, pallets, extrinsic_version, signed_extensions, address_ty, signature_ty) = match prefixed.1 {
+            RuntimeMetadata::V14(metadata) => {
+                let extensions = metadata.extrinsic.signed_extensions.iter().map(|e| SignedExtension {
+                    identifier: e.identifier.clone(),
+                    ty: e.ty.id(),
+                    additional_signed: e.additional_signed.id(),
+                }).collect();
+                // V14 only names the extrinsic type, address and signature are its type parameters
+                let param = |name: &str| metadata.types.resolve(metadata.extrinsic.ty.id())
+                    .and_then(|ty| ty.type_params().iter().find(|p| p.name() == name))
+                    .and_then(|p| p.ty().map(|ty| ty.id()));
+                let (address_ty, signature_ty) = (param("Address"), param("Signature"));
+                (metadata.types, metadata.pallets, metadata.extrinsic.version, extensions, address_ty, signature_ty)
+            }
+            RuntimeMetadata::V15(metadata) => {
+                let extensions = metadata.extrinsic.signed_extensions.iter().map(|e| SignedExtension {
+                    identifier: e.identifier.clone(),
+                    ty: e.ty.id(),
+                    additional_signed: e.additional_signed.id(),
+                }).collect();
+                let (address_ty, signature_ty) = (Some(metadata.extrinsic.address_ty.id()), Some(metadata.extrinsic.signature_ty.id()));
+                // V15 pallets only add docs on top of V14
+                let pallets = metadata.pallets.into_iter().map(|p| v14::PalletMetadata {
+                    name: p.name,
+                    storage: p.storage,
+                    calls: p.calls,
+                    event: p.event,
+                    constants: p.constants,
+                    error: p.error,
+                    index: p.index,
+                }).collect();
+                (metadata.types, pallets, metadata.extrinsic.version, extensions, address_ty, signature_ty)
+            }
+            _ => bail!("unsupported metadata version {}", version),
+        };
+
+        let mut by_name = BTreeMap::new();
+        let mut pallet_names = HashMap::new();
+        for pallet in pallets {
+            let pallet = convert_pallet(&types, pallet)?;
+            pallet_names.insert(pallet.index, pallet.name.clone());
+            by_name.insert(pallet.name.clone(), pallet);
+        }
+        Ok(Metadata {
+            version,
+            types,
+            extrinsic_version,
+            address_ty,
+            signature_ty,
+            signed_extensions,
+            pallets: by_name,
+            pallet_names,
+        })
+    }
+}
+
+
+fn convert_pallet(types: &PortableRegistry, pallet: v14::PalletMetadata<PortableForm>) -> Result<PalletMetadata> {
+    let mut calls = HashMap::new();
+    let call_ty = pallet.calls.as_ref().map(|c| c.ty.id());
+    if let Some(ty) = call_ty {
+        for variant in variants(types, ty)? {
+            calls.insert(variant.name().clone(), variant.index());
+        }
+    }
+
+    let mut storage = HashMap::new();
+    let mut storage_prefix = pallet.name.clone();
+    if let Some(pallet_storage) = pallet.storage {
+        storage_prefix = pallet_storage.prefix;
+        for entry in pallet_storage.entries {
+            let modifier = match entry.modifier {
+                v14::StorageEntryModifier::Optional => StorageModifier::Optional,
+                v14::StorageEntryModifier::Default => StorageModifier::Default,
+            };
+            let (hashers, key_ty, value_ty) = match entry.ty {
+                StorageEntryType::Plain(value) => (Vec::new(), None, value.id()),
+                StorageEntryType::Map { hashers, key, value } => {
+                    (hashers.into_iter().map(convert_hasher).collect(), Some(key.id()), value.id())
+                }
+            };
+            storage.insert(entry.name.clone(), StorageEntry {
+                name: entry.name,
+                modifier,
+                hashers,
+                key_ty,
+                value_ty,
+                default: entry.default,
+            });
+        }
+    }
+
+    let constants = pallet.constants.into_iter().map(|c| {
+        (c.name.clone(), Constant { name: c.name, ty: c.ty.id(), value: c.value })
+    }).collect();
+
+    Ok(PalletMetadata {
+        name: pallet.name,
+        index: pallet.index,
+        calls,
+        call_ty,
+        event_ty: pallet.event.map(|e| e.ty.id()),
+        error_ty: pallet.error.map(|e| e.ty.id()),
+        storage_prefix,
+        storage,
+        constants,
+    })
+}
+
+fn convert_hasher(hasher: v14::StorageHasher) -> StorageHasher {
+    match hasher {
+        v14::StorageHasher::Blake2_128 => StorageHasher::Blake2_128,
+        v14::StorageHasher::Blake2_256 => StorageHasher::Blake2_256,
+        v14::StorageHasher::Blake2_128Concat => StorageHasher::Blake2_128Concat,
+        v14::StorageHasher::Twox128 => StorageHasher::Twox128,
+        v14::StorageHasher::Twox256 => StorageHasher::Twox256,
+        v14::StorageHasher::Twox64Concat => StorageHasher::Twox64Concat,
+        v14::StorageHasher::Identity => StorageHasher::Identity,
+    }
+}
+
+/// Variants of an enum type in the registry, e.g. the calls, events or errors of a pallet.
+pub fn variants(types: &PortableRegistry, ty: u32) -> Result<&[scale_info::Variant<PortableForm>]> {
+    let resolved = types.resolve(ty).ok_or(anyhow!("type {} not found in metadata", ty))?;
+    match resolved.type_def() {
+        TypeDef::Variant(variant) => Ok(variant.variants()),
+        _ => Err(anyhow!("type {} is not an enum", ty)),
+    }
+}
+
+
+/// A small runtime built from `scale_info` types, so metadata driven code can be tested offline.
+#[cfg(test)]
+pub(crate) mod fixture {
+    use std::marker::PhantomData;
+
+    use codec::{Compact, Encode};
+    use frame_metadata::RuntimeMetadataPrefixed;
+    use frame_metadata::v14::{
+        ExtrinsicMetadata, PalletCallMetadata, PalletConstantMetadata, PalletErrorMetadata, PalletEventMetadata,
+        PalletMetadata, PalletStorageMetadata, RuntimeMetadataV14, SignedExtensionMetadata, StorageEntryMetadata,
+        StorageEntryModifier, StorageEntryType, StorageHasher,
+    };
+    use scale_info::{meta_type, MetaType, TypeInfo};
+    use sp_core::H256;
+    use sp_runtime::{MultiSignature, Perbill};
+    use sp_runtime::generic::Era;
+
+    use crate::{AccountId, GenericAddress};
+    use crate::multisig::{Multisig, Timepoint};
+    use crate::payout::{Exposure, PagedExposureMetadata};
+    use crate::pools::{PoolMember, PoolState, RewardPool};
+    use crate::rpc::types::{AccountInfo, Weight};
+    use crate::staking::{ActiveEraInfo, Nominations, RewardDestination, SlashingSpans, StakingLedger};
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum BalancesCall {
+        #[codec(index = 0)]
+        transfer { dest: GenericAddress, #[codec(compact)] value: u128 },
+        #[codec(index = 3)]
+        transfer_keep_alive { dest: GenericAddress, #[codec(compact)] value: u128 },
+        #[codec(index = 4)]
+        transfer_all { dest: GenericAddress, keep_alive: bool },
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum SystemCall {
+        #[codec(index = 0)]
+        remark { remark: Vec<u8> },
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum TimestampCall {
+        #[codec(index = 0)]
+        set { #[codec(compact)] now: u64 },
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum UtilityCall {
+        #[codec(index = 0)]
+        batch { calls: Vec<RuntimeCall> },
+        #[codec(index = 2)]
+        batch_all { calls: Vec<RuntimeCall> },
+        #[codec(index = 4)]
+        force_batch { calls: Vec<RuntimeCall> },
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum StakingCall {
+        #[codec(index = 0)]
+        bond { controller: GenericAddress, #[codec(compact)] value: u128, payee: RewardDestination },
+        #[codec(index = 1)]
+        bond_extra { #[codec(compact)] max_additional: u128 },
+        #[codec(index = 2)]
+        unbond { #[codec(compact)] value: u128 },
+        #[codec(index = 3)]
+        withdraw_unbonded { num_slashing_spans: u32 },
+        #[codec(index = 5)]
+        nominate { targets: Vec<GenericAddress> },
+        #[codec(index = 6)]
+        chill,
+        #[codec(index = 7)]
+        set_payee { payee: RewardDestination },
+        #[codec(index = 18)]
+        payout_stakers { validator_stash: AccountId, era: u32 },
+        #[codec(index = 19)]
+        rebond { #[codec(compact)] value: u128 },
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub enum BondExtra {
+        FreeBalance(u128),
+        Rewards,
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum PoolsCall {
+        #[codec(index = 0)]
+        join { #[codec(compact)] amount: u128, pool_id: u32 },
+        #[codec(index = 1)]
+        bond_extra { extra: BondExtra },
+        #[codec(index = 2)]
+        claim_payout,
+        #[codec(index = 3)]
+        unbond { member_account: GenericAddress, #[codec(compact)] unbonding_points: u128 },
+        #[codec(index = 5)]
+        withdraw_unbonded { member_account: GenericAddress, num_slashing_spans: u32 },
+    }
+
+    /// Session keys of a runtime with two of them, each a 32 byte public key.
+    #[derive(Encode, TypeInfo)]
+    pub struct SessionKeys {
+        pub grandpa: [u8; 32],
+        pub babe: [u8; 32],
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum SessionCall {
+        #[codec(index = 0)]
+        set_keys { keys: SessionKeys, proof: Vec<u8> },
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum MultisigCall {
+        #[codec(index = 1)]
+        as_multi { threshold: u16, other_signatories: Vec<AccountId>, maybe_timepoint: Option<Timepoint>, call: Box<RuntimeCall>, max_weight: Weight },
+        #[codec(index = 2)]
+        approve_as_multi { threshold: u16, other_signatories: Vec<AccountId>, maybe_timepoint: Option<Timepoint>, call_hash: [u8; 32], max_weight: Weight },
+        #[codec(index = 3)]
+        cancel_as_multi { threshold: u16, other_signatories: Vec<AccountId>, timepoint: Timepoint, call_hash: [u8; 32] },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum RuntimeCall {
+        #[codec(index = 0)]
+        System(SystemCall),
+        #[codec(index = 2)]
+        Timestamp(TimestampCall),
+        #[codec(index = 5)]
+        Balances(BalancesCall),
+        #[codec(index = 7)]
+        Staking(StakingCall),
+        #[codec(index = 9)]
+        Session(SessionCall),
+        #[codec(index = 26)]
+        Utility(UtilityCall),
+        #[codec(index = 30)]
+        Multisig(MultisigCall),
+        #[codec(index = 39)]
+        NominationPools(PoolsCall),
+    }
+
+    /// Only carries the type parameters V14 metadata describes extrinsics with.
+    #[derive(TypeInfo)]
+    pub struct UncheckedExtrinsic<Address, Call, Signature, Extra>(PhantomData<(Address, Call, Signature, Extra)>);
+
+    #[derive(Encode, TypeInfo)]
+    pub enum Phase {
+        ApplyExtrinsic(u32),
+        Finalization,
+        Initialization,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub struct DispatchInfo {
+        pub weight: u64,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub struct ModuleError {
+        pub index: u8,
+        pub error: [u8; 4],
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum DispatchError {
+        Other,
+        CannotLookup,
+        BadOrigin,
+        Module(ModuleError),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum SystemEvent {
+        ExtrinsicSuccess { dispatch_info: DispatchInfo },
+        ExtrinsicFailed { dispatch_error: DispatchError, dispatch_info: DispatchInfo },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum BalancesEvent {
+        #[codec(index = 2)]
+        Transfer { from: AccountId, to: AccountId, amount: u128 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum BalancesError {
+        VestingBalance,
+        LiquidityRestrictions,
+        InsufficientBalance,
+        ExistentialDeposit,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum TransactionPaymentEvent {
+        TransactionFeePaid { who: AccountId, actual_fee: u128, tip: u128 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum UtilityEvent {
+        BatchInterrupted { index: u32, error: DispatchError },
+        BatchCompleted,
+        BatchCompletedWithErrors,
+        ItemCompleted,
+        ItemFailed { error: DispatchError },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    pub enum RuntimeEvent {
+        #[codec(index = 0)]
+        System(SystemEvent),
+        #[codec(index = 5)]
+        Balances(BalancesEvent),
+        #[codec(index = 26)]
+        Utility(UtilityEvent),
+        #[codec(index = 32)]
+        TransactionPayment(TransactionPaymentEvent),
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub struct EventRecord {
+        pub phase: Phase,
+        pub event: RuntimeEvent,
+        pub topics: Vec<[u8; 32]>,
+    }
+
+    impl EventRecord {
+        pub fn new(phase: Phase, event: RuntimeEvent) -> Self {
+            EventRecord { phase, event, topics: vec![] }
+        }
+    }
+
+    pub const EXISTENTIAL_DEPOSIT: u128 = 10_000_000_000;
+    pub const SS58_PREFIX: u16 = 0;
+    pub const UTILITY_INDEX: u8 = 26;
+    pub const STAKING_INDEX: u8 = 7;
+    pub const BONDING_DURATION: u32 = 28;
+    pub const HISTORY_DEPTH: u32 = 84;
+    pub const POOLS_INDEX: u8 = 39;
+    pub const SESSION_INDEX: u8 = 9;
+    pub const MULTISIG_INDEX: u8 = 30;
+
+    /// `BondedPoolInner` of a runtime with pool commission, its fields in that runtime's order.
+    #[derive(Encode, TypeInfo)]
+    pub struct BondedPoolInner {
+        pub commission: Commission,
+        pub member_counter: u32,
+        pub points: u128,
+        pub roles: PoolRoles,
+        pub state: PoolState,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub struct Commission {
+        pub current: Option<(Perbill, AccountId)>,
+        pub max: Option<Perbill>,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    pub struct PoolRoles {
+        pub depositor: AccountId,
+        pub root: Option<AccountId>,
+        pub nominator: Option<AccountId>,
+        pub bouncer: Option<AccountId>,
+    }
+
+    /// An open pool of two members run by `depositor`, taking 5% commission.
+    pub fn bonded_pool(depositor: AccountId, points: u128) -> BondedPoolInner {
+        BondedPoolInner {
+            commission: Commission { current: Some((Perbill::from_percent(5), depositor.clone())), max: None },
+            member_counter: 2,
+            points,
+            roles: PoolRoles { depositor: depositor.clone(), root: Some(depositor.clone()), nominator: Some(depositor), bouncer: None },
+            state: PoolState::Open,
+        }
+    }
+
+    fn system() -> PalletMetadata {
+        PalletMetadata {
+            name: "System",
+            storage: Some(PalletStorageMetadata {
+                prefix: "System",
+                entries: vec![
+                    StorageEntryMetadata {
+                        name: "Account",
+                        modifier: StorageEntryModifier::Default,
+                        ty: StorageEntryType::Map {
+                            hashers: vec![StorageHasher::Blake2_128Concat],
+                            key: meta_type::<AccountId>(),
+                            value: meta_type::<AccountInfo>(),
+                        },
+                        default: AccountInfo::default().encode(),
+                        docs: vec![],
+                    },
+                    StorageEntryMetadata {
+                        name: "Events",
+                        modifier: StorageEntryModifier::Default,
+                        ty: StorageEntryType::Plain(meta_type::<Vec<EventRecord>>()),
+                        default: vec![0],
+                        docs: vec![],
+                    },
+                    StorageEntryMetadata {
+                        name: "Number",
+                        modifier: StorageEntryModifier::Default,
+                        ty: StorageEntryType::Plain(meta_type::<u32>()),
+                        default: 0u32.encode(),
+                        docs: vec![],
+                    },
+                ],
+            }),
+            calls: Some(PalletCallMetadata { ty: meta_type::<SystemCall>() }),
+            event: Some(PalletEventMetadata { ty: meta_type::<SystemEvent>() }),
+            constants: vec![PalletConstantMetadata {
+                name: "SS58Prefix",
+                ty: meta_type::<u16>(),
+                value: SS58_PREFIX.encode(),
+                docs: vec![],
+            }],
+            error: None,
+            index: 0,
+        }
+    }
+
+    fn balances(index: u8) -> PalletMetadata {
+        PalletMetadata {
+            name: "Balances",
+            storage: None,
+            calls: Some(PalletCallMetadata { ty: meta_type::<BalancesCall>() }),
+            event: Some(PalletEventMetadata { ty: meta_type::<BalancesEvent>() }),
+            constants: vec![PalletConstantMetadata {
+                name: "ExistentialDeposit",
+                ty: meta_type::<u128>(),
+                value: EXISTENTIAL_DEPOSIT.encode(),
+                docs: vec![],
+            }],
+            error: Some(PalletErrorMetadata { ty: meta_type::<BalancesError>() }),
+            index,
+        }
+    }
+
+    fn timestamp() -> PalletMetadata {
+        PalletMetadata {
+            name: "Timestamp",
+            storage: None,
+            calls: Some(PalletCallMetadata { ty: meta_type::<TimestampCall>() }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: 2,
+        }
+    }
+
+    fn staking() -> PalletMetadata {
+        let map = |name, hasher, value: MetaType, modifier| StorageEntryMetadata {
+            name,
+            modifier,
+            ty: StorageEntryType::Map { hashers: vec![hasher], key: meta_type::<AccountId>(), value },
+            default: vec![0],
+            docs: vec![],
+        };
+        // keyed by era and validator stash
+        let era_map = |name, value: MetaType, modifier| StorageEntryMetadata {
+            name,
+            modifier,
+            ty: StorageEntryType::Map {
+                hashers: vec![StorageHasher::Twox64Concat, StorageHasher::Twox64Concat],
+                key: meta_type::<(u32, AccountId)>(),
+                value,
+            },
+            default: vec![0],
+            docs: vec![],
+        };
+        PalletMetadata {
+            name: "Staking",
+            storage: Some(PalletStorageMetadata {
+                prefix: "Staking",
+                entries: vec![
+                    map("Bonded", StorageHasher::Twox64Concat, meta_type::<AccountId>(), StorageEntryModifier::Optional),
+                    map("Ledger", StorageHasher::Blake2_128Concat, meta_type::<StakingLedger>(), StorageEntryModifier::Optional),
+                    map("Payee", StorageHasher::Twox64Concat, meta_type::<RewardDestination>(), StorageEntryModifier::Default),
+                    map("Nominators", StorageHasher::Twox64Concat, meta_type::<Nominations>(), StorageEntryModifier::Optional),
+                    map("SlashingSpans", StorageHasher::Twox64Concat, meta_type::<SlashingSpans>(), StorageEntryModifier::Optional),
+                    era_map("ErasStakers", meta_type::<Exposure>(), StorageEntryModifier::Default),
+                    era_map("ErasStakersOverview", meta_type::<PagedExposureMetadata>(), StorageEntryModifier::Optional),
+                    era_map("ClaimedRewards", meta_type::<Vec<u32>>(), StorageEntryModifier::Default),
+                    StorageEntryMetadata {
+                        name: "ActiveEra",
+                        modifier: StorageEntryModifier::Optional,
+                        ty: StorageEntryType::Plain(meta_type::<ActiveEraInfo>()),
+                        default: vec![0],
+                        docs: vec![],
+                    },
+                ],
+            }),
+            calls: Some(PalletCallMetadata { ty: meta_type::<StakingCall>() }),
+            event: None,
+            constants: vec![
+                PalletConstantMetadata {
+                    name: "BondingDuration",
+                    ty: meta_type::<u32>(),
+                    value: BONDING_DURATION.encode(),
+                    docs: vec![],
+                },
+                PalletConstantMetadata {
+                    name: "HistoryDepth",
+                    ty: meta_type::<u32>(),
+                    value: HISTORY_DEPTH.encode(),
+                    docs: vec![],
+                },
+            ],
+            error: None,
+            index: STAKING_INDEX,
+        }
+    }
+
+    fn session() -> PalletMetadata {
+        PalletMetadata {
+            name: "Session",
+            storage: Some(PalletStorageMetadata {
+                prefix: "Session",
+                entries: vec![StorageEntryMetadata {
+                    name: "NextKeys",
+                    modifier: StorageEntryModifier::Optional,
+                    ty: StorageEntryType::Map {
+                        hashers: vec![StorageHasher::Twox64Concat],
+                        key: meta_type::<AccountId>(),
+                        value: meta_type::<SessionKeys>(),
+                    },
+                    default: vec![0],
+                    docs: vec![],
+                }],
+            }),
+            calls: Some(PalletCallMetadata { ty: meta_type::<SessionCall>() }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: SESSION_INDEX,
+        }
+    }
+
+    fn nomination_pools() -> PalletMetadata {
+        let map = |name, key: MetaType, value: MetaType, modifier| StorageEntryMetadata {
+            name,
+            modifier,
+            ty: StorageEntryType::Map { hashers: vec![StorageHasher::Twox64Concat], key, value },
+            default: vec![0],
+            docs: vec![],
+        };
+        PalletMetadata {
+            name: "NominationPools",
+            storage: Some(PalletStorageMetadata {
+                prefix: "NominationPools",
+                entries: vec![
+                    StorageEntryMetadata {
+                        name: "LastPoolId",
+                        modifier: StorageEntryModifier::Default,
+                        ty: StorageEntryType::Plain(meta_type::<u32>()),
+                        default: 0u32.encode(),
+                        docs: vec![],
+                    },
+                    map("BondedPools", meta_type::<u32>(), meta_type::<BondedPoolInner>(), StorageEntryModifier::Optional),
+                    map("RewardPools", meta_type::<u32>(), meta_type::<RewardPool>(), StorageEntryModifier::Optional),
+                    map("Metadata", meta_type::<u32>(), meta_type::<Vec<u8>>(), StorageEntryModifier::Default),
+                    map("PoolMembers", meta_type::<AccountId>(), meta_type::<PoolMember>(), StorageEntryModifier::Optional),
+                ],
+            }),
+            calls: Some(PalletCallMetadata { ty: meta_type::<PoolsCall>() }),
+            event: None,
+            constants: vec![PalletConstantMetadata {
+                name: "PalletId",
+                ty: meta_type::<[u8; 8]>(),
+                value: b"py/nopls".encode(),
+                docs: vec![],
+            }],
+            error: None,
+            index: POOLS_INDEX,
+        }
+    }
+
+    fn utility() -> PalletMetadata {
+        PalletMetadata {
+            name: "Utility",
+            storage: None,
+            calls: Some(PalletCallMetadata { ty: meta_type::<UtilityCall>() }),
+            event: Some(PalletEventMetadata { ty: meta_type::<UtilityEvent>() }),
+            constants: vec![],
+            error: None,
+            index: UTILITY_INDEX,
+        }
+    }
+
+    fn multisig() -> PalletMetadata {
+        PalletMetadata {
+            name: "Multisig",
+            storage: Some(PalletStorageMetadata {
+                prefix: "Multisig",
+                entries: vec![StorageEntryMetadata {
+                    name: "Multisigs",
+                    modifier: StorageEntryModifier::Optional,
+                    // keyed by multisig account and call hash
+                    ty: StorageEntryType::Map {
+                        hashers: vec![StorageHasher::Twox64Concat, StorageHasher::Blake2_128Concat],
+                        key: meta_type::<(AccountId, [u8; 32])>(),
+                        value: meta_type::<Multisig>(),
+                    },
+                    default: vec![0],
+                    docs: vec![],
+                }],
+            }),
+            calls: Some(PalletCallMetadata { ty: meta_type::<MultisigCall>() }),
+            event: None,
+            constants: vec![],
+            error: None,
+            index: MULTISIG_INDEX,
+        }
+    }
+
+    fn transaction_payment() -> PalletMetadata {
+        PalletMetadata {
+            name: "TransactionPayment",
+            storage: None,
+            calls: None,
+            event: Some(PalletEventMetadata { ty: meta_type::<TransactionPaymentEvent>() }),
+            constants: vec![],
+            error: None,
+            index: 32,
+        }
+    }
+
+    /// Encoded `System::Events`: a transfer with its fee in extrinsic 1 and a failed extrinsic 2.
+    pub fn events() -> Vec<u8> {
+        vec![
+            EventRecord::new(Phase::ApplyExtrinsic(0), RuntimeEvent::System(SystemEvent::ExtrinsicSuccess { dispatch_info: DispatchInfo { weight: 1 } })),
+            EventRecord::new(Phase::ApplyExtrinsic(1), RuntimeEvent::Balances(BalancesEvent::Transfer { from: [1u8; 32].into(), to: [2u8; 32].into(), amount: 10 })),
+            EventRecord::new(Phase::ApplyExtrinsic(1), RuntimeEvent::TransactionPayment(TransactionPaymentEvent::TransactionFeePaid { who: [1u8; 32].into(), actual_fee: 159_154_001, tip: 0 })),
+            EventRecord::new(Phase::ApplyExtrinsic(1), RuntimeEvent::System(SystemEvent::ExtrinsicSuccess { dispatch_info: DispatchInfo { weight: 2 } })),
+            EventRecord::new(Phase::ApplyExtrinsic(2), RuntimeEvent::System(SystemEvent::ExtrinsicFailed {
+                dispatch_error: DispatchError::Module(ModuleError { index: 5, error: [2, 0, 0, 0] }),
+                dispatch_info: DispatchInfo { weight: 3 },
+            })),
+            EventRecord::new(Phase::Finalization, RuntimeEvent::System(SystemEvent::ExtrinsicSuccess { dispatch_info: DispatchInfo { weight: 4 } })),
+        ].encode()
+    }
+
+    /// The extensions `PlainTipExtrinsicParams` signs for.
+    fn signed_extensions() -> Vec<SignedExtensionMetadata> {
+        let extension = |identifier, ty: MetaType, additional_signed: MetaType| SignedExtensionMetadata { identifier, ty, additional_signed };
+        vec![
+            extension("CheckNonZeroSender", meta_type::<()>(), meta_type::<()>()),
+            extension("CheckSpecVersion", meta_type::<()>(), meta_type::<u32>()),
+            extension("CheckTxVersion", meta_type::<()>(), meta_type::<u32>()),
+            extension("CheckGenesis", meta_type::<()>(), meta_type::<H256>()),
+            extension("CheckMortality", meta_type::<Era>(), meta_type::<H256>()),
+            extension("CheckNonce", meta_type::<Compact<u32>>(), meta_type::<()>()),
+            extension("CheckWeight", meta_type::<()>(), meta_type::<()>()),
+            extension("ChargeTransactionPayment", meta_type::<Compact<u128>>(), meta_type::<()>()),
+        ]
+    }
+
+    /// Encoded `RuntimeMetadataPrefixed` with Balances at `balances_index`.
+    pub fn metadata_with(balances_index: u8) -> Vec<u8> {
+        let pallets = vec![system(), timestamp(), balances(balances_index), staking(), session(), utility(), multisig(), nomination_pools(), transaction_payment()];
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<UncheckedExtrinsic<GenericAddress, RuntimeCall, MultiSignature, (Era, Compact<u32>, Compact<u128>)>>(),
+            version: 4,
+            signed_extensions: signed_extensions(),
+        };
+        let metadata: RuntimeMetadataPrefixed = RuntimeMetadataV14::new(pallets, extrinsic, meta_type::<()>()).into();
+        metadata.encode()
+    }
+
+    pub fn metadata() -> Vec<u8> {
+        metadata_with(5)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use sp_core::crypto::AccountId32;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        assert_eq!(metadata.version, 14);
+        assert_eq!(metadata.call_index("Balances", "transfer").unwrap(), [5, 0]);
+        assert_eq!(metadata.call_index("Balances", "transfer_keep_alive").unwrap(), [5, 3]);
+        assert!(metadata.call_index("Balances", "unknown").is_err());
+        assert!(metadata.pallet("Unknown").is_err());
+        assert_eq!(metadata.pallet_by_index(5).unwrap().name, "Balances");
+        assert_eq!(metadata.constant_value::<u128>("Balances", "ExistentialDeposit").unwrap(), fixture::EXISTENTIAL_DEPOSIT);
+        assert!(metadata.address_ty.is_some() && metadata.signature_ty.is_some());
+        assert_eq!(metadata.signed_extensions[4].identifier, "CheckMortality");
+
+        let entry = metadata.storage_entry("System", "Account").unwrap();
+        assert_eq!(entry.hashers, vec![StorageHasher::Blake2_128Concat]);
+        assert_eq!(entry.modifier, StorageModifier::Default);
+        let account = AccountId32::new([1u8; 32]);
+        let key = metadata.storage_map_key("System", "Account", &account).unwrap();
+        let expect = StorageMap::new("System", "Account", StorageHasher::Blake2_128Concat).key(&account);
+        assert_eq!(key.0, expect.0);
+        assert!(metadata.storage_map_key("System", "Number", 0u32).is_err());
+    }
+
+    #[test]
+    fn test_reordered_pallets() {
+        let metadata = Metadata::from_hex(&hex::encode(fixture::metadata_with(10))).unwrap();
+        assert_eq!(metadata.call_index("Balances", "transfer").unwrap(), [10, 0]);
+    }
+
+    #[test]
+    fn test_compose_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let call = crate::compose_call!(metadata, "Balances", "transfer", 1u8);
+        assert_eq!(call.0, [5, 0]);
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::{anyhow, bail, Result};
+use codec::{Compact, Decode, Encode};
+use scale_info::{TypeDef, TypeDefPrimitive, TypeInfo};
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::AccountId32;
+use sp_core::hashing::blake2_256;
+
+use crate::EncodedCall;
+use crate::address;
+use crate::metadata::Metadata;
+use crate::rpc::types::Weight;
+
+/// Version of the [`MultisigProposal`] files this wallet writes, newer ones are refused.
+pub const PROPOSAL_VERSION: u32 = 1;
+
+/// Used when the multisig address carries no prefix.
+const DEFAULT_SS58_PREFIX: u16 = 42;
+
+/// Block and extrinsic index of the first approval, which with the call hash identify a
+/// multisig operation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct Timepoint {
+    pub height: u32,
+    pub index: u32,
+}
+
+/// `Multisig::Multisigs` of an operation waiting for approvals.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct Multisig {
+    pub when: Timepoint,
+    /// Reserved from the depositor until the operation executes or is cancelled.
+    pub deposit: u128,
+    pub depositor: AccountId32,
+    /// Signatories that approved, sorted.
+    pub approvals: Vec<AccountId32>,
+}
+
+/// A pending operation of a multisig account, see `Client::pending_multisigs`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PendingMultisig {
+    pub call_hash: [u8; 32],
+    pub multisig: Multisig,
+}
+
+/// Account of `signatories` with `threshold`, like `pallet_multisig::multi_account_id`. The order
+/// of the signatories does not matter.
+pub fn multi_account_id(signatories: &[AccountId32], threshold: u16) -> AccountId32 {
+    let mut signatories = signatories.to_vec();
+    signatories.sort();
+    let entropy = (b"modlpy/utilisuba", signatories, threshold).using_encoded(blake2_256);
+    AccountId32::new(entropy)
+}
+
+/// Accounts of `signatories`, sorted as the multisig calls take them. An account listed twice is
+/// an error.
+pub fn signatory_accounts(signatories: &[String]) -> Result<Vec<AccountId32>> {
+    let mut accounts = signatories.iter().map(|addr| parse(addr)).collect::<Result<Vec<_>>>()?;
+    accounts.sort();
+    if accounts.windows(2).any(|pair| pair[0] == pair[1]) {
+        bail!("a signatory is listed twice");
+    }
+    Ok(accounts)
+}
+
+fn check_threshold(threshold: u16, signatories: usize) -> Result<()> {
+    if threshold < 2 || threshold as usize > signatories {
+        bail!("threshold must be between 2 and the {} signatories, not {}", signatories, threshold);
+    }
+    Ok(())
+}
+
+fn parse(addr: &str) -> Result<AccountId32> {
+    Ok(address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account)
+}
+
+
+/// A multisig operation as a portable JSON file. Signatories pass it around to check and approve
+/// the same call without a shared server, the call data only has to be on chain for the final
+/// approval. Timepoint and approvals are what the file's writer last saw on chain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultisigProposal {
+    pub version: u32,
+    /// Of the chain the call is for.
+    pub genesis_hash: String,
+    pub multisig: String,
+    pub threshold: u16,
+    /// Sorted by account.
+    pub signatories: Vec<String>,
+    /// `0x` hex of the encoded call.
+    pub call_data: String,
+    /// `0x` hex blake2_256 of the call data, what approvals refer to.
+    pub call_hash: String,
+    /// The call as decoded by the writer, decode the call data again rather than trust it.
+    pub description: String,
+    /// Weight the runtime charges for the call, the executing `as_multi` allows no more.
+    pub max_weight: Weight,
+    /// `None` until the first approval is in a block.
+    pub timepoint: Option<Timepoint>,
+    pub approvals: Vec<String>,
+}
+
+impl MultisigProposal {
+    pub fn new(genesis_hash: String, ss58_prefix: u16, signatories: &[String], threshold: u16, call: &EncodedCall, description: String, max_weight: Weight) -> Result<Self> {
+        let accounts = signatory_accounts(signatories)?;
+        check_threshold(threshold, accounts.len())?;
+        Ok(MultisigProposal {
+            version: PROPOSAL_VERSION,
+            genesis_hash,
+            multisig: address::to_ss58(&multi_account_id(&accounts, threshold), ss58_prefix),
+            threshold,
+            signatories: accounts.iter().map(|account| address::to_ss58(account, ss58_prefix)).collect(),
+            call_data: format!("0x{}", hex::encode(&call.0)),
+            call_hash: format!("0x{}", hex::encode(blake2_256(&call.0))),
+            description,
+            max_weight,
+            timepoint: None,
+            approvals: vec![],
+        })
+    }
+
+    /// Read a file written by `to_json`, see `check`.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let proposal: MultisigProposal = serde_json::from_str(json)?;
+        proposal.check()?;
+        Ok(proposal)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// That this wallet can read the file and it holds together: the multisig is the account of
+    /// the signatories and threshold, the call hash that of the call data.
+    pub fn check(&self) -> Result<()> {
+        if self.version > PROPOSAL_VERSION {
+            bail!("proposal version {} is newer than the supported {}", self.version, PROPOSAL_VERSION);
+        }
+        let accounts = signatory_accounts(&self.signatories)?;
+        check_threshold(self.threshold, accounts.len())?;
+        if parse(&self.multisig)? != multi_account_id(&accounts, self.threshold) {
+            bail!("{} is not the multisig of these signatories and threshold", self.multisig);
+        }
+        if blake2_256(&self.call_data()?) != self.call_hash()? {
+            bail!("call hash does not match the call data");
+        }
+        Ok(())
+    }
+
+    pub fn call_data(&self) -> Result<Vec<u8>> {
+        hex::decode(self.call_data.trim_start_matches("0x")).map_err(|e| anyhow!("invalid call data: {}", e))
+    }
+
+    pub fn call_hash(&self) -> Result<[u8; 32]> {
+        let hash = hex::decode(self.call_hash.trim_start_matches("0x")).map_err(|e| anyhow!("invalid call hash: {}", e))?;
+        <[u8; 32]>::try_from(hash).map_err(|_| anyhow!("call hash is not 32 bytes"))
+    }
+
+    /// Signatories other than `signer`, sorted, as the multisig calls of `signer` take them.
+    pub fn other_signatories(&self, signer: &str) -> Result<Vec<AccountId32>> {
+        let signer = parse(signer)?;
+        let accounts = signatory_accounts(&self.signatories)?;
+        if !accounts.contains(&signer) {
+            bail!("{} is not a signatory of {}", address::to_ss58(&signer, self.ss58_prefix()), self.multisig);
+        }
+        Ok(accounts.into_iter().filter(|account| *account != signer).collect())
+    }
+
+    pub fn is_approved_by(&self, addr: &str) -> bool {
+        match parse(addr) {
+            Ok(account) => self.approvals.iter().any(|approval| parse(approval).ok() == Some(account.clone())),
+            Err(_) => false,
+        }
+    }
+
+    /// Approvals still needed before the call can execute.
+    pub fn approvals_needed(&self) -> u16 {
+        self.threshold.saturating_sub(self.approvals.len() as u16)
+    }
+
+    /// Take timepoint and approvals of the operation on chain. `None` means it is not pending:
+    /// not approved yet, or already executed or cancelled.
+    pub fn update(&mut self, pending: Option<&Multisig>) {
+        let prefix = self.ss58_prefix();
+        self.timepoint = pending.map(|multisig| multisig.when);
+        self.approvals = pending.map(|multisig| multisig.approvals.iter().map(|account| address::to_ss58(account, prefix)).collect()).unwrap_or_default();
+    }
+
+    fn ss58_prefix(&self) -> u16 {
+        address::parse_address(&self.multisig).ok().and_then(|address| address.prefix).unwrap_or(DEFAULT_SS58_PREFIX)
+    }
+}
+
+
+/// A `Multisig` call of a signatory for a proposal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum MultisigAction {
+    /// `approve_as_multi`, approves the call hash and keeps the call data off chain. The first
+    /// approval opens the operation and reserves the deposit.
+    Approve,
+    /// `as_multi` with the call data, which executes the call when this approval reaches the
+    /// threshold.
+    Execute,
+    /// `cancel_as_multi`, only the depositor can cancel, which returns the deposit.
+    Cancel,
+}
+
+impl MultisigAction {
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            MultisigAction::Approve => "approve_as_multi",
+            MultisigAction::Execute => "as_multi",
+            MultisigAction::Cancel => "cancel_as_multi",
+        }
+    }
+
+    /// The call in `metadata`, signed by the signatory `signer`. The proposal's timepoint must be
+    /// that of the operation on chain, `None` for the first approval.
+    pub fn call(&self, metadata: &Metadata, proposal: &MultisigProposal, signer: &str) -> Result<EncodedCall> {
+        let call_name = self.call_name();
+        let call_index = metadata.call_index("Multisig", call_name)?;
+        let others = proposal.other_signatories(signer)?;
+        let threshold = proposal.threshold;
+        let call = match self {
+            MultisigAction::Approve => {
+                let max_weight = max_weight_arg(metadata, call_name, proposal.max_weight)?;
+                (call_index, threshold, others, proposal.timepoint, proposal.call_hash()?, max_weight).encode()
+            }
+            MultisigAction::Execute => {
+                if metadata.call_args("Multisig", call_name)?.iter().any(|arg| arg == "store_call") {
+                    bail!("runtimes storing the call with as_multi are not supported");
+                }
+                let max_weight = max_weight_arg(metadata, call_name, proposal.max_weight)?;
+                (call_index, threshold, others, proposal.timepoint, EncodedCall(proposal.call_data()?), max_weight).encode()
+            }
+            MultisigAction::Cancel => {
+                let timepoint = proposal.timepoint.ok_or(anyhow!("the operation is not on chain, there is nothing to cancel"))?;
+                (call_index, threshold, others, timepoint, proposal.call_hash()?).encode()
+            }
+        };
+        Ok(EncodedCall(call))
+    }
+}
+
+/// `max_weight` as the runtime types it, a plain `u64` before weights v2.
+fn max_weight_arg(metadata: &Metadata, call: &str, weight: Weight) -> Result<EncodedCall> {
+    let ty = metadata.call_arg_ty("Multisig", call, "max_weight")?;
+    let resolved = metadata.types.resolve(ty).ok_or(anyhow!("type {} not found in metadata", ty))?;
+    let arg = match resolved.type_def() {
+        TypeDef::Primitive(TypeDefPrimitive::U64) => weight.ref_time.encode(),
+        // `Weight { ref_time }` of the runtimes in between
+        TypeDef::Composite(composite) if composite.fields().len() == 1 => Compact(weight.ref_time).encode(),
+        TypeDef::Composite(_) => weight.encode(),
+        _ => bail!("unsupported max_weight type of Multisig::{}", call),
+    };
+    Ok(EncodedCall(arg))
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+    const CHARLIE: &str = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+
+    fn proposal() -> MultisigProposal {
+        let remark = EncodedCall(RuntimeCall::System(SystemCall::remark { remark: vec![1, 2] }).encode());
+        let signatories = vec![CHARLIE.to_string(), ALICE.to_string(), BOB.to_string()];
+        MultisigProposal::new("0x01".to_string(), 42, &signatories, 2, &remark, "System.remark(remark: 0x0102)".to_string(), Weight { ref_time: 1_000, proof_size: 10 }).unwrap()
+    }
+
+    #[test]
+    fn test_multi_account_id() {
+        let accounts = signatory_accounts(&[ALICE.to_string(), BOB.to_string(), CHARLIE.to_string()]).unwrap();
+        let mut reversed = accounts.clone();
+        reversed.reverse();
+        assert_eq!(multi_account_id(&accounts, 2), multi_account_id(&reversed, 2));
+        assert_ne!(multi_account_id(&accounts, 2), multi_account_id(&accounts, 3));
+        assert_ne!(multi_account_id(&accounts, 2), multi_account_id(&accounts[..2], 2));
+        assert!(signatory_accounts(&[ALICE.to_string(), BOB.to_string(), ALICE.to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_proposal_file() {
+        let proposal = proposal();
+        let accounts = signatory_accounts(&proposal.signatories).unwrap();
+        assert_eq!(parse(&proposal.multisig).unwrap(), multi_account_id(&accounts, 2));
+        assert_eq!(proposal.call_hash().unwrap(), blake2_256(&proposal.call_data().unwrap()));
+        assert_eq!(MultisigProposal::from_json(&proposal.to_json().unwrap()).unwrap(), proposal);
+
+        let mut edited = proposal.clone();
+        edited.call_data = "0x000008".to_string();
+        assert!(MultisigProposal::from_json(&edited.to_json().unwrap()).unwrap_err().to_string().contains("call hash"));
+        let mut edited = proposal.clone();
+        edited.threshold = 3;
+        assert!(edited.check().unwrap_err().to_string().contains("not the multisig"));
+        edited.threshold = 4;
+        assert!(edited.check().is_err());
+        let mut edited = proposal.clone();
+        edited.version = PROPOSAL_VERSION + 1;
+        assert!(edited.check().is_err());
+        assert!(MultisigProposal::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_approvals() {
+        let mut proposal = proposal();
+        assert_eq!(proposal.other_signatories(ALICE).unwrap().len(), 2);
+        assert!(proposal.other_signatories(&address::to_ss58(&AccountId32::new([9; 32]), 42)).is_err());
+
+        let alice = parse(ALICE).unwrap();
+        let pending = Multisig { when: Timepoint { height: 5, index: 1 }, deposit: 10, depositor: alice.clone(), approvals: vec![alice] };
+        proposal.update(Some(&pending));
+        assert_eq!((proposal.timepoint, proposal.approvals_needed()), (Some(pending.when), 1));
+        assert!(proposal.is_approved_by(ALICE) && !proposal.is_approved_by(BOB));
+        // approvals are stored with the multisig's prefix
+        assert_eq!(proposal.approvals, vec![address::to_ss58(&parse(ALICE).unwrap(), 42)]);
+
+        proposal.update(None);
+        assert_eq!((proposal.timepoint, proposal.approvals.len()), (None, 0));
+    }
+
+    #[test]
+    fn test_multisig_calls() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let mut proposal = proposal();
+        let others = vec![parse(BOB).unwrap(), parse(CHARLIE).unwrap()];
+        let max_weight = Weight { ref_time: 1_000, proof_size: 10 };
+        let call = |action: MultisigAction, proposal: &MultisigProposal| action.call(&metadata, proposal, ALICE).map(|call| call.0);
+
+        let approve = RuntimeCall::Multisig(MultisigCall::approve_as_multi {
+            threshold: 2, other_signatories: others.clone(), maybe_timepoint: None, call_hash: proposal.call_hash().unwrap(), max_weight,
+        });
+        assert_eq!(call(MultisigAction::Approve, &proposal).unwrap(), approve.encode());
+        assert!(call(MultisigAction::Cancel, &proposal).is_err());
+
+        let timepoint = Timepoint { height: 5, index: 1 };
+        proposal.timepoint = Some(timepoint);
+        let execute = RuntimeCall::Multisig(MultisigCall::as_multi {
+            threshold: 2, other_signatories: others.clone(), maybe_timepoint: Some(timepoint),
+            call: Box::new(RuntimeCall::System(SystemCall::remark { remark: vec![1, 2] })), max_weight,
+        });
+        assert_eq!(call(MultisigAction::Execute, &proposal).unwrap(), execute.encode());
+        let cancel = RuntimeCall::Multisig(MultisigCall::cancel_as_multi { threshold: 2, other_signatories: others, timepoint, call_hash: proposal.call_hash().unwrap() });
+        assert_eq!(call(MultisigAction::Cancel, &proposal).unwrap(), cancel.encode());
+        assert_eq!(call(MultisigAction::Cancel, &proposal).unwrap()[..2], [MULTISIG_INDEX, 3]);
+
+        assert!(MultisigAction::Approve.call(&metadata, &proposal, &address::to_ss58(&AccountId32::new([9; 32]), 42)).is_err());
+    }
+}
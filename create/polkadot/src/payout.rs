@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::crypto::AccountId32;
+
+use crate::EncodedCall;
+use crate::address;
+use crate::metadata::Metadata;
+use crate::tracker::TrackedTx;
+use crate::utility::{ItemResult, PreparedCall};
+use crate::value::Value;
+
+/// Most `payout_stakers` calls sent in one batch, so it stays within the block weight. Older
+/// eras go first as they expire first, the rest are left for the next run.
+pub const MAX_PAYOUTS: usize = 32;
+
+/// `Staking::ErasStakers` of a validator in an era.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct Exposure {
+    #[codec(compact)]
+    pub total: u128,
+    #[codec(compact)]
+    pub own: u128,
+    pub others: Vec<IndividualExposure>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct IndividualExposure {
+    pub who: AccountId32,
+    #[codec(compact)]
+    pub value: u128,
+}
+
+/// `Staking::ErasStakersOverview` of runtimes paging exposures, `ErasStakers` stays empty
+/// for the eras it covers.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct PagedExposureMetadata {
+    #[codec(compact)]
+    pub total: u128,
+    #[codec(compact)]
+    pub own: u128,
+    pub nominator_count: u32,
+    pub page_count: u32,
+}
+
+/// An era a validator was elected in and not fully paid for.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct UnclaimedEra {
+    pub era: u32,
+    /// Stake backing the validator in the era.
+    pub total: u128,
+    /// Exposure pages not paid yet, one `payout_stakers` call each.
+    pub pages: u32,
+}
+
+/// Unclaimed eras of a validator stash, oldest first.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ValidatorPayouts {
+    pub stash: String,
+    pub unclaimed: Vec<UnclaimedEra>,
+}
+
+/// The `payout_stakers` calls for `payouts`, as `(stash, era)` in batch order. Oldest eras go
+/// first and at most `MAX_PAYOUTS` are taken.
+pub fn payout_items(payouts: &[ValidatorPayouts]) -> Vec<(String, u32)> {
+    let mut items: Vec<(String, u32)> = payouts.iter()
+        .flat_map(|payout| payout.unclaimed.iter().flat_map(move |unclaimed| {
+            (0..unclaimed.pages).map(move |_| (payout.stash.clone(), unclaimed.era))
+        }))
+        .collect();
+    items.sort_by_key(|(_, era)| *era);
+    items.truncate(MAX_PAYOUTS);
+    items
+}
+
+/// `Staking::payout_stakers` paying the next unpaid page of `stash` in `era`.
+pub fn payout_call(metadata: &Metadata, stash: &str, era: u32) -> Result<EncodedCall> {
+    let call_index = metadata.call_index("Staking", "payout_stakers")?;
+    let stash = address::parse_address(stash).map_err(|e| anyhow!("invalid address {}: {}", stash, e))?.account;
+    Ok(EncodedCall((call_index, stash, era).encode()))
+}
+
+/// Eras of the ledger's `legacy_claimed_rewards`, `claimed_rewards` before exposures were
+/// paged. The ledger gained and renamed fields between runtimes, so it is read from the value
+/// decoded with the metadata rather than a fixed layout.
+pub fn legacy_claimed(ledger: &Value) -> Vec<u32> {
+    ledger.field("legacy_claimed_rewards")
+        .or_else(|| ledger.field("claimed_rewards"))
+        .and_then(Value::as_sequence)
+        .map(|eras| eras.iter().filter_map(Value::as_u128).map(|era| era as u32).collect())
+        .unwrap_or_default()
+}
+
+/// Unpaid pages of an era, from its exposure and the claims of `Staking::ClaimedRewards` and the
+/// ledger's legacy `claimed_rewards`. `None` when the validator was not elected in it.
+pub fn unclaimed_era(era: u32, overview: Option<PagedExposureMetadata>, exposure: Option<Exposure>, claimed_pages: &[u32], legacy_claimed: &[u32]) -> Option<UnclaimedEra> {
+    let (total, pages) = match (overview, exposure) {
+        (Some(overview), _) => (overview.total, overview.page_count.max(1)),
+        (None, Some(exposure)) => (exposure.total, 1),
+        (None, None) => return None,
+    };
+    if total == 0 || legacy_claimed.contains(&era) {
+        return None;
+    }
+    let pages = pages.saturating_sub(claimed_pages.len() as u32);
+    if pages == 0 {
+        return None;
+    }
+    Some(UnclaimedEra { era, total, pages })
+}
+
+
+/// What `Client::payout_stakers` found and claimed.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PayoutReport {
+    /// Eras first checked from, up to the era before the active one.
+    pub first_era: u32,
+    pub active_era: u32,
+    pub payouts: Vec<ValidatorPayouts>,
+    /// `(stash, era)` of each call in the batch.
+    pub items: Vec<(String, u32)>,
+    /// The signed batch, `None` when there was nothing to claim.
+    pub prepared: Option<PreparedCall>,
+    /// The submitted batch, `None` for a dry run.
+    pub tx: Option<TrackedTx>,
+}
+
+impl PayoutReport {
+    pub fn is_dry_run(&self) -> bool {
+        self.prepared.is_some() && self.tx.is_none()
+    }
+
+    /// Each call of the batch with its result, `None` before it is in a block or for a dry run.
+    pub fn results(&self) -> Vec<(&str, u32, Option<&ItemResult>)> {
+        let items = self.tx.as_ref().map(|tx| tx.items.as_slice()).unwrap_or_default();
+        self.items.iter().enumerate()
+            .map(|(i, (stash, era))| (stash.as_str(), *era, items.get(i)))
+            .collect()
+    }
+
+    /// Calls that paid out.
+    pub fn claimed(&self) -> usize {
+        self.results().iter().filter(|(_, _, result)| *result == Some(&ItemResult::Completed)).count()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+
+    #[test]
+    fn test_unclaimed_era() {
+        let exposure = Exposure { total: 100, own: 100, others: vec![] };
+        let overview = |page_count| PagedExposureMetadata { total: 100, own: 100, nominator_count: 700, page_count };
+        assert_eq!(unclaimed_era(5, None, Some(exposure.clone()), &[], &[]), Some(UnclaimedEra { era: 5, total: 100, pages: 1 }));
+        assert_eq!(unclaimed_era(5, None, Some(exposure.clone()), &[], &[4, 5]), None);
+        assert_eq!(unclaimed_era(5, None, Some(Exposure { total: 0, own: 0, others: vec![] }), &[], &[]), None);
+        assert_eq!(unclaimed_era(5, None, None, &[], &[]), None);
+        // the overview counts pages, claimed ones are left out
+        assert_eq!(unclaimed_era(5, Some(overview(2)), None, &[0], &[]), Some(UnclaimedEra { era: 5, total: 100, pages: 1 }));
+        assert_eq!(unclaimed_era(5, Some(overview(2)), Some(exposure), &[0, 1], &[]), None);
+    }
+
+    #[test]
+    fn test_legacy_claimed() {
+        let eras = |eras: &[u32]| Value::Sequence(eras.iter().map(|era| Value::U128(*era as u128)).collect());
+        let old = Value::Composite(vec![(Some("stash".to_string()), Value::Bytes(vec![1; 32])), (Some("claimed_rewards".to_string()), eras(&[4, 5]))]);
+        assert_eq!(legacy_claimed(&old), vec![4, 5]);
+        // a `BoundedVec` after the rename, next to the new `controller` field
+        let new = Value::Composite(vec![
+            (Some("stash".to_string()), Value::Bytes(vec![1; 32])),
+            (Some("legacy_claimed_rewards".to_string()), Value::Composite(vec![(None, eras(&[6]))])),
+            (Some("controller".to_string()), Value::Variant { name: "None".to_string(), index: 0, fields: vec![] }),
+        ]);
+        assert_eq!(legacy_claimed(&new), vec![6]);
+        assert_eq!(legacy_claimed(&Value::U128(1)), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_payout_items() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let unclaimed = |era, pages| UnclaimedEra { era, total: 1, pages };
+        let payouts = vec![
+            ValidatorPayouts { stash: ALICE.to_string(), unclaimed: vec![unclaimed(7, 1), unclaimed(9, 2)] },
+            ValidatorPayouts { stash: BOB.to_string(), unclaimed: vec![unclaimed(8, 1)] },
+        ];
+        let items = payout_items(&payouts);
+        assert_eq!(items.iter().map(|(_, era)| *era).collect::<Vec<_>>(), vec![7, 8, 9, 9]);
+        assert_eq!(items[1].0, BOB);
+
+        let many = vec![ValidatorPayouts { stash: ALICE.to_string(), unclaimed: (0..40).rev().map(|era| unclaimed(era, 1)).collect() }];
+        let items = payout_items(&many);
+        assert_eq!((items.len(), items[0].1, items[MAX_PAYOUTS - 1].1), (MAX_PAYOUTS, 0, MAX_PAYOUTS as u32 - 1));
+
+        let call = payout_call(&metadata, ALICE, 7).unwrap();
+        let alice = address::parse_address(ALICE).unwrap().account;
+        assert_eq!(call.0, RuntimeCall::Staking(StakingCall::payout_stakers { validator_stash: alice, era: 7 }).encode());
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use codec::{Compact, Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::crypto::AccountId32;
+
+use crate::{EncodedCall, GenericAddress};
+use crate::address;
+use crate::metadata::Metadata;
+use crate::value::Value;
+
+/// `NominationPools::PoolMembers` of an account.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct PoolMember {
+    pub pool_id: u32,
+    /// Share of the pool's bonded funds.
+    pub points: u128,
+    /// `FixedU128` reward counter of the last payout.
+    pub last_recorded_reward_counter: u128,
+    /// Points being unbonded by the era they unlock at.
+    pub unbonding_eras: BTreeMap<u32, u128>,
+}
+
+/// `NominationPools::RewardPools` of a pool. Newer runtimes append commission totals, which are
+/// not read.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct RewardPool {
+    pub last_recorded_reward_counter: u128,
+    pub last_recorded_total_payouts: u128,
+    pub total_rewards_claimed: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum PoolState {
+    /// Anyone can join.
+    Open,
+    /// Members can leave, nobody can join.
+    Blocked,
+    /// Being wound down by its root, members can only leave.
+    Destroying,
+}
+
+impl fmt::Display for PoolState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolState::Open => write!(f, "Open"),
+            PoolState::Blocked => write!(f, "Blocked"),
+            PoolState::Destroying => write!(f, "Destroying"),
+        }
+    }
+}
+
+/// A pool of `NominationPools::BondedPools` with its name from `NominationPools::Metadata`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct BondedPool {
+    pub id: u32,
+    /// Empty when the pool set none, not always valid UTF-8.
+    pub name: String,
+    pub state: PoolState,
+    /// Points of all members, equal to the bonded funds unless the pool was slashed.
+    pub points: u128,
+    pub member_counter: u32,
+    pub depositor: AccountId32,
+    /// Account nominating for the pool, `None` when the role is not set.
+    pub nominator: Option<AccountId32>,
+    /// Perbill of rewards taken as commission, `None` without commission.
+    pub commission: Option<u32>,
+}
+
+impl BondedPool {
+    /// Read from the `BondedPoolInner` value, which gained fields and changed their order
+    /// between runtimes, so it is decoded with the metadata rather than a fixed layout.
+    pub fn from_value(id: u32, name: &[u8], value: &Value) -> Result<BondedPool> {
+        let field = |name: &str| value.field(name).ok_or(anyhow!("BondedPools {} has no field {}", id, name));
+        let state = match field("state")?.variant_name() {
+            Some("Open") => PoolState::Open,
+            Some("Blocked") => PoolState::Blocked,
+            Some("Destroying") => PoolState::Destroying,
+            other => return Err(anyhow!("unknown state {:?} of pool {}", other, id)),
+        };
+        let roles = field("roles")?;
+        let depositor = roles.field("depositor").and_then(account).ok_or(anyhow!("pool {} has no depositor", id))?;
+        let commission = value.field("commission")
+            .and_then(|commission| commission.field("current"))
+            .and_then(some)
+            .and_then(|current| current.at(0))
+            .and_then(Value::as_u128);
+        Ok(BondedPool {
+            id,
+            name: String::from_utf8_lossy(name).to_string(),
+            state,
+            points: field("points")?.as_u128().ok_or(anyhow!("invalid points of pool {}", id))?,
+            member_counter: field("member_counter")?.as_u128().ok_or(anyhow!("invalid member counter of pool {}", id))? as u32,
+            depositor,
+            nominator: roles.field("nominator").and_then(some).and_then(account),
+            commission: commission.map(|perbill| perbill as u32),
+        })
+    }
+}
+
+fn some(value: &Value) -> Option<&Value> {
+    match value.variant_name() {
+        Some("Some") => value.at(0),
+        _ => None,
+    }
+}
+
+fn account(value: &Value) -> Option<AccountId32> {
+    AccountId32::try_from(value.as_bytes()?).ok()
+}
+
+
+/// Bonded and reward account of pool `id`, like `PalletId::into_sub_account_truncating`.
+pub fn pool_account(pallet_id: [u8; 8], kind: PoolAccount, id: u32) -> AccountId32 {
+    let mut account = [0u8; 32];
+    let seed = (b"modl", pallet_id, kind as u8, id).encode();
+    account[..seed.len()].copy_from_slice(&seed);
+    AccountId32::new(account)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolAccount {
+    Bonded = 0,
+    Reward = 1,
+}
+
+
+/// A `NominationPools` call signed by the member.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum PoolAction {
+    Join { amount: u128, pool_id: u32 },
+    /// Bond more free balance.
+    BondExtra { amount: u128 },
+    /// Bond the pending rewards instead of paying them out.
+    BondRewards,
+    ClaimPayout,
+    Unbond { points: u128 },
+    /// Unbonded funds become free, slashing spans are those of the pool's bonded account.
+    WithdrawUnbonded { num_slashing_spans: u32 },
+}
+
+impl PoolAction {
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            PoolAction::Join { .. } => "join",
+            PoolAction::BondExtra { .. } | PoolAction::BondRewards => "bond_extra",
+            PoolAction::ClaimPayout => "claim_payout",
+            PoolAction::Unbond { .. } => "unbond",
+            PoolAction::WithdrawUnbonded { .. } => "withdraw_unbonded",
+        }
+    }
+
+    /// The call in `metadata`, `member` is the account unbonding and withdrawing.
+    pub fn call(&self, metadata: &Metadata, member: &str) -> Result<EncodedCall> {
+        let call_index = metadata.call_index("NominationPools", self.call_name())?;
+        let call = match self {
+            PoolAction::Join { amount, pool_id } => (call_index, Compact(*amount), pool_id).encode(),
+            // `BondExtra::FreeBalance(amount)` and `BondExtra::Rewards`
+            PoolAction::BondExtra { amount } => (call_index, 0u8, amount).encode(),
+            PoolAction::BondRewards => (call_index, 1u8).encode(),
+            PoolAction::ClaimPayout => call_index.encode(),
+            PoolAction::Unbond { points } => (call_index, lookup(member)?, Compact(*points)).encode(),
+            PoolAction::WithdrawUnbonded { num_slashing_spans } => (call_index, lookup(member)?, num_slashing_spans).encode(),
+        };
+        Ok(EncodedCall(call))
+    }
+}
+
+fn lookup(addr: &str) -> Result<GenericAddress> {
+    let account = address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account;
+    Ok(GenericAddress::Id(account))
+}
+
+
+/// What an account has in its pool, see `Client::pool_membership`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PoolMembership {
+    pub member: PoolMember,
+    pub pool: BondedPool,
+    pub reward_pool: Option<RewardPool>,
+    /// Claimable now, from the `NominationPoolsApi_pending_rewards` runtime api.
+    pub pending_rewards: u128,
+    pub active_era: Option<u32>,
+    /// Of the pool's bonded account, for `withdraw_unbonded`.
+    pub slashing_spans: u32,
+}
+
+impl PoolMembership {
+    /// Points still unbonding by the era they unlock at, earliest first.
+    pub fn unlocking(&self) -> Vec<(u32, u128)> {
+        self.member.unbonding_eras.iter()
+            .filter(|(era, _)| !self.is_unlocked(**era))
+            .map(|(era, points)| (*era, *points))
+            .collect()
+    }
+
+    /// Unlocked points, waiting for `withdraw_unbonded`.
+    pub fn redeemable(&self) -> u128 {
+        self.member.unbonding_eras.iter()
+            .filter(|(era, _)| self.is_unlocked(**era))
+            .fold(0, |total, (_, points)| total.saturating_add(*points))
+    }
+
+    fn is_unlocked(&self, era: u32) -> bool {
+        self.active_era.map(|active| era <= active).unwrap_or(false)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_pool_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let alice = GenericAddress::Id(address::parse_address(ALICE).unwrap().account);
+        let call = |action: PoolAction| action.call(&metadata, ALICE).unwrap().0;
+        assert_eq!(call(PoolAction::Join { amount: 100, pool_id: 7 }), RuntimeCall::NominationPools(PoolsCall::join { amount: 100, pool_id: 7 }).encode());
+        assert_eq!(call(PoolAction::BondExtra { amount: 5 }), RuntimeCall::NominationPools(PoolsCall::bond_extra { extra: BondExtra::FreeBalance(5) }).encode());
+        assert_eq!(call(PoolAction::BondRewards), RuntimeCall::NominationPools(PoolsCall::bond_extra { extra: BondExtra::Rewards }).encode());
+        assert_eq!(call(PoolAction::ClaimPayout), [POOLS_INDEX, 2]);
+        assert_eq!(call(PoolAction::Unbond { points: 9 }), RuntimeCall::NominationPools(PoolsCall::unbond { member_account: alice.clone(), unbonding_points: 9 }).encode());
+        assert_eq!(call(PoolAction::WithdrawUnbonded { num_slashing_spans: 1 }), RuntimeCall::NominationPools(PoolsCall::withdraw_unbonded { member_account: alice, num_slashing_spans: 1 }).encode());
+    }
+
+    #[test]
+    fn test_bonded_pool() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let entry = metadata.storage_entry("NominationPools", "BondedPools").unwrap();
+        let inner = fixture::bonded_pool(AccountId32::new([1; 32]), 500);
+        let value = Value::decode(&metadata.types, entry.value_ty, &mut inner.encode().as_slice()).unwrap();
+        let pool = BondedPool::from_value(3, b"pool", &value).unwrap();
+        assert_eq!((pool.id, pool.name.as_str(), pool.state, pool.points, pool.member_counter), (3, "pool", PoolState::Open, 500, 2));
+        assert_eq!(pool.depositor, AccountId32::new([1; 32]));
+        assert_eq!(pool.nominator, Some(AccountId32::new([1; 32])));
+        assert_eq!(pool.commission, Some(50_000_000));
+        assert!(BondedPool::from_value(3, b"", &Value::U128(1)).is_err());
+    }
+
+    #[test]
+    fn test_pool_account() {
+        let account = pool_account(*b"py/nopls", PoolAccount::Reward, 258);
+        let bytes: &[u8] = account.as_ref();
+        assert_eq!(&bytes[..12], b"modlpy/nopls");
+        assert_eq!(&bytes[12..17], [1, 2, 1, 0, 0]);
+        assert_eq!(&bytes[17..], [0; 15]);
+        assert_ne!(pool_account(*b"py/nopls", PoolAccount::Bonded, 258), account);
+    }
+
+    #[test]
+    fn test_unbonding() {
+        let membership = PoolMembership {
+            member: PoolMember { pool_id: 1, points: 10, last_recorded_reward_counter: 0, unbonding_eras: BTreeMap::from([(8, 3), (10, 4), (12, 5)]) },
+            pool: BondedPool { id: 1, name: String::new(), state: PoolState::Open, points: 10, member_counter: 1, depositor: AccountId32::new([1; 32]), nominator: None, commission: None },
+            reward_pool: None,
+            pending_rewards: 0,
+            active_era: Some(10),
+            slashing_spans: 0,
+        };
+        assert_eq!(membership.redeemable(), 7);
+        assert_eq!(membership.unlocking(), vec![(12, 5)]);
+    }
+}
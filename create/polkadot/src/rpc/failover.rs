@@ -0,0 +1,457 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use futures::future::BoxFuture;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::client::parse_response;
+use crate::error::{Error, Result};
+use crate::rpc::transport::{NodeTransport, RpcTransport, Subscription};
+use crate::rpc::types::{Header, SystemHealth};
+
+/// How long one endpoint gets to answer before the next one is tried.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Health is checked again once this old.
+pub const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+/// Blocks a node may trail the best known head and still count as synced.
+pub const MAX_LAG: u32 = 3;
+/// Calls kept for `FailoverTransport::served`.
+const SERVED_LOG: usize = 32;
+/// Not sent again to another node once they may have reached one, the pool would refuse the
+/// second copy of an extrinsic it already has.
+const SUBMISSIONS: [&str; 2] = ["author_submitExtrinsic", "author_submitAndWatchExtrinsic"];
+
+
+/// What the last health check saw of one endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    /// Round-trip of the check, `None` before the first one or when it failed.
+    pub latency: Option<Duration>,
+    pub health: Option<SystemHealth>,
+    /// Best block number.
+    pub best: Option<u32>,
+    /// Hash of block 0, which chain the endpoint serves.
+    pub genesis_hash: Option<String>,
+    /// Not syncing, has peers if it should and at most `MAX_LAG` blocks behind the others.
+    pub synced: bool,
+    /// Why the check or the last call failed.
+    pub error: Option<String>,
+}
+
+/// Which endpoint answered a call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Served {
+    pub method: String,
+    pub endpoint: String,
+    pub latency: Duration,
+    /// Endpoints that failed before this one answered.
+    pub failed_over: usize,
+}
+
+
+struct Node<T> {
+    transport: T,
+    health: Mutex<EndpointHealth>,
+}
+
+enum Call<'a> {
+    Request(&'a str, Value),
+    Batch(Vec<(String, Value)>),
+    Subscribe(&'a str, Value, &'a str, bool),
+}
+
+enum Reply {
+    Response(String),
+    Batch(Vec<String>),
+    Subscription(Subscription<Value>),
+}
+
+
+/// Spreads calls over several nodes: the lowest-latency synced one is used, the others take over
+/// when it fails or times out. Live subscriptions stay on the node they were made on.
+pub struct FailoverTransport<T: RpcTransport = NodeTransport> {
+    nodes: RwLock<Vec<Arc<Node<T>>>>,
+    timeout: Duration,
+    /// Last health check, also held while one runs.
+    checked_at: tokio::sync::Mutex<Option<Instant>>,
+    served: Mutex<VecDeque<Served>>,
+    /// Of the first endpoint that reported one, endpoints of other chains are not used.
+    genesis_hash: Mutex<Option<String>>,
+}
+
+impl FailoverTransport {
+    pub fn new(endpoints: Vec<String>) -> Self {
+        let transports = endpoints.iter()
+            .map(|endpoint| endpoint.trim())
+            .filter(|endpoint| !endpoint.is_empty())
+            .map(NodeTransport::new)
+            .collect();
+        FailoverTransport::from_transports(transports)
+    }
+
+    /// Replace the endpoints, health is checked again on the next call.
+    pub fn set_endpoints(&self, endpoints: Vec<String>) {
+        let FailoverTransport { nodes, .. } = FailoverTransport::new(endpoints);
+        *self.nodes.write().unwrap() = nodes.into_inner().unwrap();
+        *self.genesis_hash.lock().unwrap() = None;
+        if let Ok(mut checked_at) = self.checked_at.try_lock() {
+            *checked_at = None;
+        }
+    }
+}
+
+impl<T: RpcTransport> FailoverTransport<T> {
+    /// Transports in order of preference until their health is known.
+    pub fn from_transports(transports: Vec<T>) -> Self {
+        let nodes = transports.into_iter()
+            .map(|transport| Arc::new(Node {
+                health: Mutex::new(EndpointHealth { endpoint: transport.endpoint(), ..Default::default() }),
+                transport,
+            }))
+            .collect();
+        FailoverTransport {
+            nodes: RwLock::new(nodes),
+            timeout: DEFAULT_TIMEOUT,
+            checked_at: tokio::sync::Mutex::new(None),
+            served: Mutex::new(VecDeque::new()),
+            genesis_hash: Mutex::new(None),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn endpoints(&self) -> Vec<String> {
+        self.nodes.read().unwrap().iter().map(|node| node.transport.endpoint()).collect()
+    }
+
+    /// Health of every endpoint as last seen, in order of preference.
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.ordered().iter().map(|node| node.health.lock().unwrap().clone()).collect()
+    }
+
+    /// The most recent calls and who served them, newest last.
+    pub fn served(&self) -> Vec<Served> {
+        self.served.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn last_served(&self) -> Option<Served> {
+        self.served.lock().unwrap().back().cloned()
+    }
+
+    /// Genesis hash of the chain the endpoints serve, known after a health check.
+    pub fn genesis_hash(&self) -> Option<String> {
+        self.genesis_hash.lock().unwrap().clone()
+    }
+
+    /// Ask every endpoint for `system_health` and its best header, in parallel.
+    pub async fn check_health(&self) -> Vec<EndpointHealth> {
+        let mut checked_at = self.checked_at.lock().await;
+        self.check_all().await;
+        *checked_at = Some(Instant::now());
+        self.health()
+    }
+
+    /// Check health when there is a choice to make and the last check is too old.
+    async fn ensure_checked(&self) {
+        if self.nodes.read().unwrap().len() < 2 {
+            return;
+        }
+        let mut checked_at = self.checked_at.lock().await;
+        if checked_at.map(|at| at.elapsed() < HEALTH_INTERVAL).unwrap_or(false) {
+            return;
+        }
+        self.check_all().await;
+        *checked_at = Some(Instant::now());
+    }
+
+    async fn check_all(&self) {
+        let nodes = self.nodes.read().unwrap().clone();
+        let checks = nodes.iter().map(|node| self.check(node));
+        let results = futures::future::join_all(checks).await;
+        let best = results.iter().filter_map(|health| health.best).max().unwrap_or_default();
+        // endpoints are configured in order of preference, the first one decides the chain
+        let genesis_hash = {
+            let mut genesis_hash = self.genesis_hash.lock().unwrap();
+            if genesis_hash.is_none() {
+                *genesis_hash = results.iter().find_map(|health| health.genesis_hash.clone());
+            }
+            genesis_hash.clone()
+        };
+        for (node, mut health) in nodes.iter().zip(results) {
+            health.synced = match (&health.health, health.best) {
+                (Some(system), Some(number)) => !system.is_syncing
+                    && (system.peers > 0 || !system.should_have_peers)
+                    && number + MAX_LAG >= best,
+                _ => false,
+            };
+            if let (Some(expected), Some(actual)) = (&genesis_hash, &health.genesis_hash) {
+                if expected != actual {
+                    health.error = Some(format!("another chain, genesis {} instead of {}", actual, expected));
+                    health.synced = false;
+                }
+            }
+            info!("{} latency {:?} best {:?} synced {} {}", health.endpoint, health.latency, health.best, health.synced, health.error.as_deref().unwrap_or(""));
+            *node.health.lock().unwrap() = health;
+        }
+    }
+
+    async fn check(&self, node: &Node<T>) -> EndpointHealth {
+        let mut health = EndpointHealth { endpoint: node.transport.endpoint(), ..Default::default() };
+        let start = Instant::now();
+        let requests = vec![
+            ("system_health".to_string(), json!([])),
+            ("chain_getHeader".to_string(), json!([])),
+            ("chain_getBlockHash".to_string(), json!([0])),
+        ];
+        let responses = match tokio::time::timeout(self.timeout, node.transport.batch(requests)).await {
+            Ok(Ok(responses)) => responses,
+            Ok(Err(e)) => {
+                health.error = Some(e.to_string());
+                return health;
+            }
+            Err(_) => {
+                health.error = Some(format!("no answer in {:?}", self.timeout));
+                return health;
+            }
+        };
+        health.latency = Some(start.elapsed());
+        match parse_response::<SystemHealth>(&responses[0]) {
+            Ok(Some(system)) => health.health = Some(system),
+            Ok(None) => health.error = Some("system_health returned null".to_string()),
+            Err(e) => health.error = Some(format!("system_health: {}", e)),
+        }
+        match parse_response::<Header>(&responses[1]).map(|header| header.map(|header| header.block_number())) {
+            Ok(Some(Ok(number))) => health.best = Some(number),
+            Ok(Some(Err(e))) => health.error = Some(format!("chain_getHeader: {}", e)),
+            Ok(None) => health.error = Some("chain_getHeader returned null".to_string()),
+            Err(e) => health.error = Some(format!("chain_getHeader: {}", e)),
+        }
+        match parse_response::<String>(&responses[2]) {
+            Ok(hash) => health.genesis_hash = hash,
+            Err(e) => health.error = Some(format!("chain_getBlockHash: {}", e)),
+        }
+        health
+    }
+
+    /// Reachable synced nodes by latency, then the unchecked ones in configured order, then the rest.
+    fn ordered(&self) -> Vec<Arc<Node<T>>> {
+        let mut nodes: Vec<(usize, Arc<Node<T>>)> = self.nodes.read().unwrap().iter().cloned().enumerate().collect();
+        nodes.sort_by_key(|(index, node)| {
+            let health = node.health.lock().unwrap();
+            let rank = match (&health.error, health.latency, health.synced) {
+                (None, Some(_), true) => 0,
+                (None, None, _) => 1,
+                (None, Some(_), false) => 2,
+                (Some(_), _, _) => 3,
+            };
+            (rank, health.latency.unwrap_or(Duration::MAX), *index)
+        });
+        nodes.into_iter().map(|(_, node)| node).collect()
+    }
+
+    async fn dispatch(&self, method: &str, call: Call<'_>) -> Result<Reply> {
+        self.ensure_checked().await;
+        let mut nodes = self.ordered();
+        if let Call::Subscribe(..) = call {
+            nodes.retain(|node| node.transport.can_subscribe());
+        }
+        if let Some(genesis_hash) = self.genesis_hash() {
+            nodes.retain(|node| node.health.lock().unwrap().genesis_hash.iter().all(|hash| *hash == genesis_hash));
+        }
+        let mut last_error = None;
+        for (failed_over, node) in nodes.iter().enumerate() {
+            let endpoint = node.transport.endpoint();
+            let start = Instant::now();
+            let reply = match &call {
+                Call::Request(method, params) => self.attempt(node.transport.request(method, params.clone())).await.map(Reply::Response),
+                Call::Batch(requests) => self.attempt(node.transport.batch(requests.clone())).await.map(Reply::Batch),
+                Call::Subscribe(method, params, unsubscribe, resubscribe) => self.attempt(node.transport.subscribe(method, params.clone(), unsubscribe, *resubscribe)).await.map(Reply::Subscription),
+            };
+            match reply {
+                Ok(reply) => {
+                    let served = Served { method: method.to_string(), endpoint, latency: start.elapsed(), failed_over };
+                    info!("{} served by {} in {:?}", served.method, served.endpoint, served.latency);
+                    let mut log = self.served.lock().unwrap();
+                    if log.len() == SERVED_LOG {
+                        log.pop_front();
+                    }
+                    log.push_back(served);
+                    return Ok(reply);
+                }
+                // the node answered, another one would not do better
+                Err(e @ Error::Rpc(_)) => return Err(e),
+                Err(e) => {
+                    warn!("{} failed on {}: {}", method, endpoint, e);
+                    let mut health = node.health.lock().unwrap();
+                    health.error = Some(e.to_string());
+                    health.latency = None;
+                    health.synced = false;
+                    if SUBMISSIONS.contains(&method) {
+                        return Err(Error::Transport(format!("{} on {}, the extrinsic may still have been submitted", e, endpoint)));
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| match call {
+            Call::Subscribe(..) => anyhow!("{} needs a ws:// or wss:// endpoint", method).into(),
+            _ => Error::Transport("no endpoint configured".to_string()),
+        }))
+    }
+
+    async fn attempt<R>(&self, call: BoxFuture<'_, Result<R>>) -> Result<R> {
+        match tokio::time::timeout(self.timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::Transport(format!("no answer in {:?}", self.timeout))),
+        }
+    }
+}
+
+impl<T: RpcTransport> RpcTransport for FailoverTransport<T> {
+    fn endpoint(&self) -> String {
+        self.ordered().first().map(|node| node.transport.endpoint()).unwrap_or_default()
+    }
+
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            match self.dispatch(method, Call::Request(method, params)).await? {
+                Reply::Response(response) => Ok(response),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let methods: Vec<&str> = requests.iter().map(|(method, _)| method.as_str()).collect();
+            let label = format!("batch [{}]", methods.join(", "));
+            match self.dispatch(&label, Call::Batch(requests)).await? {
+                Reply::Batch(responses) => Ok(responses),
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    fn can_subscribe(&self) -> bool {
+        self.nodes.read().unwrap().iter().any(|node| node.transport.can_subscribe())
+    }
+
+    fn subscribe<'a>(&'a self, method: &'a str, params: Value, unsubscribe: &'a str, resubscribe: bool) -> BoxFuture<'a, Result<Subscription<Value>>> {
+        Box::pin(async move {
+            match self.dispatch(method, Call::Subscribe(method, params, unsubscribe, resubscribe)).await? {
+                Reply::Subscription(subscription) => Ok(subscription),
+                _ => unreachable!(),
+            }
+        })
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use crate::rpc::mock::MockNode;
+
+    use super::*;
+
+    fn node(endpoint: &str) -> MockNode {
+        MockNode::new().with_endpoint(endpoint)
+    }
+
+    #[tokio::test]
+    async fn test_prefer_synced() {
+        let syncing = node("mock://syncing");
+        syncing.set_result("system_health", json!({"peers": 8, "isSyncing": true, "shouldHavePeers": true}));
+        let slow = node("mock://slow");
+        slow.set_latency(Duration::from_millis(50));
+        let behind = node("mock://behind");
+        behind.set_result("chain_getHeader", json!({"parentHash": "0x00", "number": "0x10", "stateRoot": "0x00", "extrinsicsRoot": "0x00"}));
+        let transport = FailoverTransport::from_transports(vec![syncing, slow, behind, node("mock://fast")]);
+
+        let health = transport.check_health().await;
+        let order: Vec<&str> = health.iter().map(|health| health.endpoint.as_str()).collect();
+        assert_eq!(order, vec!["mock://fast", "mock://slow", "mock://syncing", "mock://behind"]);
+        assert!(health[0].synced && health[1].synced);
+        assert!(!health[2].synced && !health[3].synced);
+        assert_eq!(health[3].best, Some(16));
+        assert_eq!(transport.endpoint(), "mock://fast");
+
+        transport.request("chain_getBlockHash", json!([0])).await.unwrap();
+        let served = transport.last_served().unwrap();
+        assert_eq!((served.method.as_str(), served.endpoint.as_str(), served.failed_over), ("chain_getBlockHash", "mock://fast", 0));
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let transport = FailoverTransport::from_transports(vec![node("mock://a"), node("mock://b"), node("mock://c")])
+            .with_timeout(Duration::from_millis(100));
+        transport.check_health().await;
+        let nodes = transport.ordered();
+        let first = nodes[0].transport.endpoint();
+        nodes[0].transport.set_offline(true);
+        nodes[1].transport.set_latency(Duration::from_millis(500));
+
+        // the first is down, the second too slow
+        let responses = transport.batch(vec![("chain_getBlockHash".to_string(), json!([0]))]).await.unwrap();
+        assert!(responses[0].contains(crate::rpc::mock::GENESIS_HASH));
+        let served = transport.last_served().unwrap();
+        assert_eq!(served.endpoint, nodes[2].transport.endpoint());
+        assert_eq!(served.failed_over, 2);
+        assert!(transport.health().iter().find(|health| health.endpoint == first).unwrap().error.is_some());
+        assert_eq!(transport.endpoint(), nodes[2].transport.endpoint());
+
+        // an error answer is not retried elsewhere
+        nodes[2].transport.set_error("state_getStorage", 1010, "Invalid");
+        let response = transport.request("state_getStorage", json!(["0x01"])).await.unwrap();
+        assert!(response.contains("1010"));
+
+        for node in &nodes {
+            node.transport.set_offline(true);
+        }
+        assert!(matches!(transport.request("chain_getBlockHash", json!([0])).await, Err(Error::Transport(_))));
+        let empty: FailoverTransport<MockNode> = FailoverTransport::from_transports(Vec::new());
+        assert!(matches!(empty.request("chain_getBlockHash", json!([0])).await, Err(Error::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn test_submission_not_retried() {
+        let transport = FailoverTransport::from_transports(vec![node("mock://a"), node("mock://b")])
+            .with_timeout(Duration::from_millis(100));
+        transport.check_health().await;
+        let nodes = transport.ordered();
+        nodes[0].transport.set_latency(Duration::from_millis(500));
+
+        // the slow node may have taken it, the other one would reject it as already imported
+        let error = transport.request("author_submitExtrinsic", json!(["0x00"])).await.unwrap_err();
+        assert!(matches!(error, Error::Transport(_)));
+        assert!(error.to_string().contains("may still have been submitted"));
+        assert!(nodes[1].transport.submitted().is_empty());
+        // other calls still fail over
+        transport.request("chain_getBlockHash", json!([0])).await.unwrap();
+        assert_eq!(transport.last_served().unwrap().endpoint, nodes[1].transport.endpoint());
+    }
+
+    #[tokio::test]
+    async fn test_other_chain() {
+        let other = node("mock://kusama");
+        other.set_result("chain_getBlockHash", json!(format!("0x{}", "b0".repeat(32))));
+        let transport = FailoverTransport::from_transports(vec![node("mock://polkadot"), other]);
+        let health = transport.check_health().await;
+        assert_eq!(transport.genesis_hash().as_deref(), Some(crate::rpc::mock::GENESIS_HASH));
+        let kusama = health.iter().find(|health| health.endpoint == "mock://kusama").unwrap();
+        assert!(kusama.error.as_ref().unwrap().starts_with("another chain"));
+
+        // never used, even when the node of the chain is down
+        transport.ordered()[0].transport.set_offline(true);
+        assert!(transport.request("state_getStorage", json!(["0x01"])).await.is_err());
+        assert!(transport.served().iter().all(|served| served.endpoint == "mock://polkadot"));
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use codec::Encode;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+
+use crate::error::{Error, Result, RpcError};
+use crate::rpc::storage::{StorageHasher, StorageMap};
+use crate::rpc::transport::RpcTransport;
+use crate::rpc::types::{AccountInfo, DispatchClass, DispatchInfo, FeeDetails, InclusionFee, Weight};
+use crate::tracker;
+
+pub const GENESIS_HASH: &str = "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3";
+pub const FINALIZED_HEAD: &str = "0x0000000000000000000000000000000000000000000000000000000000000064";
+/// Number of the finalized head.
+pub const FINALIZED_NUMBER: u32 = 100;
+/// `partial_fee` of every fee query.
+pub const PARTIAL_FEE: u128 = 159_154_001;
+
+
+/// An in-process node answering from canned responses, for offline tests of `Client`.
+///
+/// Storage starts empty, so accounts read as new. Submitted extrinsics are recorded and answered
+/// with their hash. Methods without a response fail with `-32601` like an unknown method.
+pub struct MockNode {
+    endpoint: String,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    results: HashMap<String, Value>,
+    /// Results for specific params by method and params JSON, before `results`.
+    results_for: HashMap<(String, String), Value>,
+    errors: HashMap<String, RpcError>,
+    /// Hex encoded values by hex key, both without `0x`.
+    storage: HashMap<String, String>,
+    /// SCALE encoded results of `state_call` by runtime api function.
+    calls: HashMap<String, Vec<u8>>,
+    requests: Vec<(String, Value)>,
+    /// Waited before every answer.
+    latency: Duration,
+    /// Fail every request like an unreachable node.
+    offline: bool,
+    /// Single requests and batches received.
+    round_trips: usize,
+    submitted: Vec<String>,
+}
+
+
+impl MockNode {
+    pub fn new() -> Self {
+        let mut state = State::default();
+        state.results.insert("chain_getBlockHash".to_string(), json!(GENESIS_HASH));
+        state.results.insert("chain_getFinalizedHead".to_string(), json!(FINALIZED_HEAD));
+        state.results.insert("system_chain".to_string(), json!("Polkadot"));
+        state.results.insert("system_name".to_string(), json!("Parity Polkadot"));
+        state.results.insert("system_properties".to_string(), json!({"ss58Format": 0, "tokenDecimals": 10, "tokenSymbol": "DOT"}));
+        state.results.insert("system_health".to_string(), json!({"peers": 8, "isSyncing": false, "shouldHavePeers": true}));
+        state.results.insert("chain_getHeader".to_string(), json!({
+            "parentHash": GENESIS_HASH,
+            "number": format!("0x{:x}", FINALIZED_NUMBER),
+            "stateRoot": GENESIS_HASH,
+            "extrinsicsRoot": GENESIS_HASH,
+        }));
+        state.results.insert("state_getRuntimeVersion".to_string(), json!({
+            "specVersion": 9370,
+            "authoringVersion": 0,
+            "implName": "parity-polkadot",
+            "specName": "polkadot",
+            "stateVersion": 0,
+            "transactionVersion": 20,
+            "apis": [["0x37c8bb1350a9a2a8", 2], ["0xf3ff14d5ab527059", 3]],
+        }));
+        let info = DispatchInfo {
+            weight: Weight { ref_time: 190_949_000, proof_size: 3_593 },
+            class: DispatchClass::Normal,
+            partial_fee: PARTIAL_FEE,
+        };
+        let details = FeeDetails {
+            inclusion_fee: Some(InclusionFee { base_fee: 125_000_000, len_fee: 14_300_000, adjusted_weight_fee: 19_854_001 }),
+            tip: 0,
+        };
+        state.calls.insert("TransactionPaymentApi_query_info".to_string(), info.encode());
+        state.calls.insert("TransactionPaymentApi_query_fee_details".to_string(), details.encode());
+        state.calls.insert("TransactionPaymentCallApi_query_call_info".to_string(), info.encode());
+        MockNode { endpoint: "mock://node".to_string(), state: Mutex::new(state) }
+    }
+
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Serve SCALE encoded metadata from `state_getMetadata`.
+    pub fn with_metadata(self, metadata: &[u8]) -> Self {
+        self.set_result("state_getMetadata", json!(format!("0x{}", hex::encode(metadata))));
+        self
+    }
+
+    pub fn set_result(&self, method: &str, result: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.errors.remove(method);
+        state.results.insert(method.to_string(), result);
+    }
+
+    /// Answer `method` called with exactly `params`, like a block by its hash.
+    pub fn set_result_for(&self, method: &str, params: Value, result: Value) {
+        self.state.lock().unwrap().results_for.insert((method.to_string(), params.to_string()), result);
+    }
+
+    /// Answer `method` with an error object until another result is set.
+    pub fn set_error(&self, method: &str, code: i64, message: &str) {
+        let error = RpcError { code, message: message.to_string(), data: None };
+        self.state.lock().unwrap().errors.insert(method.to_string(), error);
+    }
+
+    /// Set the value at a hex encoded key, `None` removes it.
+    pub fn set_storage(&self, key: &str, value: Option<&[u8]>) {
+        let key = key.trim_start_matches("0x").to_lowercase();
+        let mut state = self.state.lock().unwrap();
+        match value {
+            Some(value) => state.storage.insert(key, hex::encode(value)),
+            None => state.storage.remove(&key),
+        };
+    }
+
+    /// Store `System::Account` of `addr`.
+    pub fn set_account(&self, addr: &str, info: &AccountInfo) -> anyhow::Result<()> {
+        let account = AccountId32::from_ss58check(addr).map_err(|e| anyhow!("invalid address {}: {:?}", addr, e))?;
+        let key = StorageMap::new("System", "Account", StorageHasher::Blake2_128Concat).key(account);
+        self.set_storage(&hex::encode(key.0), Some(&info.encode()));
+        Ok(())
+    }
+
+    /// SCALE encoded result of a runtime api function called with `state_call`.
+    pub fn set_state_call(&self, function: &str, result: &[u8]) {
+        self.state.lock().unwrap().calls.insert(function.to_string(), result.to_vec());
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().latency = latency;
+    }
+
+    /// While offline every request fails with `Error::Transport`.
+    pub fn set_offline(&self, offline: bool) {
+        self.state.lock().unwrap().offline = offline;
+    }
+
+    /// Hex encoded extrinsics accepted by `author_submitExtrinsic`, oldest first.
+    pub fn submitted(&self) -> Vec<String> {
+        self.state.lock().unwrap().submitted.clone()
+    }
+
+    /// Requests and batches received so far, a batch counts once.
+    pub fn round_trips(&self) -> usize {
+        self.state.lock().unwrap().round_trips
+    }
+
+    /// Every request received, as method and params.
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn respond(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push((method.to_string(), params.clone()));
+        if let Some(error) = state.errors.get(method) {
+            return Err(error.clone());
+        }
+        if let Some(result) = state.results_for.get(&(method.to_string(), params.to_string())) {
+            return Ok(result.clone());
+        }
+        let first = params.get(0).and_then(Value::as_str).unwrap_or_default().to_string();
+        match method {
+            "state_getStorage" => {
+                let key = first.trim_start_matches("0x").to_lowercase();
+                Ok(state.storage.get(&key).map(|value| json!(format!("0x{}", value))).unwrap_or(Value::Null))
+            }
+            "state_getKeysPaged" => {
+                let prefix = first.trim_start_matches("0x").to_lowercase();
+                let count = params.get(1).and_then(Value::as_u64).unwrap_or(u64::MAX) as usize;
+                let mut keys: Vec<&String> = state.storage.keys().filter(|key| key.starts_with(&prefix)).collect();
+                keys.sort();
+                Ok(json!(keys.into_iter().take(count).map(|key| format!("0x{}", key)).collect::<Vec<_>>()))
+            }
+            "state_call" => match state.calls.get(&first) {
+                Some(result) => Ok(json!(format!("0x{}", hex::encode(result)))),
+                None => Err(RpcError { code: 1002, message: format!("Exported method {} is not found", first), data: None }),
+            },
+            "author_submitExtrinsic" => {
+                let hash = tracker::extrinsic_hash(&first)
+                    .map_err(|e| RpcError { code: -32602, message: e.to_string(), data: None })?;
+                state.submitted.push(first.trim_start_matches("0x").to_string());
+                Ok(json!(hash))
+            }
+            _ => state.results.get(method).cloned()
+                .ok_or_else(|| RpcError { code: -32601, message: "Method not found".to_string(), data: None }),
+        }
+    }
+
+    async fn round_trip(&self) -> Result<()> {
+        let (latency, offline) = {
+            let mut state = self.state.lock().unwrap();
+            state.round_trips += 1;
+            (state.latency, state.offline)
+        };
+        if offline {
+            return Err(Error::Transport(format!("{} is offline", self.endpoint)));
+        }
+        tokio::time::sleep(latency).await;
+        Ok(())
+    }
+
+    fn response(&self, method: &str, params: Value) -> String {
+        let response = match self.respond(method, params) {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": 1, "result": result}),
+            Err(error) => json!({"jsonrpc": "2.0", "id": 1, "error": error}),
+        };
+        response.to_string()
+    }
+}
+
+impl Default for MockNode {
+    fn default() -> Self {
+        MockNode::new()
+    }
+}
+
+impl RpcTransport for MockNode {
+    fn endpoint(&self) -> String {
+        self.endpoint.clone()
+    }
+
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            self.round_trip().await?;
+            Ok(self.response(method, params))
+        })
+    }
+
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            self.round_trip().await?;
+            Ok(requests.into_iter().map(|(method, params)| self.response(&method, params)).collect())
+        })
+    }
+}
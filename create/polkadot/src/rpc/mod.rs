@@ -1,5 +1,6 @@
-pub(crate) mod storage;
+pub mod failover;
+pub mod mock;
+pub mod storage;
+pub mod transport;
 pub mod types;
-
-
-
+pub mod ws;
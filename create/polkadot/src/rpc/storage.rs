@@ -14,6 +14,13 @@ pub struct StorageValue {
 
 
 impl StorageValue {
+    pub fn new(module_prefix: &str, storage_prefix: &str) -> Self {
+        StorageValue {
+            module_prefix: module_prefix.as_bytes().to_vec(),
+            storage_prefix: storage_prefix.as_bytes().to_vec(),
+        }
+    }
+
     pub fn key(&self) -> StorageKey {
         let mut bytes = sp_core::twox_128(&self.module_prefix).to_vec();
         bytes.extend(&sp_core::twox_128(&self.storage_prefix)[..]);
@@ -66,7 +73,7 @@ pub enum StorageHasher {
     Identity,
 }
 
-fn key_hash<K: Encode>(key: &K, hasher: &StorageHasher) -> Vec<u8> {
+pub(crate) fn key_hash<K: Encode>(key: &K, hasher: &StorageHasher) -> Vec<u8> {
     let encoded_key = key.encode();
     match hasher {
         StorageHasher::Blake2_128 => {
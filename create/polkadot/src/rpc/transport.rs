@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use anyhow::anyhow;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::rpc::ws::WsClient;
+
+/// How `Client` talks to a node: one JSON-RPC request at a time, subscriptions if supported.
+pub trait RpcTransport: Send + Sync {
+    /// The endpoint requests go to, or the preferred one when there are several.
+    fn endpoint(&self) -> String;
+
+    /// Send a request and return the raw JSON-RPC response.
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>>;
+
+    /// Send the requests together, returns the raw responses in request order. Transports
+    /// without JSON-RPC batches send them one by one, concurrently.
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            let requests = requests.into_iter().map(|(method, params)| async move { self.request(&method, params).await });
+            futures::future::try_join_all(requests).await
+        })
+    }
+
+    fn can_subscribe(&self) -> bool {
+        false
+    }
+
+    /// `resubscribe` subscriptions are made again after a reconnect, the others just end.
+    fn subscribe<'a>(&'a self, method: &'a str, _params: Value, _unsubscribe: &'a str, _resubscribe: bool) -> BoxFuture<'a, Result<Subscription<Value>>> {
+        Box::pin(async move {
+            Err(anyhow!("{} needs a ws:// or wss:// endpoint, not {}", method, self.endpoint()).into())
+        })
+    }
+}
+
+
+/// Notifications of one subscription, unsubscribed when dropped.
+pub struct Subscription<T> {
+    inner: Notifications,
+    _marker: PhantomData<fn() -> T>,
+}
+
+struct Notifications {
+    receiver: mpsc::UnboundedReceiver<Value>,
+    on_drop: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Drop for Notifications {
+    fn drop(&mut self) {
+        if let Some(on_drop) = self.on_drop.take() {
+            on_drop();
+        }
+    }
+}
+
+impl<T> Subscription<T> {
+    /// Subscription fed by `receiver`, `on_drop` runs when it is dropped.
+    pub fn new(receiver: mpsc::UnboundedReceiver<Value>, on_drop: Option<Box<dyn FnOnce() + Send>>) -> Self {
+        Subscription {
+            inner: Notifications { receiver, on_drop },
+            _marker: PhantomData,
+        }
+    }
+
+    /// The same notifications read as another type.
+    pub fn cast<R>(self) -> Subscription<R> {
+        Subscription {
+            inner: self.inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Subscription<T> {
+    /// `None` once the subscription has ended.
+    pub async fn next(&mut self) -> Option<Result<T>> {
+        let value = self.inner.receiver.recv().await?;
+        Some(serde_json::from_value(value).map_err(Error::from))
+    }
+}
+
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub(crate) struct JsonRpcReq<T> {
+    pub(crate) id: u64,
+    #[serde(rename = "jsonrpc")]
+    pub(crate) json_rpc: String,
+    pub(crate) method: String,
+    pub(crate) params: T,
+}
+
+
+impl<T> JsonRpcReq<T> {
+    pub(crate) fn new(method: String, params: T) -> Self {
+        let id = Uuid::new_v4();
+        JsonRpcReq {
+            id: id.to_u128_le() as u64,
+            json_rpc: "2.0".to_string(),
+            method,
+            params,
+        }
+    }
+}
+
+
+/// One HTTP POST per request.
+pub struct HttpTransport {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub fn new(endpoint: &str) -> Self {
+        HttpTransport {
+            endpoint: endpoint.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl RpcTransport for HttpTransport {
+    fn endpoint(&self) -> String {
+        self.endpoint.clone()
+    }
+
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let req = JsonRpcReq::new(method.to_string(), params);
+            let body = serde_json::to_string(&req)?;
+            let res = self.client.post(&self.endpoint)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send().await?.text().await?;
+            Ok(res)
+        })
+    }
+
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            if requests.is_empty() {
+                return Ok(Vec::new());
+            }
+            let requests: Vec<JsonRpcReq<Value>> = requests.into_iter().map(|(method, params)| JsonRpcReq::new(method, params)).collect();
+            let body = serde_json::to_string(&requests)?;
+            let res = self.client.post(&self.endpoint)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send().await?.text().await?;
+            split_batch(&requests, &res)
+        })
+    }
+}
+
+
+/// Responses of a batch in the order of `requests`, they may come back in any order.
+fn split_batch(requests: &[JsonRpcReq<Value>], response: &str) -> Result<Vec<String>> {
+    let responses = match serde_json::from_str::<Value>(response)? {
+        Value::Array(responses) => responses,
+        // a single error object when the node rejects the whole batch
+        other => {
+            if let Some(error) = other.get("error") {
+                return Err(Error::Rpc(serde_json::from_value(error.clone())?));
+            }
+            return Err(anyhow!("batch answered with {}", other).into());
+        }
+    };
+    let mut by_id: HashMap<u64, Value> = responses.into_iter()
+        .filter_map(|response| response.get("id").and_then(Value::as_u64).map(|id| (id, response)))
+        .collect();
+    requests.iter()
+        .map(|request| by_id.remove(&request.id)
+            .map(|response| response.to_string())
+            .ok_or_else(|| anyhow!("no response to {} in batch", request.method).into()))
+        .collect()
+}
+
+
+impl RpcTransport for WsClient {
+    fn endpoint(&self) -> String {
+        self.url().to_string()
+    }
+
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            WsClient::request(self, method, params).await.map_err(|e| Error::Transport(e.to_string()))
+        })
+    }
+
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        Box::pin(async move {
+            WsClient::batch(self, requests).await.map_err(|e| Error::Transport(e.to_string()))
+        })
+    }
+
+    fn can_subscribe(&self) -> bool {
+        true
+    }
+
+    fn subscribe<'a>(&'a self, method: &'a str, params: Value, unsubscribe: &'a str, resubscribe: bool) -> BoxFuture<'a, Result<Subscription<Value>>> {
+        Box::pin(async move {
+            let subscription = if resubscribe {
+                WsClient::subscribe(self, method, params, unsubscribe).await
+            } else {
+                WsClient::subscribe_once(self, method, params, unsubscribe).await
+            };
+            subscription.map_err(|e| Error::Transport(e.to_string()))
+        })
+    }
+}
+
+
+/// The transport picked from the endpoint url, websocket for `ws://` and `wss://`.
+pub enum NodeTransport {
+    Http(HttpTransport),
+    Ws(WsClient),
+}
+
+impl NodeTransport {
+    pub fn new(endpoint: &str) -> Self {
+        if endpoint.starts_with("ws://") || endpoint.starts_with("wss://") {
+            NodeTransport::Ws(WsClient::new(endpoint))
+        } else {
+            NodeTransport::Http(HttpTransport::new(endpoint))
+        }
+    }
+}
+
+impl RpcTransport for NodeTransport {
+    fn endpoint(&self) -> String {
+        match self {
+            NodeTransport::Http(http) => http.endpoint(),
+            NodeTransport::Ws(ws) => RpcTransport::endpoint(ws),
+        }
+    }
+
+    fn request<'a>(&'a self, method: &'a str, params: Value) -> BoxFuture<'a, Result<String>> {
+        match self {
+            NodeTransport::Http(http) => http.request(method, params),
+            NodeTransport::Ws(ws) => RpcTransport::request(ws, method, params),
+        }
+    }
+
+    fn batch<'a>(&'a self, requests: Vec<(String, Value)>) -> BoxFuture<'a, Result<Vec<String>>> {
+        match self {
+            NodeTransport::Http(http) => http.batch(requests),
+            NodeTransport::Ws(ws) => RpcTransport::batch(ws, requests),
+        }
+    }
+
+    fn can_subscribe(&self) -> bool {
+        matches!(self, NodeTransport::Ws(_))
+    }
+
+    fn subscribe<'a>(&'a self, method: &'a str, params: Value, unsubscribe: &'a str, resubscribe: bool) -> BoxFuture<'a, Result<Subscription<Value>>> {
+        match self {
+            NodeTransport::Http(http) => http.subscribe(method, params, unsubscribe, resubscribe),
+            NodeTransport::Ws(ws) => RpcTransport::subscribe(ws, method, params, unsubscribe, resubscribe),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_split_batch() {
+        let requests = vec![
+            JsonRpcReq::new("chain_getBlockHash".to_string(), json!([0])),
+            JsonRpcReq::new("state_getStorage".to_string(), json!(["0x01"])),
+        ];
+        let response = json!([
+            {"jsonrpc": "2.0", "id": requests[1].id, "result": null},
+            {"jsonrpc": "2.0", "id": requests[0].id, "result": "0x91b1"},
+        ]).to_string();
+        let responses = split_batch(&requests, &response).unwrap();
+        assert!(responses[0].contains("0x91b1"));
+        assert!(responses[1].contains("null"));
+
+        let missing = json!([{"jsonrpc": "2.0", "id": requests[0].id, "result": "0x91b1"}]).to_string();
+        assert!(split_batch(&requests, &missing).is_err());
+        let rejected = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "Invalid request"}}).to_string();
+        assert_eq!(split_batch(&requests, &rejected).unwrap_err().rpc_code(), Some(-32600));
+    }
+}
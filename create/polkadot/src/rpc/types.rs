@@ -1,7 +1,10 @@
 use codec::{Decode, Encode};
-use serde::{Deserialize, Serialize};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::balance::{Amount, AmountError, Rounding};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RuntimeVersion {
     #[serde(rename = "specVersion")]
     pub spec_version: u32,
@@ -15,10 +18,272 @@ pub struct RuntimeVersion {
     pub state_version: u32,
     #[serde(rename = "transactionVersion")]
     pub transaction_version: u32,
+    /// Runtime api ids (hex encoded blake2_64 of the api name) and their versions.
+    #[serde(default)]
+    pub apis: Vec<(String, u32)>,
+}
+
+impl RuntimeVersion {
+    /// Version of the runtime api with the given 8 byte id.
+    pub fn api_version(&self, id: &[u8; 8]) -> Option<u32> {
+        let id = format!("0x{}", hex::encode(id));
+        self.apis.iter().find(|(api, _)| *api == id).map(|(_, version)| *version)
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Header {
+    #[serde(rename = "parentHash")]
+    pub parent_hash: String,
+    /// Hex encoded block number.
+    pub number: String,
+    #[serde(rename = "stateRoot")]
+    pub state_root: String,
+    #[serde(rename = "extrinsicsRoot")]
+    pub extrinsics_root: String,
+}
+
+impl Header {
+    pub fn block_number(&self) -> anyhow::Result<u32> {
+        Ok(u32::from_str_radix(self.number.trim_start_matches("0x"), 16)?)
+    }
+}
+
+
+/// Result of `system_health`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SystemHealth {
+    pub peers: u64,
+    #[serde(rename = "isSyncing")]
+    pub is_syncing: bool,
+    /// False for dev chains that run without peers.
+    #[serde(rename = "shouldHavePeers")]
+    pub should_have_peers: bool,
+}
+
+
+/// Result of `system_properties`, chains may leave any of them out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemProperties {
+    #[serde(rename = "ss58Format", default)]
+    pub ss58_format: Option<u16>,
+    /// Chains with several tokens list them all, the first is the native one.
+    #[serde(rename = "tokenDecimals", default, deserialize_with = "first_of_many")]
+    pub token_decimals: Option<u8>,
+    #[serde(rename = "tokenSymbol", default, deserialize_with = "first_of_many")]
+    pub token_symbol: Option<String>,
+}
+
+fn first_of_many<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<T>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+    Ok(match Option::<OneOrMany<T>>::deserialize(deserializer)? {
+        Some(OneOrMany::One(value)) => Some(value),
+        Some(OneOrMany::Many(values)) => values.into_iter().next(),
+        None => None,
+    })
+}
+
+impl SystemProperties {
+    /// The generic substrate prefix when the chain has none.
+    pub fn ss58_prefix(&self) -> u16 {
+        self.ss58_format.unwrap_or(42)
+    }
+
+    /// Without decimals balances are shown in plancks.
+    pub fn decimals(&self) -> u8 {
+        self.token_decimals.unwrap_or(0)
+    }
+
+    pub fn symbol(&self) -> &str {
+        self.token_symbol.as_deref().unwrap_or("UNIT")
+    }
+
+    pub fn balance(&self, plancks: u128) -> Amount {
+        Amount::new(plancks, self.decimals())
+    }
+
+    /// Plancks of an amount typed in tokens, see [`Amount::parse`].
+    pub fn parse_balance(&self, text: &str) -> std::result::Result<u128, AmountError> {
+        Amount::parse(text, self.decimals()).map(|balance| balance.plancks())
+    }
+
+    /// `12.3456 DOT`, at most 4 decimals, cut rather than rounded up.
+    pub fn format_balance(&self, plancks: u128) -> String {
+        format!("{} {}", self.balance(plancks).format(4, Rounding::Down), self.symbol())
+    }
+}
+
+/// Which network a node serves, from `system_chain`, `system_name` and `system_properties`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainInfo {
+    /// Like `Polkadot` or `Development`.
+    pub chain: String,
+    /// Node implementation, like `Parity Polkadot`.
+    pub name: String,
+    pub properties: SystemProperties,
+}
+
+impl ChainInfo {
+    /// Shown to tell networks apart, like `Polkadot (DOT)`.
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.chain, self.properties.symbol())
+    }
 }
 
 
-#[derive(Clone, Eq, PartialEq, Default, Encode, Decode)]
+/// Result of `chain_getBlock`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedBlock {
+    pub block: Block,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Block {
+    pub header: Header,
+    /// Hex encoded extrinsics in block order.
+    pub extrinsics: Vec<String>,
+}
+
+
+/// Notification of `state_subscribeStorage`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StorageChangeSet {
+    pub block: String,
+    /// Hex encoded storage keys and their new values, `None` when removed.
+    pub changes: Vec<(String, Option<String>)>,
+}
+
+impl StorageChangeSet {
+    pub fn values(&self) -> anyhow::Result<Vec<(String, Option<Vec<u8>>)>> {
+        let mut values = Vec::new();
+        for (key, value) in &self.changes {
+            let value = match value {
+                Some(value) => Some(hex::decode(value.trim_start_matches("0x"))?),
+                None => None,
+            };
+            values.push((key.clone(), value));
+        }
+        Ok(values)
+    }
+}
+
+
+/// Notification of `author_submitAndWatchExtrinsic`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionStatus {
+    Future,
+    Ready,
+    /// Peer ids the extrinsic was gossiped to.
+    Broadcast(Vec<String>),
+    InBlock(String),
+    Retracted(String),
+    FinalityTimeout(String),
+    Finalized(String),
+    /// Replaced by another extrinsic with the same nonce, hash of the other one.
+    Usurped(String),
+    Dropped,
+    Invalid,
+}
+
+impl TransactionStatus {
+    /// No more notifications follow this one.
+    pub fn is_final(&self) -> bool {
+        matches!(self, TransactionStatus::Finalized(_) | TransactionStatus::FinalityTimeout(_) | TransactionStatus::Usurped(_) | TransactionStatus::Dropped | TransactionStatus::Invalid)
+    }
+}
+
+
+/// Weight with both the computation time and the proof size, `ref_time` only before weights v2.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, Default, Encode, Decode, TypeInfo)]
+pub struct Weight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Encode, Decode)]
+pub enum DispatchClass {
+    Normal,
+    Operational,
+    Mandatory,
+}
+
+
+/// `RuntimeDispatchInfo` returned by `TransactionPaymentApi_query_info`.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct DispatchInfo {
+    pub weight: Weight,
+    pub class: DispatchClass,
+    /// The fee without the tip.
+    pub partial_fee: u128,
+}
+
+impl DispatchInfo {
+    /// `TransactionPaymentApi` before version 2 returns the weight as a plain `u64`.
+    pub fn decode_versioned(data: &[u8], api_version: u32) -> Result<Self, codec::Error> {
+        let input = &mut &data[..];
+        if api_version >= 2 {
+            return DispatchInfo::decode(input);
+        }
+        let ref_time = u64::decode(input)?;
+        Ok(DispatchInfo {
+            weight: Weight { ref_time, proof_size: 0 },
+            class: DispatchClass::decode(input)?,
+            partial_fee: u128::decode(input)?,
+        })
+    }
+}
+
+
+/// The part of the fee paid for the inclusion of an extrinsic in a block.
+#[derive(Clone, Debug, Eq, PartialEq, Default, Encode, Decode)]
+pub struct InclusionFee {
+    /// Minimum amount charged for any extrinsic.
+    pub base_fee: u128,
+    /// Fee for the encoded length of the extrinsic.
+    pub len_fee: u128,
+    /// Weight fee multiplied by the current fee multiplier.
+    pub adjusted_weight_fee: u128,
+}
+
+
+/// `FeeDetails` returned by `TransactionPaymentApi_query_fee_details`.
+#[derive(Clone, Debug, Eq, PartialEq, Default, Encode, Decode)]
+pub struct FeeDetails {
+    /// `None` for unsigned extrinsics.
+    pub inclusion_fee: Option<InclusionFee>,
+    pub tip: u128,
+}
+
+
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct FeeEstimate {
+    pub info: DispatchInfo,
+    pub details: FeeDetails,
+}
+
+impl FeeEstimate {
+    pub fn partial_fee(&self) -> u128 {
+        self.info.partial_fee
+    }
+
+    /// Everything the sender pays for the extrinsic, tip included.
+    pub fn total_fee(&self) -> u128 {
+        self.info.partial_fee.saturating_add(self.details.tip)
+    }
+}
+
+
+#[derive(Clone, Eq, PartialEq, Default, Encode, Decode, TypeInfo)]
 pub struct AccountInfo {
     /// The number of transactions this account has sent.
     pub nonce: u32,
@@ -37,7 +302,7 @@ pub struct AccountInfo {
 }
 
 
-#[derive(Encode, Decode, Clone, PartialEq, Eq, Default)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, TypeInfo)]
 pub struct AccountData {
     /// Non-reserved part of the balance. There may still be restrictions on this, but it is the
     /// total pool what may in principle be transferred, reserved and used for tipping.
@@ -60,3 +325,27 @@ pub struct AccountData {
     /// fee payment.
     pub fee_frozen: u128,
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_system_properties() {
+        let dot: SystemProperties = serde_json::from_str(r#"{"ss58Format": 0, "tokenDecimals": 10, "tokenSymbol": "DOT"}"#).unwrap();
+        assert_eq!(dot.format_balance(123_456_789_012), "12.3456 DOT");
+        assert_eq!(dot.format_balance(10_000_000_000), "1 DOT");
+        assert_eq!(dot.format_balance(1), "0 DOT");
+        assert_eq!(dot.format_balance(1_000_000), "0.0001 DOT");
+        assert_eq!(dot.parse_balance("1.5"), Ok(15_000_000_000));
+        assert_eq!(dot.parse_balance("1.00000000001"), Err(AmountError::TooManyDecimals(10)));
+        assert_eq!(serde_json::from_str::<SystemProperties>(&serde_json::to_string(&dot).unwrap()).unwrap(), dot);
+
+        let multi: SystemProperties = serde_json::from_str(r#"{"ss58Format": 8, "tokenDecimals": [12, 12], "tokenSymbol": ["KAR", "KUSD"]}"#).unwrap();
+        assert_eq!((multi.ss58_prefix(), multi.decimals(), multi.symbol()), (8, 12, "KAR"));
+
+        let empty: SystemProperties = serde_json::from_str("{}").unwrap();
+        assert_eq!((empty.ss58_prefix(), empty.format_balance(1_234)), (42, "1234 UNIT".to_string()));
+    }
+}
@@ -0,0 +1,629 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::rpc::transport::Subscription;
+
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(200);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+
+enum Command {
+    Request {
+        id: u64,
+        method: String,
+        params: Value,
+        reply: oneshot::Sender<Result<String>>,
+    },
+    Subscribe {
+        id: u64,
+        method: String,
+        params: Value,
+        unsubscribe: String,
+        resubscribe: bool,
+        sink: mpsc::UnboundedSender<Value>,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    Unsubscribe {
+        key: u64,
+    },
+    /// Requests sent together as one JSON-RPC batch.
+    Batch {
+        requests: Vec<(u64, String, Value, oneshot::Sender<Result<String>>)>,
+    },
+}
+
+
+/// JSON-RPC over a single WebSocket connection.
+///
+/// Requests are multiplexed by id. The connection is opened by a background task on the first
+/// call and opened again whenever it drops: requests in flight at that moment fail, subscriptions
+/// are made again on the new connection.
+pub struct WsClient {
+    url: String,
+    next_id: Arc<AtomicU64>,
+    commands: mpsc::UnboundedSender<Command>,
+    /// Taken by the background task, started lazily so it runs on the caller's runtime.
+    worker: Mutex<Option<mpsc::UnboundedReceiver<Command>>>,
+}
+
+
+impl WsClient {
+    pub fn new(url: &str) -> Self {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        WsClient {
+            url: url.to_string(),
+            next_id: Arc::new(AtomicU64::new(1)),
+            commands,
+            worker: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn start(&self) {
+        if let Some(receiver) = self.worker.lock().unwrap().take() {
+            let worker = Worker::new(self.url.clone(), receiver, self.next_id.clone());
+            tokio::spawn(worker.run());
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<()> {
+        self.start();
+        self.commands.send(command).map_err(|_| anyhow!("websocket worker stopped"))
+    }
+
+    /// Send a request and return the raw JSON-RPC response.
+    pub async fn request(&self, method: &str, params: Value) -> Result<String> {
+        let (reply, response) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.send(Command::Request { id, method: method.to_string(), params, reply })?;
+        response.await.map_err(|_| anyhow!("websocket worker stopped"))?
+    }
+
+    /// Send the requests as one batch, returns the raw responses in request order.
+    pub async fn batch(&self, requests: Vec<(String, Value)>) -> Result<Vec<String>> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut batch = Vec::with_capacity(requests.len());
+        let mut responses = Vec::with_capacity(requests.len());
+        for (method, params) in requests {
+            let (reply, response) = oneshot::channel();
+            batch.push((self.next_id.fetch_add(1, Ordering::SeqCst), method, params, reply));
+            responses.push(response);
+        }
+        self.send(Command::Batch { requests: batch })?;
+        let mut results = Vec::with_capacity(responses.len());
+        for response in responses {
+            results.push(response.await.map_err(|_| anyhow!("websocket worker stopped"))??);
+        }
+        Ok(results)
+    }
+
+    /// Subscription that is made again after a reconnect.
+    pub async fn subscribe<T: DeserializeOwned>(&self, method: &str, params: Value, unsubscribe: &str) -> Result<Subscription<T>> {
+        self.subscribe_with(method, params, unsubscribe, true).await
+    }
+
+    /// Subscription that ends when the connection drops, for calls with side effects such as
+    /// `author_submitAndWatchExtrinsic`.
+    pub async fn subscribe_once<T: DeserializeOwned>(&self, method: &str, params: Value, unsubscribe: &str) -> Result<Subscription<T>> {
+        self.subscribe_with(method, params, unsubscribe, false).await
+    }
+
+    async fn subscribe_with<T: DeserializeOwned>(&self, method: &str, params: Value, unsubscribe: &str, resubscribe: bool) -> Result<Subscription<T>> {
+        let (reply, response) = oneshot::channel();
+        let (sink, notifications) = mpsc::unbounded_channel();
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.send(Command::Subscribe {
+            id,
+            method: method.to_string(),
+            params,
+            unsubscribe: unsubscribe.to_string(),
+            resubscribe,
+            sink,
+            reply,
+        })?;
+        response.await.map_err(|_| anyhow!("websocket worker stopped"))??;
+        let commands = self.commands.clone();
+        let unsubscribe = move || {
+            let _ = commands.send(Command::Unsubscribe { key: id });
+        };
+        Ok(Subscription::new(notifications, Some(Box::new(unsubscribe))))
+    }
+}
+
+
+enum Pending {
+    Request(oneshot::Sender<Result<String>>),
+    /// `reply` is `None` when subscribing again after a reconnect.
+    Subscribe {
+        key: u64,
+        unsubscribe: String,
+        reply: Option<oneshot::Sender<Result<()>>>,
+    },
+}
+
+struct ActiveSubscription {
+    method: String,
+    params: Value,
+    unsubscribe: String,
+    resubscribe: bool,
+    sink: mpsc::UnboundedSender<Value>,
+    server_id: Option<String>,
+}
+
+
+struct Worker {
+    url: String,
+    commands: mpsc::UnboundedReceiver<Command>,
+    next_id: Arc<AtomicU64>,
+    pending: HashMap<u64, Pending>,
+    /// By the id of the request that created them, stable across reconnects.
+    subscriptions: HashMap<u64, ActiveSubscription>,
+    /// Subscription id given by the node to our key.
+    routes: HashMap<String, u64>,
+    /// Messages to write on the current connection.
+    outgoing: Vec<String>,
+}
+
+
+impl Worker {
+    fn new(url: String, commands: mpsc::UnboundedReceiver<Command>, next_id: Arc<AtomicU64>) -> Self {
+        Worker {
+            url,
+            commands,
+            next_id,
+            pending: HashMap::new(),
+            subscriptions: HashMap::new(),
+            routes: HashMap::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    async fn run(mut self) {
+        let mut delay = MIN_RECONNECT_DELAY;
+        loop {
+            let socket = match connect_async(self.url.as_str()).await {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    warn!("connect {} error: {}", self.url, e);
+                    // nobody should wait for a node that is not there
+                    if !self.fail_queued(&format!("connect {} error: {}", self.url, e)) {
+                        return;
+                    }
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    continue;
+                }
+            };
+            info!("connected to {}", self.url);
+            delay = MIN_RECONNECT_DELAY;
+            self.resubscribe();
+            let (mut write, mut read) = socket.split();
+            let closed = loop {
+                let mut failed = false;
+                for message in self.outgoing.drain(..) {
+                    debug!("send: {}", message);
+                    if let Err(e) = write.send(Message::Text(message)).await {
+                        warn!("send error: {}", e);
+                        failed = true;
+                        break;
+                    }
+                }
+                if failed {
+                    break false;
+                }
+                tokio::select! {
+                    command = self.commands.recv() => match command {
+                        Some(command) => self.handle(command),
+                        None => break true,
+                    },
+                    message = read.next() => match message {
+                        Some(Ok(Message::Text(text))) => self.dispatch(text),
+                        Some(Ok(Message::Close(_))) | None => break false,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => {
+                            warn!("receive error: {}", e);
+                            break false;
+                        }
+                    },
+                }
+            };
+            if closed {
+                let _ = write.close().await;
+                return;
+            }
+            warn!("connection to {} dropped, reconnecting", self.url);
+            self.connection_lost();
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    fn handle(&mut self, command: Command) {
+        match command {
+            Command::Request { id, method, params, reply } => {
+                self.pending.insert(id, Pending::Request(reply));
+                self.write(id, &method, params);
+            }
+            Command::Subscribe { id, method, params, unsubscribe, resubscribe, sink, reply } => {
+                self.pending.insert(id, Pending::Subscribe { key: id, unsubscribe: unsubscribe.clone(), reply: Some(reply) });
+                self.write(id, &method, params.clone());
+                self.subscriptions.insert(id, ActiveSubscription { method, params, unsubscribe, resubscribe, sink, server_id: None });
+            }
+            Command::Batch { requests } => {
+                let mut batch = Vec::with_capacity(requests.len());
+                for (id, method, params, reply) in requests {
+                    self.pending.insert(id, Pending::Request(reply));
+                    batch.push(request(id, &method, params));
+                }
+                self.outgoing.push(Value::Array(batch).to_string());
+            }
+            Command::Unsubscribe { key } => {
+                if let Some(subscription) = self.subscriptions.remove(&key) {
+                    if let Some(server_id) = subscription.server_id {
+                        self.routes.remove(&server_id);
+                        let id = self.id();
+                        self.write(id, &subscription.unsubscribe, json!([server_id]));
+                    }
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, id: u64, method: &str, params: Value) {
+        self.outgoing.push(request(id, method, params).to_string());
+    }
+
+    fn dispatch(&mut self, text: String) {
+        debug!("receive: {}", text);
+        let value: Value = match serde_json::from_str(&text) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("invalid message {}: {}", text, e);
+                return;
+            }
+        };
+        match value {
+            // answer to a batch, one response per request
+            Value::Array(responses) => {
+                for response in responses {
+                    let text = response.to_string();
+                    self.dispatch_value(response, text);
+                }
+            }
+            value => self.dispatch_value(value, text),
+        }
+    }
+
+    fn dispatch_value(&mut self, value: Value, text: String) {
+        if let Some(id) = value.get("id").and_then(Value::as_u64) {
+            match self.pending.remove(&id) {
+                Some(Pending::Request(reply)) => {
+                    let _ = reply.send(Ok(text));
+                }
+                Some(Pending::Subscribe { key, unsubscribe, reply }) => {
+                    let result = match value.get("result") {
+                        Some(server_id) if !server_id.is_null() => Ok(subscription_id(server_id)),
+                        _ => Err(anyhow!("subscribe error {}", value.get("error").unwrap_or(&Value::Null))),
+                    };
+                    match result {
+                        Ok(server_id) => match self.subscriptions.get_mut(&key) {
+                            Some(subscription) => {
+                                subscription.server_id = Some(server_id.clone());
+                                self.routes.insert(server_id, key);
+                                if let Some(reply) = reply {
+                                    let _ = reply.send(Ok(()));
+                                }
+                            }
+                            // dropped before the node answered
+                            None => {
+                                let id = self.id();
+                                self.write(id, &unsubscribe, json!([server_id]));
+                            }
+                        },
+                        Err(e) => {
+                            warn!("{}", e);
+                            self.subscriptions.remove(&key);
+                            if let Some(reply) = reply {
+                                let _ = reply.send(Err(e));
+                            }
+                        }
+                    }
+                }
+                None => debug!("response to unknown request {}", id),
+            }
+        } else if let Some(params) = value.get("params") {
+            let server_id = match params.get("subscription") {
+                Some(server_id) => subscription_id(server_id),
+                None => return,
+            };
+            if let Some(key) = self.routes.get(&server_id) {
+                if let Some(subscription) = self.subscriptions.get(key) {
+                    let _ = subscription.sink.send(params.get("result").cloned().unwrap_or(Value::Null));
+                }
+            }
+        }
+    }
+
+    /// Subscribe again on a new connection.
+    fn resubscribe(&mut self) {
+        let keys: Vec<u64> = self.subscriptions.keys().cloned().collect();
+        for key in keys {
+            let (method, params, unsubscribe) = {
+                let subscription = &self.subscriptions[&key];
+                (subscription.method.clone(), subscription.params.clone(), subscription.unsubscribe.clone())
+            };
+            let id = self.id();
+            self.pending.insert(id, Pending::Subscribe { key, unsubscribe, reply: None });
+            self.write(id, &method, params);
+        }
+    }
+
+    fn connection_lost(&mut self) {
+        self.outgoing.clear();
+        self.routes.clear();
+        for (_, pending) in self.pending.drain() {
+            match pending {
+                Pending::Request(reply) => {
+                    let _ = reply.send(Err(anyhow!("connection closed")));
+                }
+                Pending::Subscribe { key, reply: Some(reply), .. } => {
+                    self.subscriptions.remove(&key);
+                    let _ = reply.send(Err(anyhow!("connection closed")));
+                }
+                Pending::Subscribe { reply: None, .. } => {}
+            }
+        }
+        // dropping the sink ends the subscription for its reader
+        self.subscriptions.retain(|_, subscription| subscription.resubscribe);
+        for subscription in self.subscriptions.values_mut() {
+            subscription.server_id = None;
+        }
+    }
+
+    /// Fail every queued command while there is no connection, false once the client is gone.
+    fn fail_queued(&mut self, error: &str) -> bool {
+        loop {
+            match self.commands.try_recv() {
+                Ok(Command::Request { reply, .. }) => {
+                    let _ = reply.send(Err(anyhow!("{}", error)));
+                }
+                Ok(Command::Subscribe { reply, .. }) => {
+                    let _ = reply.send(Err(anyhow!("{}", error)));
+                }
+                Ok(Command::Unsubscribe { key }) => {
+                    self.subscriptions.remove(&key);
+                }
+                Ok(Command::Batch { requests }) => {
+                    for (_, _, _, reply) in requests {
+                        let _ = reply.send(Err(anyhow!("{}", error)));
+                    }
+                }
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+}
+
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({
+        "id": id,
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+    })
+}
+
+fn subscription_id(value: &Value) -> String {
+    match value {
+        Value::String(id) => id.clone(),
+        other => other.to_string(),
+    }
+}
+
+
+/// Minimal WebSocket JSON-RPC node for tests.
+///
+/// `echo` answers its params after `params[1]` milliseconds, `close` drops the connection,
+/// `chain_subscribeNewHeads` pushes two heads, methods in `notifications` subscribe and push theirs,
+/// everything else is answered from `responses`. Batches are answered at once, in reverse order.
+#[cfg(test)]
+pub(crate) mod stand_in {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    pub(crate) struct StandIn {
+        pub url: String,
+        /// Connections accepted so far.
+        pub connections: Arc<AtomicUsize>,
+    }
+
+    pub(crate) async fn start(responses: HashMap<String, Value>) -> StandIn {
+        start_with(responses, HashMap::new()).await
+    }
+
+    pub(crate) async fn start_with(responses: HashMap<String, Value>, notifications: HashMap<String, Vec<Value>>) -> StandIn {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        let responses = Arc::new(responses);
+        let notifications = Arc::new(notifications);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let connection = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                let responses = responses.clone();
+                let notifications = notifications.clone();
+                tokio::spawn(async move {
+                    let socket = accept_async(stream).await.unwrap();
+                    let (mut write, mut read) = socket.split();
+                    let (sender, mut receiver) = mpsc::unbounded_channel::<Value>();
+                    tokio::spawn(async move {
+                        while let Some(message) = receiver.recv().await {
+                            if write.send(Message::Text(message.to_string())).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                    while let Some(Ok(Message::Text(text))) = read.next().await {
+                        let request: Value = serde_json::from_str(&text).unwrap();
+                        if let Value::Array(batch) = request {
+                            let answers: Vec<Value> = batch.iter().rev().map(|request| match request["method"].as_str().unwrap() {
+                                "echo" => json!({"jsonrpc": "2.0", "id": request["id"], "result": request["params"]}),
+                                method => answer(&responses, method, request["id"].clone()),
+                            }).collect();
+                            let _ = sender.send(Value::Array(answers));
+                            continue;
+                        }
+                        let id = request["id"].clone();
+                        let params = request["params"].clone();
+                        match request["method"].as_str().unwrap() {
+                            "close" => return,
+                            "echo" => {
+                                let sender = sender.clone();
+                                tokio::spawn(async move {
+                                    tokio::time::sleep(Duration::from_millis(params[1].as_u64().unwrap_or(0))).await;
+                                    let _ = sender.send(json!({"jsonrpc": "2.0", "id": id, "result": params}));
+                                });
+                            }
+                            "chain_subscribeNewHeads" => {
+                                let subscription = format!("heads-{}", connection);
+                                let _ = sender.send(json!({"jsonrpc": "2.0", "id": id, "result": subscription}));
+                                for number in 0..2 {
+                                    let header = json!({
+                                        "parentHash": "0x00",
+                                        "number": format!("0x{:x}", connection * 10 + number),
+                                        "stateRoot": "0x00",
+                                        "extrinsicsRoot": "0x00",
+                                    });
+                                    let _ = sender.send(json!({"jsonrpc": "2.0", "method": "chain_newHead", "params": {"subscription": subscription, "result": header}}));
+                                }
+                            }
+                            method if notifications.contains_key(method) => {
+                                let subscription = format!("{}-{}", method, connection);
+                                let _ = sender.send(json!({"jsonrpc": "2.0", "id": id, "result": subscription}));
+                                for result in &notifications[method] {
+                                    let _ = sender.send(json!({"jsonrpc": "2.0", "method": method, "params": {"subscription": subscription, "result": result}}));
+                                }
+                            }
+                            method => {
+                                let _ = sender.send(answer(&responses, method, id));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        StandIn { url, connections }
+    }
+
+    fn answer(responses: &HashMap<String, Value>, method: &str, id: Value) -> Value {
+        match responses.get(method) {
+            Some(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            None => json!({"jsonrpc": "2.0", "id": id, "error": {"code": -32601, "message": "Method not found"}}),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::rpc::types::Header;
+
+    use super::*;
+    use super::stand_in;
+
+    #[tokio::test]
+    async fn test_multiplex() {
+        let node = stand_in::start(HashMap::new()).await;
+        let client = WsClient::new(&node.url);
+        // the slow request is answered last but still gets its own response
+        let (slow, fast) = tokio::join!(client.request("echo", json!(["slow", 200])), client.request("echo", json!(["fast", 0])));
+        let slow: Value = serde_json::from_str(&slow.unwrap()).unwrap();
+        let fast: Value = serde_json::from_str(&fast.unwrap()).unwrap();
+        assert_eq!(slow["result"][0], "slow");
+        assert_eq!(fast["result"][0], "fast");
+        assert_ne!(slow["id"], fast["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        let node = stand_in::start(HashMap::new()).await;
+        let client = WsClient::new(&node.url);
+        let mut heads = client.subscribe::<Header>("chain_subscribeNewHeads", json!([]), "chain_unsubscribeNewHeads").await.unwrap();
+        assert_eq!(heads.next().await.unwrap().unwrap().block_number().unwrap(), 10);
+        assert_eq!(heads.next().await.unwrap().unwrap().block_number().unwrap(), 11);
+
+        assert!(client.request("close", json!([])).await.is_err());
+        // the subscription is made again on the new connection
+        assert_eq!(heads.next().await.unwrap().unwrap().block_number().unwrap(), 20);
+        let response = client.request("echo", json!(["again", 0])).await.unwrap();
+        assert!(response.contains("again"));
+        assert_eq!(node.connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_once() {
+        let node = stand_in::start(HashMap::new()).await;
+        let client = WsClient::new(&node.url);
+        let mut heads = client.subscribe_once::<Header>("chain_subscribeNewHeads", json!([]), "chain_unsubscribeNewHeads").await.unwrap();
+        heads.next().await.unwrap().unwrap();
+        heads.next().await.unwrap().unwrap();
+        assert!(client.request("close", json!([])).await.is_err());
+        assert!(heads.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let mut responses = HashMap::new();
+        responses.insert("chain_getBlockHash".to_string(), json!("0x91b1"));
+        let node = stand_in::start(responses).await;
+        let client = WsClient::new(&node.url);
+        let results = client.batch(vec![
+            ("echo".to_string(), json!(["first", 0])),
+            ("chain_getBlockHash".to_string(), json!([0])),
+            ("missing".to_string(), json!([])),
+        ]).await.unwrap();
+        // answered in reverse, matched back by id
+        let results: Vec<Value> = results.iter().map(|result| serde_json::from_str(result).unwrap()).collect();
+        assert_eq!(results[0]["result"][0], "first");
+        assert_eq!(results[1]["result"], "0x91b1");
+        assert_eq!(results[2]["error"]["code"], -32601);
+        assert!(client.batch(Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable() {
+        let client = WsClient::new("ws://127.0.0.1:1");
+        assert!(client.request("echo", json!([])).await.is_err());
+        assert!(client.subscribe::<Header>("chain_subscribeNewHeads", json!([]), "chain_unsubscribeNewHeads").await.is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{anyhow, bail, Result};
+use codec::{Decode, Encode};
+
+use crate::EncodedCall;
+use crate::metadata::Metadata;
+use crate::value::Value;
+
+/// One public key of a validator's session keys.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct SessionKey {
+    /// Field of the runtime's `SessionKeys`, like `grandpa`.
+    pub name: String,
+    /// Four letter key type id the keystore files it under, like `gran`.
+    pub key_type: String,
+    pub public: Vec<u8>,
+}
+
+/// Keys `author_rotateKeys` generated on the operator's node.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RotatedKeys {
+    /// Node the keys were generated on and are stored in.
+    pub endpoint: String,
+    /// Encoded `SessionKeys`, what `set_keys` registers.
+    pub keys: Vec<u8>,
+    pub decoded: Vec<SessionKey>,
+}
+
+/// Split encoded session keys into their public keys, named by the fields of the runtime's
+/// `SessionKeys` type. `key_types` are the `(public, key type)` pairs of the
+/// `SessionKeys_decode_session_keys` runtime api, in the same order.
+pub fn decode_keys(metadata: &Metadata, keys: &[u8], key_types: &[(Vec<u8>, [u8; 4])]) -> Result<Vec<SessionKey>> {
+    let keys_ty = metadata.call_arg_ty("Session", "set_keys", "keys")?;
+    let input = &mut &keys[..];
+    let value = Value::decode(&metadata.types, keys_ty, input)?;
+    if !input.is_empty() {
+        bail!("{} bytes left after the session keys", input.len());
+    }
+    let fields = value.fields().ok_or(anyhow!("session keys are not a struct"))?;
+    if fields.len() != key_types.len() {
+        bail!("runtime decoded {} keys, its SessionKeys has {}", key_types.len(), fields.len());
+    }
+    fields.iter().zip(key_types).map(|((name, value), (public, key_type))| {
+        let bytes = value.as_bytes().ok_or(anyhow!("session key {:?} is not a public key", name))?;
+        if bytes != public.as_slice() {
+            bail!("session key {:?} does not match the runtime's decoding", name);
+        }
+        Ok(SessionKey {
+            name: name.clone().unwrap_or_default(),
+            key_type: String::from_utf8_lossy(key_type).to_string(),
+            public: public.clone(),
+        })
+    }).collect()
+}
+
+/// `Session::set_keys` registering encoded `keys` for the next session. Runtimes so far ignore
+/// the ownership `proof`, an empty one is accepted.
+pub fn set_keys_call(metadata: &Metadata, keys: &[u8], proof: &[u8]) -> Result<EncodedCall> {
+    let call_index = metadata.call_index("Session", "set_keys")?;
+    // the keys are already encoded, unlike the proof they carry no length prefix
+    Ok(EncodedCall((call_index, EncodedCall(keys.to_vec()), proof.to_vec()).encode()))
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    #[test]
+    fn test_decode_keys() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let keys = SessionKeys { grandpa: [1; 32], babe: [2; 32] }.encode();
+        let key_types = vec![([1u8; 32].to_vec(), *b"gran"), ([2u8; 32].to_vec(), *b"babe")];
+        let decoded = decode_keys(&metadata, &keys, &key_types).unwrap();
+        assert_eq!(decoded[0], SessionKey { name: "grandpa".to_string(), key_type: "gran".to_string(), public: vec![1; 32] });
+        assert_eq!((decoded[1].name.as_str(), decoded[1].key_type.as_str()), ("babe", "babe"));
+
+        assert!(decode_keys(&metadata, &keys[..40], &key_types).is_err());
+        assert!(decode_keys(&metadata, &[keys.clone(), vec![0]].concat(), &key_types).is_err());
+        assert!(decode_keys(&metadata, &keys, &key_types[..1]).is_err());
+        assert!(decode_keys(&metadata, &keys, &[key_types[1].clone(), key_types[0].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_set_keys_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let keys = SessionKeys { grandpa: [1; 32], babe: [2; 32] };
+        let call = set_keys_call(&metadata, &keys.encode(), &[]).unwrap();
+        let expected = RuntimeCall::Session(SessionCall::set_keys { keys: SessionKeys { grandpa: [1; 32], babe: [2; 32] }, proof: vec![] });
+        assert_eq!(call.0, expected.encode());
+        assert_eq!(call.0[..2], [SESSION_INDEX, 0]);
+    }
+}
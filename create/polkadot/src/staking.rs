@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use codec::{Compact, Decode, Encode};
+use scale_info::TypeInfo;
+use sp_core::crypto::AccountId32;
+
+use crate::{EncodedCall, GenericAddress};
+use crate::address;
+use crate::metadata::Metadata;
+
+/// Where staking rewards are paid, as `pallet_staking::RewardDestination`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub enum RewardDestination {
+    /// Paid to the stash and bonded, compounding the stake.
+    #[default]
+    Staked,
+    /// Paid to the stash, free to spend.
+    Stash,
+    Controller,
+    Account(AccountId32),
+    None,
+}
+
+impl fmt::Display for RewardDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RewardDestination::Staked => write!(f, "Stash, compounded"),
+            RewardDestination::Stash => write!(f, "Stash"),
+            RewardDestination::Controller => write!(f, "Controller"),
+            RewardDestination::Account(account) => write!(f, "Account {}", account),
+            RewardDestination::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Funds being unbonded, free to withdraw from `era` on.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct UnlockChunk {
+    #[codec(compact)]
+    pub value: u128,
+    #[codec(compact)]
+    pub era: u32,
+}
+
+/// `Staking::Ledger` of a controller.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct StakingLedger {
+    pub stash: AccountId32,
+    /// Bonded and unlocking.
+    #[codec(compact)]
+    pub total: u128,
+    /// Bonded, counted for election.
+    #[codec(compact)]
+    pub active: u128,
+    pub unlocking: Vec<UnlockChunk>,
+    /// Eras rewards were paid for, `legacy_claimed_rewards` in newer runtimes.
+    pub claimed_rewards: Vec<u32>,
+}
+
+/// `Staking::Nominators` of a stash.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct Nominations {
+    pub targets: Vec<AccountId32>,
+    /// Era the nominations were made in, they count from the next one.
+    pub submitted_in: u32,
+    /// Cleared by a slash of a target.
+    pub suppressed: bool,
+}
+
+/// `Staking::ActiveEra`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct ActiveEraInfo {
+    pub index: u32,
+    /// Unix time in milliseconds, `None` until the first block of the era.
+    pub start: Option<u64>,
+}
+
+/// `Staking::SlashingSpans` of a stash that was slashed.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode, TypeInfo)]
+pub struct SlashingSpans {
+    pub span_index: u32,
+    pub last_start: u32,
+    pub last_nonzero_slash: u32,
+    /// Lengths of the earlier spans, newest first.
+    pub prior: Vec<u32>,
+}
+
+impl SlashingSpans {
+    /// What `withdraw_unbonded` needs as `num_slashing_spans`.
+    pub fn count(&self) -> u32 {
+        self.prior.len() as u32 + 1
+    }
+}
+
+
+/// A `Staking` call signed by the stash, which is its own controller.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum StakingAction {
+    Bond { value: u128, payee: RewardDestination },
+    BondExtra { value: u128 },
+    /// Validators by address of any network or public key.
+    Nominate { targets: Vec<String> },
+    Chill,
+    Unbond { value: u128 },
+    /// Bond again funds that are still unlocking.
+    Rebond { value: u128 },
+    /// Unlocked funds become free, slashing spans from `Staking::SlashingSpans`.
+    WithdrawUnbonded { num_slashing_spans: u32 },
+    SetPayee { payee: RewardDestination },
+}
+
+impl StakingAction {
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            StakingAction::Bond { .. } => "bond",
+            StakingAction::BondExtra { .. } => "bond_extra",
+            StakingAction::Nominate { .. } => "nominate",
+            StakingAction::Chill => "chill",
+            StakingAction::Unbond { .. } => "unbond",
+            StakingAction::Rebond { .. } => "rebond",
+            StakingAction::WithdrawUnbonded { .. } => "withdraw_unbonded",
+            StakingAction::SetPayee { .. } => "set_payee",
+        }
+    }
+
+    /// The call in `metadata`. `stash` is the controller for runtimes whose `bond` still takes
+    /// one.
+    pub fn call(&self, metadata: &Metadata, stash: &str) -> Result<EncodedCall> {
+        let call_index = metadata.call_index("Staking", self.call_name())?;
+        let call = match self {
+            StakingAction::Bond { value, payee } => {
+                if metadata.call_args("Staking", "bond")?.first().map(String::as_str) == Some("controller") {
+                    (call_index, lookup(stash)?, Compact(*value), payee).encode()
+                } else {
+                    (call_index, Compact(*value), payee).encode()
+                }
+            }
+            StakingAction::BondExtra { value } | StakingAction::Unbond { value } | StakingAction::Rebond { value } => {
+                (call_index, Compact(*value)).encode()
+            }
+            StakingAction::Nominate { targets } => {
+                let targets = targets.iter().map(|target| lookup(target)).collect::<Result<Vec<_>>>()?;
+                (call_index, targets).encode()
+            }
+            StakingAction::Chill => call_index.encode(),
+            StakingAction::WithdrawUnbonded { num_slashing_spans } => (call_index, num_slashing_spans).encode(),
+            StakingAction::SetPayee { payee } => (call_index, payee).encode(),
+        };
+        Ok(EncodedCall(call))
+    }
+}
+
+fn lookup(addr: &str) -> Result<GenericAddress> {
+    let account = address::parse_address(addr).map_err(|e| anyhow!("invalid address {}: {}", addr, e))?.account;
+    Ok(GenericAddress::Id(account))
+}
+
+
+/// What a stash has at stake, see `Client::staking_info`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct StakingInfo {
+    /// `None` while the stash is not bonded.
+    pub controller: Option<AccountId32>,
+    pub ledger: Option<StakingLedger>,
+    pub payee: RewardDestination,
+    pub nominations: Option<Nominations>,
+    pub active_era: Option<u32>,
+    /// Eras unbonded funds stay locked.
+    pub bonding_duration: u32,
+    /// Of `Staking::SlashingSpans`, 0 for a stash never slashed.
+    pub slashing_spans: u32,
+}
+
+impl StakingInfo {
+    pub fn is_bonded(&self) -> bool {
+        self.ledger.is_some()
+    }
+
+    pub fn bonded(&self) -> u128 {
+        self.ledger.as_ref().map(|ledger| ledger.active).unwrap_or(0)
+    }
+
+    /// Amounts still unlocking by the era they unlock at, earliest first.
+    pub fn unlocking(&self) -> Vec<(u32, u128)> {
+        let mut eras = BTreeMap::new();
+        for chunk in self.chunks().filter(|chunk| !self.is_unlocked(chunk)) {
+            let value: &mut u128 = eras.entry(chunk.era).or_default();
+            *value = value.saturating_add(chunk.value);
+        }
+        eras.into_iter().collect()
+    }
+
+    /// Unlocked, waiting for `withdraw_unbonded`.
+    pub fn redeemable(&self) -> u128 {
+        self.chunks().filter(|chunk| self.is_unlocked(chunk)).fold(0, |total, chunk| total.saturating_add(chunk.value))
+    }
+
+    fn chunks(&self) -> impl Iterator<Item=&UnlockChunk> {
+        self.ledger.iter().flat_map(|ledger| ledger.unlocking.iter())
+    }
+
+    fn is_unlocked(&self, chunk: &UnlockChunk) -> bool {
+        self.active_era.map(|era| chunk.era <= era).unwrap_or(false)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    #[test]
+    fn test_staking_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let alice = address::parse_address(ALICE).unwrap().account;
+        let bond = StakingAction::Bond { value: 100, payee: RewardDestination::Stash }.call(&metadata, ALICE).unwrap();
+        // the fixture runtime still takes a controller
+        let expected = RuntimeCall::Staking(StakingCall::bond { controller: GenericAddress::Id(alice.clone()), value: 100, payee: RewardDestination::Stash });
+        assert_eq!(bond.0, expected.encode());
+
+        let nominate = StakingAction::Nominate { targets: vec![ALICE.to_string()] }.call(&metadata, ALICE).unwrap();
+        assert_eq!(nominate.0, RuntimeCall::Staking(StakingCall::nominate { targets: vec![GenericAddress::Id(alice)] }).encode());
+        assert_eq!(StakingAction::Chill.call(&metadata, ALICE).unwrap().0, [STAKING_INDEX, 6]);
+        assert_eq!(StakingAction::Rebond { value: 5 }.call(&metadata, ALICE).unwrap().0, RuntimeCall::Staking(StakingCall::rebond { value: 5 }).encode());
+        assert_eq!(StakingAction::WithdrawUnbonded { num_slashing_spans: 0 }.call(&metadata, ALICE).unwrap().0, [STAKING_INDEX, 3, 0, 0, 0, 0]);
+        assert!(StakingAction::Nominate { targets: vec!["x".to_string()] }.call(&metadata, ALICE).is_err());
+    }
+
+    #[test]
+    fn test_unlocking() {
+        let chunk = |value, era| UnlockChunk { value, era };
+        let info = StakingInfo {
+            ledger: Some(StakingLedger {
+                stash: AccountId32::new([1; 32]),
+                total: 100,
+                active: 40,
+                unlocking: vec![chunk(10, 8), chunk(20, 12), chunk(5, 10), chunk(25, 12)],
+                claimed_rewards: vec![],
+            }),
+            active_era: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(info.bonded(), 40);
+        assert_eq!(info.redeemable(), 15);
+        assert_eq!(info.unlocking(), vec![(12, 45)]);
+        assert_eq!(StakingInfo::default().unlocking(), vec![]);
+        assert!(!StakingInfo::default().is_bonded());
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+use codec::{Decode, Encode};
+use sp_core::hashing::blake2_256;
+
+use crate::decode::decode_extrinsic;
+use crate::events::decode_events;
+use crate::metadata::Metadata;
+use crate::rpc::types::{Block, TransactionStatus};
+use crate::utility::{self, ItemResult};
+
+/// Where a submitted extrinsic is, from the pool to a finalized block.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum TxStatus {
+    /// Sent, nothing heard back yet.
+    Submitted,
+    /// In the pool, waiting for an earlier nonce.
+    Future,
+    Ready,
+    /// Gossiped to this many peers.
+    Broadcast(u32),
+    InBlock,
+    /// The block it was in is no longer on the best chain.
+    Retracted,
+    /// Finality took too long, the node stopped watching.
+    FinalityTimeout,
+    Finalized,
+    /// Replaced by the extrinsic with this hash.
+    Usurped(String),
+    Dropped,
+    Invalid,
+    /// The watch ended before a final status, e.g. the connection dropped.
+    Lost,
+}
+
+impl TxStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(self, TxStatus::Finalized | TxStatus::FinalityTimeout | TxStatus::Usurped(_) | TxStatus::Dropped | TxStatus::Invalid | TxStatus::Lost)
+    }
+}
+
+impl From<&TransactionStatus> for TxStatus {
+    fn from(status: &TransactionStatus) -> Self {
+        match status {
+            TransactionStatus::Future => TxStatus::Future,
+            TransactionStatus::Ready => TxStatus::Ready,
+            TransactionStatus::Broadcast(peers) => TxStatus::Broadcast(peers.len() as u32),
+            TransactionStatus::InBlock(_) => TxStatus::InBlock,
+            TransactionStatus::Retracted(_) => TxStatus::Retracted,
+            TransactionStatus::FinalityTimeout(_) => TxStatus::FinalityTimeout,
+            TransactionStatus::Finalized(_) => TxStatus::Finalized,
+            TransactionStatus::Usurped(hash) => TxStatus::Usurped(hash.clone()),
+            TransactionStatus::Dropped => TxStatus::Dropped,
+            TransactionStatus::Invalid => TxStatus::Invalid,
+        }
+    }
+}
+
+
+/// Result of the dispatch, from the `System` events of the block.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum TxOutcome {
+    Success,
+    /// The dispatch error, module errors by name like `Balances.InsufficientBalance`.
+    Failed(String),
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct TrackedTx {
+    /// `0x` prefixed blake2_256 of the encoded extrinsic.
+    pub hash: String,
+    pub status: TxStatus,
+    pub block_hash: Option<String>,
+    /// Position of the extrinsic in the block.
+    pub index: Option<u32>,
+    pub outcome: Option<TxOutcome>,
+    /// Per call of a `Utility` batch, empty otherwise.
+    pub items: Vec<ItemResult>,
+}
+
+impl TrackedTx {
+    pub fn new(extrinsic: &str) -> Result<Self> {
+        Ok(TrackedTx {
+            hash: extrinsic_hash(extrinsic)?,
+            status: TxStatus::Submitted,
+            block_hash: None,
+            index: None,
+            outcome: None,
+            items: Vec::new(),
+        })
+    }
+
+    /// Apply a pool status, dropping the block of a retracted extrinsic.
+    pub fn update(&mut self, status: &TransactionStatus) {
+        if let TransactionStatus::Retracted(_) = status {
+            self.block_hash = None;
+            self.index = None;
+            self.outcome = None;
+            self.items.clear();
+        }
+        self.status = TxStatus::from(status);
+    }
+}
+
+
+/// Hash of a hex encoded extrinsic, as returned by `author_submitExtrinsic`.
+pub fn extrinsic_hash(extrinsic: &str) -> Result<String> {
+    let bytes = hex::decode(extrinsic.trim_start_matches("0x"))?;
+    Ok(format!("0x{}", hex::encode(blake2_256(&bytes))))
+}
+
+/// Index of the extrinsic with `hash` in `block`.
+pub fn extrinsic_index(block: &Block, hash: &str) -> Option<u32> {
+    block.extrinsics.iter()
+        .position(|extrinsic| extrinsic_hash(extrinsic).map(|h| h == hash).unwrap_or(false))
+        .map(|index| index as u32)
+}
+
+/// Find `ExtrinsicSuccess` or `ExtrinsicFailed` for the extrinsic at `index` in the encoded
+/// `System::Events` of its block.
+pub fn extrinsic_outcome(metadata: &Metadata, events: &[u8], index: u32) -> Result<Option<TxOutcome>> {
+    for event in decode_events(metadata, events)? {
+        if event.extrinsic() != Some(index) {
+            continue;
+        }
+        if event.is("System", "ExtrinsicSuccess") {
+            return Ok(Some(TxOutcome::Success));
+        }
+        if let Some(failed) = event.extrinsic_failed(metadata) {
+            return Ok(Some(TxOutcome::Failed(failed.error.to_string())));
+        }
+    }
+    Ok(None)
+}
+
+/// Result of every call when the hex encoded `extrinsic` at `index` is a `Utility` batch, from
+/// the encoded `System::Events` of its block. Empty for other extrinsics.
+pub fn batch_items(metadata: &Metadata, extrinsic: &str, events: &[u8], index: u32) -> Result<Vec<ItemResult>> {
+    let decoded = decode_extrinsic(metadata, extrinsic)?;
+    match utility::batch_len(&decoded.pallet, &decoded.call, decoded.arg("calls")) {
+        Some(items) => Ok(utility::batch_results(metadata, &decode_events(metadata, events)?, index, items)),
+        None => Ok(Vec::new()),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use codec::Encode;
+
+    use crate::metadata::fixture::{self, *};
+    use crate::rpc::types::Header;
+
+    use super::*;
+
+    #[test]
+    fn test_outcome() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let events = fixture::events();
+        assert_eq!(extrinsic_outcome(&metadata, &events, 1).unwrap(), Some(TxOutcome::Success));
+        assert_eq!(extrinsic_outcome(&metadata, &events, 2).unwrap(), Some(TxOutcome::Failed("Balances.InsufficientBalance".to_string())));
+        assert_eq!(extrinsic_outcome(&metadata, &events, 3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_index() {
+        let block = Block {
+            header: Header { parent_hash: "0x00".to_string(), number: "0x1".to_string(), state_root: "0x00".to_string(), extrinsics_root: "0x00".to_string() },
+            extrinsics: vec!["0x0c0102".to_string(), "0x0c0304".to_string()],
+        };
+        let hash = extrinsic_hash("0c0304").unwrap();
+        assert_eq!(extrinsic_index(&block, &hash), Some(1));
+        assert_eq!(extrinsic_index(&block, &extrinsic_hash("0c0506").unwrap()), None);
+
+        let mut tx = TrackedTx::new("0x0c0304").unwrap();
+        assert_eq!(tx.hash, hash);
+        tx.update(&TransactionStatus::Broadcast(vec!["peer".to_string()]));
+        assert_eq!(tx.status, TxStatus::Broadcast(1));
+        tx.block_hash = Some("0x01".to_string());
+        tx.update(&TransactionStatus::Retracted("0x01".to_string()));
+        assert_eq!(tx.block_hash, None);
+        assert!(!tx.status.is_final());
+    }
+}
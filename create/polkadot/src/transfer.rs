@@ -0,0 +1,278 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use codec::{Compact, Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::{EncodedCall, GenericAddress};
+use crate::address;
+use crate::balance::Amount;
+use crate::metadata::Metadata;
+use crate::rpc::types::{AccountData, FeeEstimate};
+use crate::utility::BatchMode;
+
+/// Which `Balances` call moves the funds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum TransferKind {
+    /// `transfer_keep_alive`, fails rather than leaving the sender below the existential deposit.
+    KeepAlive,
+    /// `transfer_allow_death`, `transfer` in older runtimes.
+    AllowDeath,
+    /// `transfer_all`, sends everything transferable, the amount is only an estimate.
+    All { keep_alive: bool },
+}
+
+impl TransferKind {
+    pub fn all() -> [TransferKind; 4] {
+        [TransferKind::KeepAlive, TransferKind::AllowDeath, TransferKind::All { keep_alive: true }, TransferKind::All { keep_alive: false }]
+    }
+
+    pub fn keep_alive(&self) -> bool {
+        matches!(self, TransferKind::KeepAlive | TransferKind::All { keep_alive: true })
+    }
+
+    /// `[pallet_index, call_index]` of the call in `metadata`.
+    pub fn call_index(&self, metadata: &Metadata) -> Result<[u8; 2]> {
+        match self {
+            TransferKind::KeepAlive => metadata.call_index("Balances", "transfer_keep_alive"),
+            TransferKind::AllowDeath => metadata.call_index("Balances", "transfer_allow_death")
+                .or_else(|_| metadata.call_index("Balances", "transfer")),
+            TransferKind::All { .. } => metadata.call_index("Balances", "transfer_all"),
+        }
+    }
+}
+
+impl fmt::Display for TransferKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferKind::KeepAlive => write!(f, "Keep alive"),
+            TransferKind::AllowDeath => write!(f, "Allow death"),
+            TransferKind::All { keep_alive: true } => write!(f, "All, keep alive"),
+            TransferKind::All { keep_alive: false } => write!(f, "All"),
+        }
+    }
+}
+
+/// The `Balances` call of `kind` sending `amount` to `to`, an address of any network or a
+/// public key.
+pub fn transfer_call(metadata: &Metadata, kind: TransferKind, to: &str, amount: u128) -> Result<EncodedCall> {
+    let call_index = kind.call_index(metadata)?;
+    let dest = GenericAddress::Id(address::parse_address(to).map_err(|e| anyhow!("invalid destination {}: {}", to, e))?.account);
+    let call = match kind {
+        TransferKind::All { keep_alive } => (call_index, dest, keep_alive).encode(),
+        _ => (call_index, dest, Compact(amount)).encode(),
+    };
+    Ok(EncodedCall(call))
+}
+
+
+/// What the runtime would make of a transfer, found before it is submitted.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum TransferIssue {
+    /// Amount and fee are more than the sender can spend, frozen balance excluded.
+    InsufficientBalance { needed: u128, usable: u128 },
+    /// A keep alive transfer leaving less than the existential deposit is rejected.
+    KeepAliveFails { remaining: u128, existential_deposit: u128 },
+    /// The sender account is removed and what is left below the existential deposit is lost.
+    SenderReaped { lost: u128 },
+    /// A recipient ending below the existential deposit is rejected.
+    RecipientBelowDeposit { recipient: String, balance: u128, existential_deposit: u128 },
+}
+
+impl TransferIssue {
+    /// The runtime would reject the transfer, only warnings may be confirmed.
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, TransferIssue::SenderReaped { .. })
+    }
+
+    /// Describe the issue with amounts shown by `format`.
+    pub fn message<F: Fn(u128) -> String>(&self, format: F) -> String {
+        match self {
+            TransferIssue::InsufficientBalance { needed, usable } => {
+                format!("amount and fee need {} but only {} can be spent", format(*needed), format(*usable))
+            }
+            TransferIssue::KeepAliveFails { remaining, existential_deposit } => {
+                format!("would leave {}, below the existential deposit of {}, and keep alive fails", format(*remaining), format(*existential_deposit))
+            }
+            TransferIssue::SenderReaped { lost } => {
+                format!("your account will be reaped and the remaining {} lost", format(*lost))
+            }
+            TransferIssue::RecipientBelowDeposit { recipient, balance, existential_deposit } => {
+                format!("{} would hold {}, below the existential deposit of {}", recipient, format(*balance), format(*existential_deposit))
+            }
+        }
+    }
+}
+
+impl fmt::Display for TransferIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message(|plancks| plancks.to_string()))
+    }
+}
+
+
+/// A signed transfer waiting for the user to confirm its cost.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct PreparedTransfer {
+    /// Hex encoded signed extrinsic, without `0x`.
+    pub extrinsic: String,
+    pub kind: TransferKind,
+    /// `Some` when the transfers to `recipients` are sent as one `Utility` batch.
+    pub batch: Option<BatchMode>,
+    /// Address and amount of every transfer, in call order.
+    pub recipients: Vec<(String, u128)>,
+    /// Of all transfers, estimated for `TransferKind::All`.
+    pub amount: u128,
+    pub fee: FeeEstimate,
+    /// Checked against the existential deposit, blocking ones keep it from being submitted.
+    pub issues: Vec<TransferIssue>,
+}
+
+impl PreparedTransfer {
+    pub fn total(&self) -> u128 {
+        self.amount.saturating_add(self.fee.total_fee())
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.issues.iter().any(TransferIssue::is_blocking)
+    }
+}
+
+
+/// Plancks `transfer_all` sends after the fee, everything above the frozen balance and, to
+/// keep alive, above the existential deposit.
+pub fn transfer_all_amount(sender: &AccountData, fee: u128, keep_alive: bool, existential_deposit: u128) -> u128 {
+    let floor = frozen(sender).max(if keep_alive { existential_deposit } else { 0 });
+    sender.free.saturating_sub(fee).saturating_sub(floor)
+}
+
+/// Issues of sending `amount` from `sender`, paying `fee`, to `recipient` at address `to`.
+pub fn check_transfer(kind: TransferKind, to: &str, amount: u128, fee: u128, sender: &AccountData, recipient: &AccountData, existential_deposit: u128) -> Vec<TransferIssue> {
+    check_sender(kind, amount, fee, sender, existential_deposit).into_iter()
+        .chain(check_recipient(to, amount, recipient, existential_deposit))
+        .collect()
+}
+
+/// Whether `sender` can pay `amount` and `fee`, and is kept alive or reaped as `kind` allows.
+pub fn check_sender(kind: TransferKind, amount: u128, fee: u128, sender: &AccountData, existential_deposit: u128) -> Option<TransferIssue> {
+    let needed = amount.saturating_add(fee);
+    let usable = sender.free.saturating_sub(frozen(sender));
+    if needed > usable {
+        return Some(TransferIssue::InsufficientBalance { needed, usable });
+    }
+    let remaining = sender.free - needed;
+    // reserved balance keeps the account alive
+    if remaining.saturating_add(sender.reserved) >= existential_deposit {
+        None
+    } else if kind.keep_alive() {
+        Some(TransferIssue::KeepAliveFails { remaining, existential_deposit })
+    } else {
+        Some(TransferIssue::SenderReaped { lost: remaining })
+    }
+}
+
+/// Whether `recipient` at address `to` reaches the existential deposit with `amount`.
+pub fn check_recipient(to: &str, amount: u128, recipient: &AccountData, existential_deposit: u128) -> Option<TransferIssue> {
+    let balance = recipient.free.saturating_add(recipient.reserved).saturating_add(amount);
+    (balance < existential_deposit).then(|| TransferIssue::RecipientBelowDeposit { recipient: to.to_string(), balance, existential_deposit })
+}
+
+/// One `address amount` per line, amounts in tokens of `decimals` and addresses of the network
+/// with `prefix` or public keys, sent as addresses of that network. Blank lines are skipped.
+pub fn parse_recipients(text: &str, decimals: u8, prefix: u16) -> Result<Vec<(String, u128)>> {
+    let mut recipients = Vec::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let (to, amount) = line.trim().split_once(char::is_whitespace).ok_or(anyhow!("line {}: expected an address and an amount", i + 1))?;
+        let to = address::check_address(to, prefix).map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+        let amount = Amount::parse(amount, decimals).map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+        recipients.push((to.to_ss58(prefix), amount.plancks()));
+    }
+    if recipients.is_empty() {
+        bail!("no recipients");
+    }
+    Ok(recipients)
+}
+
+fn frozen(account: &AccountData) -> u128 {
+    account.misc_frozen.max(account.fee_frozen)
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::metadata::fixture;
+
+    use super::*;
+
+    const TO: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+    const ED: u128 = 100;
+    const FEE: u128 = 10;
+
+    fn account(free: u128) -> AccountData {
+        AccountData { free, ..Default::default() }
+    }
+
+    #[test]
+    fn test_call_index() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        assert_eq!(TransferKind::KeepAlive.call_index(&metadata).unwrap(), [5, 3]);
+        // the fixture runtime predates the rename
+        assert_eq!(TransferKind::AllowDeath.call_index(&metadata).unwrap(), [5, 0]);
+        assert_eq!(TransferKind::All { keep_alive: true }.call_index(&metadata).unwrap(), [5, 4]);
+    }
+
+    #[test]
+    fn test_check_transfer() {
+        let funded = account(1_000);
+        assert!(check_transfer(TransferKind::KeepAlive, TO, 500, FEE, &account(1_000), &funded, ED).is_empty());
+        assert_eq!(check_transfer(TransferKind::KeepAlive, TO, 950, FEE, &account(1_000), &funded, ED), vec![TransferIssue::KeepAliveFails { remaining: 40, existential_deposit: ED }]);
+        let reaped = check_transfer(TransferKind::AllowDeath, TO, 950, FEE, &account(1_000), &funded, ED);
+        assert_eq!(reaped, vec![TransferIssue::SenderReaped { lost: 40 }]);
+        assert!(!reaped[0].is_blocking());
+        assert_eq!(reaped[0].to_string(), "your account will be reaped and the remaining 40 lost");
+
+        let mut reserved = account(1_000);
+        reserved.reserved = ED;
+        assert!(check_transfer(TransferKind::KeepAlive, TO, 990, FEE, &reserved, &funded, ED).is_empty());
+        let mut frozen = account(1_000);
+        frozen.misc_frozen = 600;
+        assert_eq!(check_transfer(TransferKind::AllowDeath, TO, 500, FEE, &frozen, &funded, ED), vec![TransferIssue::InsufficientBalance { needed: 510, usable: 400 }]);
+
+        // dust to a new account
+        let issues = check_transfer(TransferKind::KeepAlive, TO, 50, FEE, &account(1_000), &account(0), ED);
+        assert_eq!(issues, vec![TransferIssue::RecipientBelowDeposit { recipient: TO.to_string(), balance: 50, existential_deposit: ED }]);
+        assert!(issues[0].is_blocking());
+        assert_eq!(issues[0].to_string(), format!("{} would hold 50, below the existential deposit of 100", TO));
+        assert!(check_transfer(TransferKind::KeepAlive, TO, 50, FEE, &account(1_000), &account(60), ED).is_empty());
+    }
+
+    #[test]
+    fn test_parse_recipients() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let text = format!("{} 1.5\n\n  {}\t1 000\n", alice, TO);
+        assert_eq!(parse_recipients(&text, 2, 42).unwrap(), vec![(alice.to_string(), 150), (TO.to_string(), 100_000)]);
+        let key = format!("0x{} 1", hex::encode([7u8; 32]));
+        assert_eq!(parse_recipients(&key, 0, 0).unwrap()[0].1, 1);
+
+        assert_eq!(parse_recipients(&format!("{} 1.555", alice), 2, 42).unwrap_err().to_string(), "line 1: amount has more than 2 decimals");
+        assert!(parse_recipients(&format!("{} 1\n{}", alice, TO), 2, 42).unwrap_err().to_string().starts_with("line 2: expected"));
+        assert!(parse_recipients(&format!("{} 1", alice), 2, 0).unwrap_err().to_string().contains("you are on Polkadot"));
+        assert!(parse_recipients(" \n", 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_transfer_all() {
+        let mut sender = account(1_000);
+        assert_eq!(transfer_all_amount(&sender, FEE, false, ED), 990);
+        assert_eq!(transfer_all_amount(&sender, FEE, true, ED), 890);
+        sender.fee_frozen = 300;
+        assert_eq!(transfer_all_amount(&sender, FEE, true, ED), 690);
+        assert_eq!(transfer_all_amount(&account(50), FEE, true, ED), 0);
+
+        let amount = transfer_all_amount(&account(1_000), FEE, false, ED);
+        let issues = check_transfer(TransferKind::All { keep_alive: false }, TO, amount, FEE, &account(1_000), &account(0), ED);
+        assert_eq!(issues, vec![TransferIssue::SenderReaped { lost: 0 }]);
+        let amount = transfer_all_amount(&account(1_000), FEE, true, ED);
+        assert!(check_transfer(TransferKind::All { keep_alive: true }, TO, amount, FEE, &account(1_000), &account(0), ED).is_empty());
+    }
+}
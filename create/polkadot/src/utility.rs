@@ -0,0 +1,176 @@
+use std::fmt;
+
+use anyhow::Result;
+use codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::EncodedCall;
+use crate::events::{self, EventRecord};
+use crate::metadata::Metadata;
+use crate::rpc::types::FeeEstimate;
+use crate::value::Value;
+
+/// How a `Utility` batch dispatches its calls.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum BatchMode {
+    /// `batch`, stops at the first failing call, the calls before it stay done.
+    Batch,
+    /// `batch_all`, all calls or none.
+    BatchAll,
+    /// `force_batch`, every call is tried, failing ones are skipped.
+    ForceBatch,
+}
+
+impl BatchMode {
+    pub fn all() -> [BatchMode; 3] {
+        [BatchMode::BatchAll, BatchMode::Batch, BatchMode::ForceBatch]
+    }
+
+    pub fn call_name(&self) -> &'static str {
+        match self {
+            BatchMode::Batch => "batch",
+            BatchMode::BatchAll => "batch_all",
+            BatchMode::ForceBatch => "force_batch",
+        }
+    }
+}
+
+impl fmt::Display for BatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchMode::Batch => write!(f, "Batch, stop at a failure"),
+            BatchMode::BatchAll => write!(f, "All or nothing"),
+            BatchMode::ForceBatch => write!(f, "Force, skip failures"),
+        }
+    }
+}
+
+/// What became of one call of a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum ItemResult {
+    Completed,
+    /// The dispatch error, named like `Balances.InsufficientBalance`.
+    Failed(String),
+    /// Not dispatched after an earlier call failed, or rolled back with a failed `batch_all`.
+    Skipped,
+}
+
+impl fmt::Display for ItemResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemResult::Completed => write!(f, "completed"),
+            ItemResult::Failed(error) => write!(f, "failed: {}", error),
+            ItemResult::Skipped => write!(f, "not executed"),
+        }
+    }
+}
+
+
+/// A signed call other than a transfer, waiting for the user to confirm its fee.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+pub struct PreparedCall {
+    /// Hex encoded signed extrinsic, without `0x`.
+    pub extrinsic: String,
+    /// The decoded call with its arguments, for the user to check.
+    pub description: String,
+    pub fee: FeeEstimate,
+}
+
+
+/// The `Utility` call of `mode` dispatching `calls` in order.
+pub fn batch_call(metadata: &Metadata, mode: BatchMode, calls: Vec<EncodedCall>) -> Result<EncodedCall> {
+    let call_index = metadata.call_index("Utility", mode.call_name())?;
+    Ok(EncodedCall((call_index, calls).encode()))
+}
+
+/// Number of calls of a decoded `Utility` batch extrinsic, `None` for other calls.
+pub fn batch_len(pallet: &str, call: &str, calls: Option<&Value>) -> Option<usize> {
+    let is_batch = pallet == "Utility" && BatchMode::all().iter().any(|mode| mode.call_name() == call);
+    match calls {
+        Some(Value::Sequence(calls)) if is_batch => Some(calls.len()),
+        _ => None,
+    }
+}
+
+/// Result of each of the `items` calls of the batch at `extrinsic`, from the `ItemCompleted`,
+/// `ItemFailed` and `BatchInterrupted` events of its block.
+pub fn batch_results(metadata: &Metadata, events: &[EventRecord], extrinsic: u32, items: usize) -> Vec<ItemResult> {
+    let mut results = vec![ItemResult::Skipped; items];
+    let mut next = 0;
+    let error = |event: &EventRecord| event.field("error", 0)
+        .map(|error| events::dispatch_error(metadata, error).to_string())
+        .unwrap_or_default();
+    for event in events.iter().filter(|event| event.extrinsic() == Some(extrinsic) && event.pallet == "Utility") {
+        let result = match event.variant.as_str() {
+            "ItemCompleted" => ItemResult::Completed,
+            "ItemFailed" => ItemResult::Failed(error(event)),
+            "BatchInterrupted" => {
+                // the calls after it never run
+                next = event.field("index", 0).and_then(Value::as_u128).unwrap_or(next as u128) as usize;
+                ItemResult::Failed(error(event))
+            }
+            _ => continue,
+        };
+        if let Some(slot) = results.get_mut(next) {
+            *slot = result;
+        }
+        next += 1;
+    }
+    results
+}
+
+
+#[cfg(test)]
+mod test {
+    use codec::Compact;
+
+    use crate::{AccountId, GenericAddress};
+    use crate::decode::decode_extrinsic;
+    use crate::events::decode_events;
+    use crate::metadata::fixture::{self, *};
+
+    use super::*;
+
+    #[test]
+    fn test_batch_call() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let transfer = |to: u8, amount: u128| EncodedCall(([5u8, 3u8], GenericAddress::Id(AccountId::new([to; 32])), Compact(amount)).encode());
+        let call = batch_call(&metadata, BatchMode::BatchAll, vec![transfer(1, 10), transfer(2, 20)]).unwrap();
+        assert_eq!(call.0[..2], [UTILITY_INDEX, 2]);
+        // the same bytes as the runtime's own call enum
+        let expected = RuntimeCall::Utility(UtilityCall::batch_all { calls: vec![
+            RuntimeCall::Balances(BalancesCall::transfer_keep_alive { dest: GenericAddress::Id(AccountId::new([1; 32])), value: 10 }),
+            RuntimeCall::Balances(BalancesCall::transfer_keep_alive { dest: GenericAddress::Id(AccountId::new([2; 32])), value: 20 }),
+        ] });
+        assert_eq!(call.encode(), expected.encode());
+
+        let mut xt = vec![4u8];
+        xt.extend(call.encode());
+        let decoded = decode_extrinsic(&metadata, &hex::encode(xt.encode())).unwrap();
+        assert_eq!(batch_len(&decoded.pallet, &decoded.call, decoded.arg("calls")), Some(2));
+        assert_eq!(batch_len("Balances", "transfer", None), None);
+    }
+
+    #[test]
+    fn test_batch_results() {
+        let metadata = Metadata::decode(&fixture::metadata()).unwrap();
+        let failed = || DispatchError::Module(ModuleError { index: 5, error: [2, 0, 0, 0] });
+        let record = |index, event| fixture::EventRecord::new(Phase::ApplyExtrinsic(index), RuntimeEvent::Utility(event));
+        let events = vec![
+            // batch interrupted at its second call
+            record(1, UtilityEvent::ItemCompleted),
+            record(1, UtilityEvent::BatchInterrupted { index: 1, error: failed() }),
+            // force_batch with a failing second call
+            record(2, UtilityEvent::ItemCompleted),
+            record(2, UtilityEvent::ItemFailed { error: failed() }),
+            record(2, UtilityEvent::ItemCompleted),
+            record(2, UtilityEvent::BatchCompletedWithErrors),
+        ].encode();
+        let events = decode_events(&metadata, &events).unwrap();
+        let insufficient = ItemResult::Failed("Balances.InsufficientBalance".to_string());
+        assert_eq!(batch_results(&metadata, &events, 1, 3), vec![ItemResult::Completed, insufficient.clone(), ItemResult::Skipped]);
+        assert_eq!(batch_results(&metadata, &events, 2, 3), vec![ItemResult::Completed, insufficient, ItemResult::Completed]);
+        // a failed batch_all leaves no item events
+        assert_eq!(batch_results(&metadata, &events, 3, 2), vec![ItemResult::Skipped, ItemResult::Skipped]);
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail, Result};
+use codec::{Compact, Decode};
+use scale_info::{PortableRegistry, TypeDef, TypeDefPrimitive};
+use scale_info::form::PortableForm;
+
+/// A SCALE value decoded with the type information of the runtime metadata, for data whose
+/// layout is only known at runtime such as events, call arguments and storage values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// Unsigned integers up to 128 bits, compact encoded ones included.
+    U128(u128),
+    I128(i128),
+    /// 256 bit integers as little endian bytes.
+    U256([u8; 32]),
+    I256([u8; 32]),
+    /// Sequences and arrays of `u8`, like account ids and hashes.
+    Bytes(Vec<u8>),
+    /// Structs and tuples, fields are unnamed for tuple structs and tuples.
+    Composite(Vec<(Option<String>, Value)>),
+    Sequence(Vec<Value>),
+    Variant {
+        name: String,
+        index: u8,
+        fields: Vec<(Option<String>, Value)>,
+    },
+    /// Raw bit sequence storage.
+    BitSequence(Vec<u8>),
+}
+
+
+impl Value {
+    /// Decode a value of type `ty`, advancing `input`.
+    pub fn decode(types: &PortableRegistry, ty: u32, input: &mut &[u8]) -> Result<Value> {
+        let resolved = types.resolve(ty).ok_or(anyhow!("type {} not found in metadata", ty))?;
+        match resolved.type_def() {
+            TypeDef::Composite(composite) => {
+                Ok(Value::Composite(decode_fields(types, composite.fields(), input)?))
+            }
+            TypeDef::Variant(variant) => {
+                let index = u8::decode(input)?;
+                let variant = variant.variants().iter().find(|v| v.index() == index)
+                    .ok_or(anyhow!("variant {} not found in type {}", index, ty))?;
+                Ok(Value::Variant {
+                    name: variant.name().clone(),
+                    index,
+                    fields: decode_fields(types, variant.fields(), input)?,
+                })
+            }
+            TypeDef::Sequence(sequence) => {
+                let len = Compact::<u32>::decode(input)?.0 as usize;
+                decode_items(types, sequence.type_param().id(), len, input)
+            }
+            TypeDef::Array(array) => {
+                decode_items(types, array.type_param().id(), array.len() as usize, input)
+            }
+            TypeDef::Tuple(tuple) => {
+                let mut fields = Vec::new();
+                for field in tuple.fields() {
+                    fields.push((None, Value::decode(types, field.id(), input)?));
+                }
+                Ok(Value::Composite(fields))
+            }
+            TypeDef::Primitive(primitive) => decode_primitive(primitive, input),
+            TypeDef::Compact(compact) => {
+                // compact of a wrapper like `Perbill` has the same encoding as its inner integer
+                let inner = types.resolve(compact.type_param().id()).ok_or(anyhow!("type {} not found in metadata", ty))?;
+                match inner.type_def() {
+                    TypeDef::Primitive(TypeDefPrimitive::U256) => bail!("compact u256 is not supported"),
+                    _ => Ok(Value::U128(Compact::<u128>::decode(input)?.0)),
+                }
+            }
+            TypeDef::BitSequence(bits) => {
+                let store = types.resolve(bits.bit_store_type().id()).ok_or(anyhow!("type {} not found in metadata", ty))?;
+                let store_bits = match store.type_def() {
+                    TypeDef::Primitive(TypeDefPrimitive::U8) => 8,
+                    TypeDef::Primitive(TypeDefPrimitive::U16) => 16,
+                    TypeDef::Primitive(TypeDefPrimitive::U32) => 32,
+                    TypeDef::Primitive(TypeDefPrimitive::U64) => 64,
+                    _ => bail!("unsupported bit store type in type {}", ty),
+                };
+                let len = Compact::<u32>::decode(input)?.0 as usize;
+                let bytes = (len + store_bits - 1) / store_bits * (store_bits / 8);
+                Ok(Value::BitSequence(take(input, bytes)?))
+            }
+        }
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            Value::U128(value) => Some(*value),
+            Value::Composite(fields) if fields.len() == 1 => fields[0].1.as_u128(),
+            _ => None,
+        }
+    }
+
+    /// Bytes of byte arrays and of newtypes around them, like `AccountId32`.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            Value::Composite(fields) if fields.len() == 1 => fields[0].1.as_bytes(),
+            _ => None,
+        }
+    }
+
+    /// Items of sequences and of newtypes around them, like `BoundedVec`.
+    pub fn as_sequence(&self) -> Option<&[Value]> {
+        match self {
+            Value::Sequence(items) => Some(items),
+            Value::Composite(fields) if fields.len() == 1 => fields[0].1.as_sequence(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Named field of a struct or enum variant.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields()?.iter().find(|(n, _)| n.as_deref() == Some(name)).map(|(_, v)| v)
+    }
+
+    /// Field by position, named or not.
+    pub fn at(&self, index: usize) -> Option<&Value> {
+        match self {
+            Value::Sequence(items) => items.get(index),
+            _ => self.fields()?.get(index).map(|(_, v)| v),
+        }
+    }
+
+    pub fn fields(&self) -> Option<&[(Option<String>, Value)]> {
+        match self {
+            Value::Composite(fields) => Some(fields),
+            Value::Variant { fields, .. } => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn variant_name(&self) -> Option<&str> {
+        match self {
+            Value::Variant { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+
+fn decode_fields(types: &PortableRegistry, fields: &[scale_info::Field<PortableForm>], input: &mut &[u8]) -> Result<Vec<(Option<String>, Value)>> {
+    let mut values = Vec::with_capacity(fields.len());
+    for field in fields {
+        values.push((field.name().cloned(), Value::decode(types, field.ty().id(), input)?));
+    }
+    Ok(values)
+}
+
+fn decode_items(types: &PortableRegistry, ty: u32, len: usize, input: &mut &[u8]) -> Result<Value> {
+    if let Some(TypeDef::Primitive(TypeDefPrimitive::U8)) = types.resolve(ty).map(|t| t.type_def()) {
+        return Ok(Value::Bytes(take(input, len)?));
+    }
+    let mut items = Vec::new();
+    for _ in 0..len {
+        items.push(Value::decode(types, ty, input)?);
+    }
+    Ok(Value::Sequence(items))
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Value> {
+    let value = match primitive {
+        TypeDefPrimitive::Bool => Value::Bool(bool::decode(input)?),
+        TypeDefPrimitive::Char => {
+            let code = u32::decode(input)?;
+            Value::Char(char::from_u32(code).ok_or(anyhow!("invalid char {}", code))?)
+        }
+        TypeDefPrimitive::Str => Value::Str(String::decode(input)?),
+        TypeDefPrimitive::U8 => Value::U128(u8::decode(input)? as u128),
+        TypeDefPrimitive::U16 => Value::U128(u16::decode(input)? as u128),
+        TypeDefPrimitive::U32 => Value::U128(u32::decode(input)? as u128),
+        TypeDefPrimitive::U64 => Value::U128(u64::decode(input)? as u128),
+        TypeDefPrimitive::U128 => Value::U128(u128::decode(input)?),
+        TypeDefPrimitive::U256 => Value::U256(<[u8; 32]>::decode(input)?),
+        TypeDefPrimitive::I8 => Value::I128(i8::decode(input)? as i128),
+        TypeDefPrimitive::I16 => Value::I128(i16::decode(input)? as i128),
+        TypeDefPrimitive::I32 => Value::I128(i32::decode(input)? as i128),
+        TypeDefPrimitive::I64 => Value::I128(i64::decode(input)? as i128),
+        TypeDefPrimitive::I128 => Value::I128(i128::decode(input)?),
+        TypeDefPrimitive::I256 => Value::I256(<[u8; 32]>::decode(input)?),
+    };
+    Ok(value)
+}
+
+fn take(input: &mut &[u8], len: usize) -> Result<Vec<u8>> {
+    if input.len() < len {
+        bail!("not enough data, need {} bytes, have {}", len, input.len());
+    }
+    let (head, tail) = input.split_at(len);
+    *input = tail;
+    Ok(head.to_vec())
+}
+
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{:?}", value),
+            Value::U128(value) => write!(f, "{}", value),
+            Value::I128(value) => write!(f, "{}", value),
+            Value::U256(value) | Value::I256(value) => write!(f, "0x{}", hex::encode(value)),
+            Value::Bytes(value) | Value::BitSequence(value) => write!(f, "0x{}", hex::encode(value)),
+            Value::Composite(fields) => {
+                // newtypes read better without the wrapper
+                if fields.len() == 1 && fields[0].0.is_none() {
+                    return write!(f, "{}", fields[0].1);
+                }
+                write!(f, "(")?;
+                write_fields(f, fields)?;
+                write!(f, ")")
+            }
+            Value::Sequence(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Variant { name, fields, .. } => {
+                write!(f, "{}", name)?;
+                // `Module(index: 5, error: ..)` rather than `Module((index: 5, error: ..))`
+                if let [(None, inner @ Value::Composite(inner_fields))] = fields.as_slice() {
+                    if inner_fields.len() > 1 || inner_fields.iter().any(|(name, _)| name.is_some()) {
+                        return write!(f, "{}", inner);
+                    }
+                }
+                if !fields.is_empty() {
+                    write!(f, "(")?;
+                    write_fields(f, fields)?;
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[(Option<String>, Value)]) -> fmt::Result {
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        match name {
+            Some(name) => write!(f, "{}: {}", name, value)?,
+            None => write!(f, "{}", value)?,
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use codec::Encode;
+    use scale_info::{meta_type, Registry, TypeInfo};
+
+    use super::*;
+
+    #[derive(Encode, TypeInfo)]
+    struct Transfer {
+        from: [u8; 32],
+        #[codec(compact)]
+        amount: u128,
+        memo: Vec<u8>,
+        tags: Vec<(u16, bool)>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Event {
+        Ping,
+        Transfer(Transfer),
+        Text { text: String, value: i32 },
+    }
+
+    fn registry() -> (PortableRegistry, u32) {
+        let mut registry = Registry::new();
+        let ty = registry.register_type(&meta_type::<Event>());
+        (registry.into(), ty.id())
+    }
+
+    #[test]
+    fn test_decode() {
+        let (types, ty) = registry();
+        let event = Event::Transfer(Transfer { from: [7u8; 32], amount: 1_000_000, memo: b"hi".to_vec(), tags: vec![(3, true)] });
+        let data = event.encode();
+        let input = &mut &data[..];
+        let value = Value::decode(&types, ty, input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(value.variant_name(), Some("Transfer"));
+        let transfer = value.at(0).unwrap();
+        assert_eq!(transfer.field("from").unwrap().as_bytes(), Some(&[7u8; 32][..]));
+        assert_eq!(transfer.field("amount").unwrap().as_u128(), Some(1_000_000));
+        assert_eq!(transfer.field("memo").unwrap().as_bytes(), Some(&b"hi"[..]));
+        assert_eq!(transfer.field("tags").unwrap().at(0).unwrap().at(1).unwrap().as_bool(), Some(true));
+
+        let data = Event::Text { text: "abc".to_string(), value: -5 }.encode();
+        let value = Value::decode(&types, ty, &mut &data[..]).unwrap();
+        assert_eq!(value.to_string(), "Text(text: \"abc\", value: -5)");
+        let data = Event::Ping.encode();
+        assert_eq!(Value::decode(&types, ty, &mut &data[..]).unwrap().to_string(), "Ping");
+        assert!(Value::decode(&types, ty, &mut &[9u8][..]).is_err());
+    }
+}
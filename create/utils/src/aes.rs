@@ -0,0 +1,296 @@
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use anyhow::{anyhow, bail, Result};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Magic bytes at the start of every keystore blob.
+const MAGIC: &[u8; 4] = b"UWKS";
+/// Current keystore format version.
+pub const KEYSTORE_VERSION: u8 = 1;
+/// Kdf identifier for scrypt.
+const KDF_SCRYPT: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const VERIFIER_LEN: usize = 32;
+/// magic + version + kdf + log_n + r + p
+const PARAMS_LEN: usize = 4 + 1 + 1 + 1 + 4 + 4;
+const HEADER_LEN: usize = PARAMS_LEN + SALT_LEN + NONCE_LEN;
+
+/// Largest accepted scrypt parameters, a tampered header must not make unlocking hang or
+/// exhaust memory. 2^20 * 8 * 128 bytes = 1 GiB.
+const MAX_LOG_N: u8 = 20;
+const MAX_R_P: u64 = 64;
+const MAX_KDF_MEMORY: u64 = 1 << 30;
+
+
+/// Scrypt cost parameters stored in the keystore header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        // 2^15 * 8 * 128 bytes = 32 MiB of memory per derivation
+        KdfParams {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Reject parameters outside of what a wallet would choose.
+    pub fn check(&self) -> Result<()> {
+        let (r, p) = (self.r as u64, self.p as u64);
+        if self.log_n == 0 || self.log_n > MAX_LOG_N {
+            bail!("kdf log_n {} out of range 1..={}", self.log_n, MAX_LOG_N);
+        }
+        if r == 0 || p == 0 || r * p > MAX_R_P {
+            bail!("kdf r {} p {} out of range, r * p at most {}", r, p, MAX_R_P);
+        }
+        if (128 * r) << self.log_n > MAX_KDF_MEMORY {
+            bail!("kdf needs more than {} MiB", MAX_KDF_MEMORY >> 20);
+        }
+        Ok(())
+    }
+
+    fn derive(&self, password: &str, salt: &[u8]) -> Result<[u8; KEY_LEN * 2]> {
+        let params = scrypt::Params::new(self.log_n, self.r, self.p).map_err(|e| anyhow!("invalid kdf params {}", e))?;
+        let mut output = [0u8; KEY_LEN * 2];
+        scrypt::scrypt(password.as_bytes(), salt, &params, &mut output).map_err(|e| anyhow!("kdf error {}", e))?;
+        Ok(output)
+    }
+}
+
+
+/// A mnemonic (or any other secret) sealed with a password.
+///
+/// The password is stretched with scrypt into 64 bytes: the first half is the AES-256-GCM key,
+/// the sha256 of the second half is kept as a verifier so a wrong password can be told apart
+/// from a corrupted blob. The header (magic, version, kdf params, salt, nonce) is bound to the
+/// ciphertext as associated data.
+///
+/// Layout: `UWKS | version | kdf | log_n | r (le) | p (le) | salt | nonce | verifier | ciphertext`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keystore {
+    pub version: u8,
+    pub kdf: KdfParams,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+    verifier: [u8; VERIFIER_LEN],
+    ciphertext: Vec<u8>,
+}
+
+
+impl Keystore {
+    pub fn seal(password: &str, secret: &[u8]) -> Result<Self> {
+        Keystore::seal_with_params(password, secret, KdfParams::default())
+    }
+
+    pub fn seal_with_params(password: &str, secret: &[u8], kdf: KdfParams) -> Result<Self> {
+        if password.is_empty() {
+            bail!("password is empty");
+        }
+        kdf.check()?;
+        let mut rng = rand::thread_rng();
+        let mut salt = [0u8; SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+
+        let derived = kdf.derive(password, &salt)?;
+        let mut keystore = Keystore {
+            version: KEYSTORE_VERSION,
+            kdf,
+            salt,
+            nonce,
+            verifier: verifier(&derived),
+            ciphertext: Vec::new(),
+        };
+        let cipher = Aes256Gcm::new(Key::from_slice(&derived[..KEY_LEN]));
+        let header = keystore.header();
+        keystore.ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), Payload { msg: secret, aad: &header })
+            .map_err(|_| anyhow!("encrypt keystore error"))?;
+        Ok(keystore)
+    }
+
+    /// Check the password against the stored verifier without decrypting.
+    pub fn verify(&self, password: &str) -> Result<bool> {
+        let derived = self.kdf.derive(password, &self.salt)?;
+        Ok(constant_time_eq(&verifier(&derived), &self.verifier))
+    }
+
+    pub fn unlock(&self, password: &str) -> Result<Vec<u8>> {
+        let derived = self.kdf.derive(password, &self.salt)?;
+        if !constant_time_eq(&verifier(&derived), &self.verifier) {
+            bail!("password not match");
+        }
+        let cipher = Aes256Gcm::new(Key::from_slice(&derived[..KEY_LEN]));
+        let header = self.header();
+        let secret = cipher.decrypt(Nonce::from_slice(&self.nonce), Payload { msg: &self.ciphertext, aad: &header })
+            .map_err(|_| anyhow!("keystore is corrupted"))?;
+        Ok(secret)
+    }
+
+    /// Re-seal the same secret under a new password.
+    pub fn change_password(&self, old_password: &str, new_password: &str) -> Result<Self> {
+        let secret = self.unlock(old_password)?;
+        Keystore::seal_with_params(new_password, &secret, self.kdf)
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(self.version);
+        header.push(KDF_SCRYPT);
+        header.push(self.kdf.log_n);
+        header.extend_from_slice(&self.kdf.r.to_le_bytes());
+        header.extend_from_slice(&self.kdf.p.to_le_bytes());
+        header.extend_from_slice(&self.salt);
+        header.extend_from_slice(&self.nonce);
+        header
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header();
+        bytes.extend_from_slice(&self.verifier);
+        bytes.extend_from_slice(&self.ciphertext);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LEN + VERIFIER_LEN || &bytes[..4] != MAGIC {
+            bail!("not a keystore");
+        }
+        let version = bytes[4];
+        if version != KEYSTORE_VERSION {
+            bail!("unsupported keystore version {}", version);
+        }
+        if bytes[5] != KDF_SCRYPT {
+            bail!("unsupported keystore kdf {}", bytes[5]);
+        }
+        let kdf = KdfParams {
+            log_n: bytes[6],
+            r: u32::from_le_bytes(bytes[7..11].try_into()?),
+            p: u32::from_le_bytes(bytes[11..15].try_into()?),
+        };
+        kdf.check()?;
+        let mut offset = PARAMS_LEN;
+        let salt: [u8; SALT_LEN] = bytes[offset..offset + SALT_LEN].try_into()?;
+        offset += SALT_LEN;
+        let nonce: [u8; NONCE_LEN] = bytes[offset..offset + NONCE_LEN].try_into()?;
+        offset += NONCE_LEN;
+        let verifier: [u8; VERIFIER_LEN] = bytes[offset..offset + VERIFIER_LEN].try_into()?;
+        offset += VERIFIER_LEN;
+        Ok(Keystore {
+            version,
+            kdf,
+            salt,
+            nonce,
+            verifier,
+            ciphertext: bytes[offset..].to_vec(),
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    pub fn from_hex(data: &str) -> Result<Self> {
+        let bytes = hex::decode(data)?;
+        Keystore::from_bytes(&bytes)
+    }
+}
+
+
+fn verifier(derived: &[u8; KEY_LEN * 2]) -> [u8; VERIFIER_LEN] {
+    let mut verifier = [0u8; VERIFIER_LEN];
+    verifier.copy_from_slice(&Sha256::digest(&derived[KEY_LEN..]));
+    verifier
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PHRASE: &str = "palace utility secret spice retire air notice wage shove alcohol render sphere";
+
+    fn fast_params() -> KdfParams {
+        KdfParams { log_n: 4, r: 8, p: 1 }
+    }
+
+    #[test]
+    fn test_seal_unlock() {
+        let keystore = Keystore::seal_with_params("abcd", PHRASE.as_bytes(), fast_params()).unwrap();
+        assert!(keystore.verify("abcd").unwrap());
+        assert!(!keystore.verify("abce").unwrap());
+        assert_eq!(keystore.unlock("abcd").unwrap(), PHRASE.as_bytes());
+        assert!(keystore.unlock("abce").is_err());
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let keystore = Keystore::seal_with_params("abcd", PHRASE.as_bytes(), fast_params()).unwrap();
+        let data = keystore.to_hex();
+        assert!(!data.contains(&hex::encode(PHRASE)));
+        let decoded = Keystore::from_hex(&data).unwrap();
+        assert_eq!(keystore, decoded);
+        assert_eq!(decoded.unlock("abcd").unwrap(), PHRASE.as_bytes());
+    }
+
+    #[test]
+    fn test_tampered_header() {
+        let keystore = Keystore::seal_with_params("abcd", PHRASE.as_bytes(), fast_params()).unwrap();
+        let mut bytes = keystore.to_bytes();
+        bytes[4] = 2;
+        assert!(Keystore::from_bytes(&bytes).is_err());
+        let mut bytes = keystore.to_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(Keystore::from_bytes(&bytes).unwrap().unlock("abcd").is_err());
+    }
+
+    #[test]
+    fn test_kdf_bounds() {
+        assert!(KdfParams::default().check().is_ok());
+        assert!(fast_params().check().is_ok());
+        let keystore = Keystore::seal_with_params("abcd", PHRASE.as_bytes(), fast_params()).unwrap();
+        let tampered = |offset: usize, value: &[u8]| {
+            let mut bytes = keystore.to_bytes();
+            bytes[offset..offset + value.len()].copy_from_slice(value);
+            Keystore::from_bytes(&bytes)
+        };
+        // log_n
+        assert!(tampered(6, &[21]).is_err());
+        assert!(tampered(6, &[0]).is_err());
+        // r
+        assert!(tampered(7, &u32::MAX.to_le_bytes()).is_err());
+        assert!(tampered(7, &0u32.to_le_bytes()).is_err());
+        // p
+        assert!(tampered(11, &65u32.to_le_bytes()).is_err());
+        // 2^20 * 16 * 128 bytes is 2 GiB
+        assert!(tampered(6, &[20, 16, 0, 0, 0]).is_err());
+        assert!(tampered(6, &[20, 8, 0, 0, 0]).is_ok());
+        assert!(Keystore::seal_with_params("abcd", PHRASE.as_bytes(), KdfParams { log_n: 4, r: 8, p: 9 }).is_err());
+    }
+
+    #[test]
+    fn test_change_password() {
+        let keystore = Keystore::seal_with_params("abcd", PHRASE.as_bytes(), fast_params()).unwrap();
+        let keystore = keystore.change_password("abcd", "efgh").unwrap();
+        assert!(keystore.unlock("abcd").is_err());
+        assert_eq!(keystore.unlock("efgh").unwrap(), PHRASE.as_bytes());
+    }
+}
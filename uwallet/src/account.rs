@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use coreui::state::AppState;
+use polkadot::keys::{CryptoScheme, Key, SecretUri};
+use utils::aes::{KdfParams, Keystore};
+
+use crate::network;
+
+/// Public description of an account derived from the wallet phrase, persisted as `ACCOUNTS`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Account {
+    pub name: String,
+    pub scheme: CryptoScheme,
+    /// Derivation junctions such as `//polkadot//0`, empty for the root key.
+    #[serde(default)]
+    pub path: String,
+    /// The `///password` part of the derivation, sealed with the wallet phrase.
+    #[serde(default)]
+    password: Option<String>,
+}
+
+impl Account {
+    pub fn new(name: &str, scheme: CryptoScheme) -> Self {
+        Self {
+            name: name.to_owned(),
+            scheme,
+            path: "".to_string(),
+            password: None,
+        }
+    }
+
+    /// Create an account from a derivation suffix like `//polkadot//0` or `/soft///pwd`.
+    pub fn derived(name: &str, scheme: CryptoScheme, phrase: &str, derivation: &str) -> Result<Self> {
+        let suri = SecretUri::from_str(&format!("{}{}", phrase, derivation.trim()))?;
+        // make sure the scheme supports the junctions before storing the account
+        Key::address_from_suri(scheme, &format!("{}{}", phrase, derivation.trim()), 0)?;
+        let password = match &suri.password {
+            // the phrase is the master secret, a light kdf is enough here
+            Some(password) => Some(Keystore::seal_with_params(phrase, password.as_bytes(), KdfParams { log_n: 10, r: 8, p: 1 })?.to_hex()),
+            None => None,
+        };
+        Ok(Self {
+            name: name.to_owned(),
+            scheme,
+            path: suri.path,
+            password,
+        })
+    }
+
+    /// The full secret uri of this account.
+    pub fn suri(&self, phrase: &str) -> Result<String> {
+        let mut suri = format!("{}{}", phrase, self.path);
+        if let Some(password) = &self.password {
+            let password = Keystore::from_hex(password)?.unlock(phrase)?;
+            suri.push_str("///");
+            suri.push_str(&String::from_utf8(password)?);
+        }
+        Ok(suri)
+    }
+
+    pub fn display_path(&self) -> String {
+        let path = if self.path.is_empty() { "root" } else { &self.path };
+        match &self.password {
+            Some(_) => format!("{}///***", path),
+            None => path.to_string(),
+        }
+    }
+
+    /// `network_id` is the SS58 prefix of the connected chain, see `network::ss58_prefix`.
+    pub fn address(&self, phrase: &str, network_id: u16) -> Result<String> {
+        Key::address_from_suri(self.scheme, &self.suri(phrase)?, network_id)
+    }
+}
+
+
+pub fn accounts(state: &AppState) -> Vec<Account> {
+    if let Some(data) = state.get_value("ACCOUNTS") {
+        if let Ok(accounts) = serde_json::from_str::<Vec<Account>>(&data) {
+            if !accounts.is_empty() {
+                return accounts;
+            }
+        }
+    }
+    // wallets created before accounts were stored always used ed25519
+    vec![Account::new("Account", CryptoScheme::Ed25519)]
+}
+
+pub fn set_accounts(state: &AppState, accounts: &[Account]) {
+    if let Ok(data) = serde_json::to_string(accounts) {
+        state.set_value("ACCOUNTS".to_owned(), data);
+    }
+}
+
+pub fn add_account(state: &AppState, account: Account) {
+    let mut list = accounts(state);
+    list.push(account);
+    set_accounts(state, &list);
+}
+
+pub fn current_index(state: &AppState) -> usize {
+    state.get_value("ACCOUNT_INDEX").and_then(|index| index.parse::<usize>().ok()).unwrap_or(0)
+}
+
+pub fn set_current_index(state: &AppState, index: usize) {
+    state.set_value("ACCOUNT_INDEX".to_owned(), index.to_string());
+}
+
+pub fn current_account(state: &AppState) -> Account {
+    let mut list = accounts(state);
+    let index = current_index(state);
+    if index < list.len() {
+        list.remove(index)
+    } else {
+        list.remove(0)
+    }
+}
+
+/// Address and secret uri of the current account, an error while the wallet is locked or when
+/// the account cannot be derived from the phrase.
+pub fn signer(state: &AppState) -> Result<(String, String)> {
+    let account = current_account(state);
+    let phrase = state.get_session_value("PHRASE").ok_or(anyhow!("wallet is locked"))?;
+    let address = account.address(&phrase, network::ss58_prefix(state))
+        .map_err(|e| anyhow!("account {} cannot be derived: {}", account.name, e))?;
+    // derived accounts have no seed of their own, sign with the full secret uri
+    let suri = account.suri(&phrase).map_err(|e| anyhow!("account {} cannot be derived: {}", account.name, e))?;
+    Ok((address, suri))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PHRASE: &str = "palace utility secret spice retire air notice wage shove alcohol render sphere";
+
+    #[test]
+    fn test() {
+        let state = AppState::new();
+        assert_eq!(current_account(&state).scheme, CryptoScheme::Ed25519);
+        set_accounts(&state, &[Account::new("Account", CryptoScheme::Sr25519)]);
+        assert_eq!(current_account(&state).scheme, CryptoScheme::Sr25519);
+    }
+
+    #[test]
+    fn test_derived() {
+        let state = AppState::new();
+        set_accounts(&state, &[Account::new("Account", CryptoScheme::Sr25519)]);
+        let account = Account::derived("Stash", CryptoScheme::Sr25519, PHRASE, "/soft///pwd").unwrap();
+        assert_eq!(account.path, "/soft");
+        assert_eq!(account.display_path(), "/soft///***");
+        assert_eq!(account.suri(PHRASE).unwrap(), format!("{}/soft///pwd", PHRASE));
+        add_account(&state, account.clone());
+        // the derivation password never reaches the persisted state in plain text
+        assert!(!state.get_value("ACCOUNTS").unwrap().contains("pwd"));
+        set_current_index(&state, 1);
+        assert_eq!(current_account(&state), account);
+        assert!(Account::derived("Bad", CryptoScheme::Ed25519, PHRASE, "/soft").is_err());
+    }
+
+    #[test]
+    fn test_signer() {
+        let state = AppState::new();
+        set_accounts(&state, &[Account::new("Account", CryptoScheme::Sr25519)]);
+        assert_eq!(signer(&state).unwrap_err().to_string(), "wallet is locked");
+        state.set_session_value("PHRASE".to_owned(), PHRASE.to_string());
+        let (address, suri) = signer(&state).unwrap();
+        assert_eq!(address, current_account(&state).address(PHRASE, 0).unwrap());
+        assert_eq!(suri, PHRASE);
+    }
+}
@@ -6,6 +6,7 @@ use anyhow::Result;
 use bip39::{Language, Mnemonic, MnemonicType};
 use codec::{Decode, Encode};
 use log::{debug, error};
+use tokio::task::JoinHandle;
 use tokio::time;
 
 use coreui::{
@@ -20,55 +21,106 @@ use coreui::{
 use coreui::lifecycle::start_act;
 use polkadot::{
     client::Client,
+    keys::CryptoScheme,
     rpc::{*},
-    rpc::types::AccountInfo,
+    rpc::types::{AccountInfo, ChainInfo},
 };
 
-use crate::view::{common, state::{BottomStatusBar, DataModel, ViewStatus}};
+use crate::account::{self, Account};
+use crate::network;
+use crate::view::{common, state::{self, BottomStatusBar, DataModel, ViewStatus}};
+
+const ACCOUNT: i32 = 0;
+/// JSON of the `ChainInfo` of the node.
+const CHAIN: i32 = 1;
 
 pub struct HomeActivity {
-    balance: String,
+    /// Free balance in plancks, formatted with the chain properties.
+    balance: u128,
+    chain: ChainInfo,
+    scheme: String,
     nonce: String,
     address: String,
+    path: String,
+    accounts: Vec<(Account, String)>,
+    derive_path: String,
+    derive_scheme: CryptoScheme,
+    hit_info: String,
 
     bottom_status_bar: BottomStatusBar,
     view_status_sender: Sender<ViewStatus>,
     view_status_receiver: Receiver<ViewStatus>,
     client: Arc<Client>,
     status: ViewStatus,
+    /// Live balance updates of the current account.
+    watch: Option<JoinHandle<()>>,
 }
 
 impl HomeActivity {
     pub fn new(ctx: egui::Context, client: Arc<Client>) -> HomeActivity {
         let (sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
         Self {
-            balance: "0.0".to_string(),
+            balance: 0,
+            chain: ChainInfo::default(),
+            scheme: "".to_string(),
             address: "15QFBQY6TF6Abr6vA1r6opRh6RbRSMWgBC1PcCMDDzRSEXf5".to_string(),
             nonce: "0".to_string(),
+            path: "".to_string(),
+            accounts: vec![],
+            derive_path: "".to_string(),
+            derive_scheme: CryptoScheme::default(),
+            hit_info: "".to_string(),
             bottom_status_bar: BottomStatusBar::new(ctx.clone()),
             view_status_sender: sender,
             view_status_receiver: receiver,
             client,
             status: ViewStatus::Loading,
+            watch: None,
         }
     }
 
     pub fn navigate(&mut self, key: ActName) {
         start_act(key).unwrap();
     }
-}
 
-impl IActivity for HomeActivity {
-    fn on_create(&mut self, ctx: &egui::Context, state: &AppState) {
-        debug!("on_create");
-        if let Some(phrase) = state.get_value("PHRASE") {
-            self.address = polkadot::keys::Key::address_from_phrase(&phrase, None);
+    fn load_accounts(&mut self, state: &AppState) {
+        self.accounts.clear();
+        let prefix = network::ss58_prefix(state);
+        if let Some(phrase) = state.get_session_value("PHRASE") {
+            for account in account::accounts(state) {
+                match account.address(&phrase, prefix) {
+                    Ok(address) => self.accounts.push((account, address)),
+                    Err(e) => error!("derive account {} error: {}", account.name, e),
+                }
+            }
+            let current = account::current_account(state);
+            self.scheme = current.scheme.to_string();
+            self.path = current.display_path();
+            if let Ok(address) = current.address(&phrase, prefix) {
+                self.address = address;
+            }
             debug!("address: {}",self.address);
         }
     }
 
-    fn on_resume(&mut self, ctx: &egui::Context, state: &AppState) {
-        debug!("on_resume");
+    fn add_account(&mut self, state: &AppState) {
+        if let Some(phrase) = state.get_session_value("PHRASE") {
+            let name = format!("Account {}", self.accounts.len() + 1);
+            match Account::derived(&name, self.derive_scheme, &phrase, &self.derive_path) {
+                Ok(account) => {
+                    account::add_account(state, account);
+                    self.derive_path = "".to_string();
+                    self.hit_info = "".to_string();
+                    self.load_accounts(state);
+                }
+                Err(e) => {
+                    self.hit_info = format!("invalid derivation path: {}", e);
+                }
+            }
+        }
+    }
+
+    fn request_account(&mut self, ctx: &egui::Context) {
         self.view_status_sender.send(ViewStatus::Loading).unwrap();
         let sender = self.view_status_sender.clone();
         let ctx = ctx.clone();
@@ -79,7 +131,7 @@ impl IActivity for HomeActivity {
             match client.system_account(&address).await {
                 Ok(account) => {
                     debug!("request account ");
-                    sender.send(ViewStatus::Success(DataModel { data_type: 0, data: account })).unwrap();
+                    sender.send(ViewStatus::Success(DataModel { data_type: ACCOUNT, data: account.encode() })).unwrap();
                 }
                 Err(e) => {
                     error!("request account error");
@@ -90,48 +142,182 @@ impl IActivity for HomeActivity {
         });
     }
 
+    /// Ask the node which chain it serves, for the label, balance format and address prefix.
+    fn request_chain(&mut self, ctx: &egui::Context) {
+        let sender = self.view_status_sender.clone();
+        let ctx = ctx.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.chain_info().await {
+                Ok(info) => {
+                    if let Ok(data) = serde_json::to_vec(&info) {
+                        sender.send(ViewStatus::Success(DataModel { data_type: CHAIN, data })).unwrap();
+                    }
+                }
+                Err(e) => error!("request chain info error: {}", e),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Store the chain info, addresses are encoded again when the prefix changed.
+    fn set_chain(&mut self, ctx: &egui::Context, state: &AppState, info: ChainInfo) {
+        let prefix_changed = network::ss58_prefix(state) != info.properties.ss58_prefix();
+        network::set_chain_info(state, &info);
+        self.chain = info;
+        if prefix_changed {
+            self.load_accounts(state);
+            self.request_account(ctx);
+            self.watch_account(ctx);
+        }
+    }
+
+    /// Follow the account storage, only websocket endpoints support it.
+    fn watch_account(&mut self, ctx: &egui::Context) {
+        self.stop_watch();
+        let sender = self.view_status_sender.clone();
+        let ctx = ctx.clone();
+        let client = self.client.clone();
+        let address = self.address.clone();
+        self.watch = Some(EXECUTOR.spawn(async move {
+            let mut subscription = match client.subscribe_system_account(&address).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    debug!("no live balance: {}", e);
+                    return;
+                }
+            };
+            while let Some(change) = subscription.next().await {
+                let values = match change.and_then(|change| change.values()) {
+                    Ok(values) => values,
+                    Err(e) => {
+                        error!("account change error: {}", e);
+                        continue;
+                    }
+                };
+                for (_, value) in values {
+                    // the account is removed from storage once reaped
+                    let account = value.unwrap_or_else(|| AccountInfo::default().encode());
+                    sender.send(ViewStatus::Success(DataModel { data_type: ACCOUNT, data: account })).unwrap();
+                    ctx.request_repaint();
+                }
+            }
+        }));
+    }
+
+    fn stop_watch(&mut self) {
+        if let Some(watch) = self.watch.take() {
+            watch.abort();
+        }
+    }
+}
+
+impl IActivity for HomeActivity {
+    fn on_create(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_create");
+        self.chain = network::chain_info(state).unwrap_or_default();
+        self.load_accounts(state);
+    }
+
+    fn on_resume(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_resume");
+        self.request_chain(ctx);
+        self.request_account(ctx);
+        self.watch_account(ctx);
+    }
+
     fn on_pause(&mut self, ctx: &egui::Context, state: &AppState) {
         debug!("on_pause");
+        self.stop_watch();
         self.bottom_status_bar.stop();
     }
 
     fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
 
         // left menu
-        let (home, transfer, setting) = common::left_menu(ctx);
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
         if home {
             self.navigate(ActName::new("home"));
         } else if transfer {
             self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
         } else if setting {
             self.navigate(ActName::new("setting"));
         }
         //
 
+        if let Ok(mut data) = self.view_status_receiver.try_recv() {
+            self.status = data.clone();
+            match &data {
+                ViewStatus::Success(data) if data.data_type == CHAIN => {
+                    if let Ok(info) = serde_json::from_slice::<ChainInfo>(&data.data) {
+                        self.set_chain(ctx, state, info);
+                    }
+                }
+                ViewStatus::Success(data) => {
+                    if let Ok(account) = AccountInfo::decode(&mut data.data.as_slice()) {
+                        self.balance = account.data.free;
+                        self.nonce = format!("{}", account.nonce);
+                    }
+                }
+                _ => {}
+            }
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             common::five_space(ui);
-            common::title(ui, "Polkadot");
+            let title = if self.chain.chain.is_empty() { "Polkadot".to_string() } else { self.chain.label() };
+            common::title(ui, &title);
             common::five_space(ui);
             ui.separator();
             common::five_space(ui);
-            if let Ok(mut data) = self.view_status_receiver.try_recv() {
-                self.status = data.clone();
-                match &data {
-                    ViewStatus::Success(data) => {
-                        let account: AccountInfo = Decode::decode(&mut data.data.as_slice()).unwrap();
-                        self.balance = format!("{}", account.data.free);
-                        self.nonce = format!("{}", account.nonce);
-                    }
-                    _ => {}
-                }
-            }
             common::single_label(ui, "Address:\t\t", &self.address);
+            common::five_space(ui);
+            common::single_label(ui, "Scheme:\t\t", &self.scheme);
+            common::five_space(ui);
+            common::single_label(ui, "Path:\t\t\t", &self.path);
             common::ten_space(ui);
-            common::single_label(ui, "Balance:\t\t", &self.balance);
+            common::single_label(ui, "Balance:\t\t", &self.chain.properties.format_balance(self.balance));
             common::five_space(ui);
             common::single_label(ui, "Nonce\t:\t\t", &self.nonce);
             common::five_space(ui);
             ui.separator();
+            common::five_space(ui);
+            common::label(ui, "Accounts");
+            common::five_space(ui);
+            let mut selected = None;
+            for (index, (account, address)) in self.accounts.iter().enumerate() {
+                ui.horizontal_wrapped(|ui| {
+                    if common::ssmall_button(ui, "use").clicked() {
+                        selected = Some(index);
+                    }
+                    common::single_label(ui, &format!("{}  {}  {}", account.name, account.scheme, account.display_path()), address);
+                });
+            }
+            if let Some(index) = selected {
+                account::set_current_index(state, index);
+                self.load_accounts(state);
+                self.request_account(ctx);
+                self.watch_account(ctx);
+            }
+            common::five_space(ui);
+            common::simple_input_label(ui, "Derivation path:", "//polkadot//0 or /soft///password", &mut self.derive_path);
+            ui.horizontal_wrapped(|ui| {
+                common::scheme_selector(ui, &mut self.derive_scheme);
+                if common::ssmall_button(ui, "add account").clicked() {
+                    self.add_account(state);
+                }
+            });
+            if self.hit_info != "" {
+                state::hit_info(ui, &self.hit_info);
+            }
+            common::five_space(ui);
+            ui.separator();
             self.bottom_status_bar.set_view(ui, &self.status);
         });
     }
@@ -5,5 +5,8 @@ pub mod home;
 pub mod template;
 pub mod transfer;
 pub mod setting;
+pub mod staking;
+pub mod pools;
+pub mod multisig;
 
 
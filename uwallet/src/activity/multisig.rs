@@ -0,0 +1,476 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender};
+
+use log::debug;
+
+use coreui::{
+    executor::{Executor, EXECUTOR},
+    lifecycle::ActName,
+    state::AppState,
+    IActivity,
+    egui,
+    IView,
+    eframe,
+};
+use coreui::lifecycle::start_act;
+
+use codec::{Decode, Encode};
+use polkadot::address;
+use polkadot::client::Client;
+use polkadot::multisig::{MultisigAction, MultisigProposal, PendingMultisig};
+use polkadot::rpc::types::SystemProperties;
+use polkadot::tracker::{TrackedTx, TxOutcome};
+use polkadot::transfer::{self, TransferKind};
+use polkadot::utility::PreparedCall;
+
+use crate::{account, network};
+use crate::view::{common, state};
+use crate::view::state::{BottomStatusBar, DataModel, ViewStatus};
+
+
+/// JSON of a `MultisigProposal`, the file format.
+const PROPOSAL: i32 = 0;
+const PENDING: i32 = 1;
+const PREPARED: i32 = 2;
+const TX_STATUS: i32 = 3;
+
+const DEFAULT_PROPOSAL_FILE: &str = "multisig-proposal.json";
+
+
+pub struct MultisigActivity {
+    client: Arc<Client>,
+    bottom_status_bar: BottomStatusBar,
+    status_sender: Sender<ViewStatus>,
+    status_receiver: Receiver<ViewStatus>,
+    status: ViewStatus,
+    /// Signatories of a new proposal, one address per line, the current account among them.
+    signatories: String,
+    threshold: String,
+    /// Recipient and amount of the transfer a new proposal makes.
+    dest_address: String,
+    amount: String,
+    /// Proposal file loaded from and saved to.
+    path: String,
+    /// Proposal being approved, as last read from chain.
+    proposal: Option<MultisigProposal>,
+    /// Operations of the proposal's multisig waiting for approvals.
+    pending: Vec<PendingMultisig>,
+    /// Shown after the proposal file was written.
+    saved: Option<String>,
+    /// Signed call waiting for the user to accept the fee.
+    prepared: Option<PreparedCall>,
+    /// Multisig calls submitted in this session, newest last.
+    tx_list: Vec<TrackedTx>,
+    properties: SystemProperties,
+    ss58_prefix: u16,
+}
+
+impl MultisigActivity {
+    pub fn new(ctx: egui::Context, client: Arc<Client>) -> MultisigActivity {
+        let (status_sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
+        Self {
+            client,
+            bottom_status_bar: BottomStatusBar::new(ctx),
+            status_sender,
+            status_receiver: receiver,
+            status: ViewStatus::Normal,
+            signatories: String::new(),
+            threshold: "2".to_string(),
+            dest_address: String::new(),
+            amount: String::new(),
+            path: DEFAULT_PROPOSAL_FILE.to_string(),
+            proposal: None,
+            pending: vec![],
+            saved: None,
+            prepared: None,
+            tx_list: vec![],
+            properties: SystemProperties::default(),
+            ss58_prefix: 0,
+        }
+    }
+
+    pub fn navigate(&mut self, key: ActName) {
+        start_act(key).unwrap();
+    }
+
+    /// A proposal of the signatories to transfer the amount to the recipient.
+    fn propose(&mut self, ctx: &egui::Context) {
+        let signatories = match self.addresses(&self.signatories) {
+            Some(signatories) => signatories,
+            None => return,
+        };
+        let threshold = match self.threshold.trim().parse::<u16>() {
+            Ok(threshold) => threshold,
+            Err(_) => {
+                self.status_sender.send(ViewStatus::Fail(format!("invalid threshold {}", self.threshold))).unwrap();
+                return;
+            }
+        };
+        let dest = match address::check_address(&self.dest_address, self.ss58_prefix) {
+            Ok(dest) => dest.to_ss58(self.ss58_prefix),
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(format!("recipient: {}", e))).unwrap();
+                return;
+            }
+        };
+        let amount = match self.properties.parse_balance(&self.amount) {
+            Ok(amount) => amount,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let proposal = client.propose_multisig(&signatories, threshold, move |metadata| {
+                transfer::transfer_call(metadata, TransferKind::KeepAlive, &dest, amount)
+            }).await;
+            match proposal.map_err(|e| e.to_string()).and_then(|proposal| serde_json::to_vec(&proposal).map_err(|e| e.to_string())) {
+                Ok(data) => sender.send(ViewStatus::Success(DataModel { data_type: PROPOSAL, data })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e)).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Read the proposal file and check it against the chain.
+    fn load(&mut self, ctx: &egui::Context) {
+        let proposal = std::fs::read_to_string(self.path.trim()).map_err(|e| e.to_string())
+            .and_then(|json| MultisigProposal::from_json(&json).map_err(|e| e.to_string()));
+        match proposal {
+            Ok(proposal) => {
+                self.saved = None;
+                self.refresh(ctx, proposal);
+            }
+            Err(e) => self.status_sender.send(ViewStatus::Fail(format!("{}: {}", self.path.trim(), e))).unwrap(),
+        }
+    }
+
+    fn save(&mut self) {
+        let proposal = match &self.proposal {
+            Some(proposal) => proposal,
+            None => return,
+        };
+        let path = self.path.trim().to_string();
+        match proposal.to_json().map_err(|e| e.to_string()).and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string())) {
+            Ok(()) => self.saved = Some(path),
+            Err(e) => self.status_sender.send(ViewStatus::Fail(format!("{}: {}", path, e))).unwrap(),
+        }
+    }
+
+    /// Decode the call again with this chain's runtime and read approvals and timepoint.
+    fn refresh(&mut self, ctx: &egui::Context, proposal: MultisigProposal) {
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let proposal = client.refresh_proposal(proposal).await;
+            match proposal.map_err(|e| e.to_string()).and_then(|proposal| serde_json::to_vec(&proposal).map_err(|e| e.to_string())) {
+                Ok(data) => sender.send(ViewStatus::Success(DataModel { data_type: PROPOSAL, data })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e)).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn load_pending(&mut self, ctx: &egui::Context, multisig: String) {
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.pending_multisigs(&multisig).await {
+                Ok(pending) => sender.send(ViewStatus::Success(DataModel { data_type: PENDING, data: pending.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Sign `action` on the proposal for the current account and estimate its fee.
+    fn prepare(&mut self, ctx: &egui::Context, state: &AppState, action: MultisigAction) {
+        let proposal = match &self.proposal {
+            Some(proposal) => proposal.clone(),
+            None => return,
+        };
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        debug!("prepare multisig {}", action.call_name());
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.prepare_multisig(scheme, seed, from, proposal, action).await {
+                Ok(prepared) => sender.send(ViewStatus::Success(DataModel { data_type: PREPARED, data: prepared.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn confirm(&mut self, ctx: &egui::Context) {
+        let prepared = match self.prepared.take() {
+            Some(prepared) => prepared,
+            None => return,
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let update_sender = sender.clone();
+            let update_ctx = ctx.clone();
+            let on_update = move |tx: &TrackedTx| {
+                update_sender.send(ViewStatus::Success(DataModel { data_type: TX_STATUS, data: tx.encode() })).unwrap();
+                update_ctx.request_repaint();
+            };
+            if let Err(e) = client.track(prepared.extrinsic.clone(), on_update).await {
+                sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Addresses of this network, one per line of `text`, errors shown in the status bar.
+    fn addresses(&self, text: &str) -> Option<Vec<String>> {
+        let mut addresses = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match address::check_address(line, self.ss58_prefix) {
+                Ok(address) => addresses.push(address.to_ss58(self.ss58_prefix)),
+                Err(e) => {
+                    self.status_sender.send(ViewStatus::Fail(format!("{}: {}", line, e))).unwrap();
+                    return None;
+                }
+            }
+        }
+        Some(addresses)
+    }
+}
+
+impl IActivity for MultisigActivity {
+    fn on_create(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_create");
+    }
+
+    fn on_resume(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_resume");
+        self.properties = network::properties(state);
+        self.ss58_prefix = network::ss58_prefix(state);
+        if let Some(proposal) = self.proposal.clone() {
+            self.refresh(ctx, proposal);
+        }
+    }
+
+    fn on_pause(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_pause");
+        self.bottom_status_bar.stop();
+    }
+
+    fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
+        if home {
+            self.navigate(ActName::new("home"));
+        } else if transfer {
+            self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
+        } else if setting {
+            self.navigate(ActName::new("setting"));
+        }
+        let mut refresh = false;
+        if let Ok(data) = self.status_receiver.try_recv() {
+            self.status = data.clone();
+            match &data {
+                ViewStatus::Success(data) if data.data_type == PROPOSAL => {
+                    if let Ok(proposal) = serde_json::from_slice::<MultisigProposal>(&data.data) {
+                        self.load_pending(ctx, proposal.multisig.clone());
+                        self.proposal = Some(proposal);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == PENDING => {
+                    if let Ok(pending) = Vec::<PendingMultisig>::decode(&mut data.data.as_slice()) {
+                        self.pending = pending;
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == PREPARED => {
+                    if let Ok(prepared) = PreparedCall::decode(&mut data.data.as_slice()) {
+                        self.prepared = Some(prepared);
+                    }
+                }
+                ViewStatus::Success(data) => {
+                    if let Ok(tx) = TrackedTx::decode(&mut data.data.as_slice()) {
+                        // approvals and timepoint changed once the call is in a block
+                        refresh = tx.outcome.is_some() && tx.status.is_final();
+                        match self.tx_list.iter_mut().find(|item| item.hash == tx.hash) {
+                            Some(item) => *item = tx,
+                            None => self.tx_list.push(tx),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if refresh {
+            if let Some(proposal) = self.proposal.clone() {
+                self.refresh(ctx, proposal);
+            }
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                common::five_space(ui);
+                common::title(ui, "Multisig");
+                common::five_space(ui);
+                ui.separator();
+                common::five_space(ui);
+
+                common::label(ui, "New transfer proposal");
+                ui.separator();
+                common::label(ui, "Signatories, one address per line, yours among them");
+                ui.add(egui::TextEdit::multiline(&mut self.signatories).desired_rows(3).hint_text("signatory address"));
+                common::simple_input_label(ui, "\t\tThreshold \t\t", "approvals needed", &mut self.threshold);
+                common::simple_input_label(ui, "\t\tRecipient \t\t", "recipient address", &mut self.dest_address);
+                common::simple_input_label(ui, "\t\tAmount \t\t\t", &format!("input amount in {}", self.properties.symbol()), &mut self.amount);
+                if common::small_button(ui, "Propose").clicked() {
+                    self.propose(ctx);
+                }
+                common::fifteen_space(ui);
+
+                common::label(ui, "Proposal file, shared with the other signatories");
+                ui.separator();
+                common::simple_input_label(ui, "\t\tFile \t\t\t", "path of the proposal file", &mut self.path);
+                let mut load = false;
+                let mut save = false;
+                ui.horizontal_wrapped(|ui| {
+                    load = common::small_button(ui, "Load").clicked();
+                    save = self.proposal.is_some() && common::small_button(ui, "Save").clicked();
+                });
+                if let Some(path) = &self.saved {
+                    state::hit_info(ui, &format!("\t\tsaved to {}", path));
+                }
+                if load {
+                    self.load(ctx);
+                } else if save {
+                    self.save();
+                }
+                common::fifteen_space(ui);
+
+                let mut action = None;
+                if let Some(proposal) = &self.proposal {
+                    let me = account::signer(state).map(|(me, _)| me).unwrap_or_default();
+                    common::label(ui, "Proposal");
+                    ui.separator();
+                    common::single_label(ui, "Multisig\t", &proposal.multisig);
+                    common::single_label(ui, "Threshold\t", &format!("{} of {}", proposal.threshold, proposal.signatories.len()));
+                    for signatory in &proposal.signatories {
+                        let approved = if proposal.is_approved_by(signatory) { "  approved" } else { "" };
+                        let you = if *signatory == me { "  (you)" } else { "" };
+                        common::single_label(ui, "", &format!("{}{}{}", signatory, you, approved));
+                    }
+                    common::single_label(ui, "Call\t\t", &proposal.description);
+                    common::single_label(ui, "Call hash\t", &proposal.call_hash);
+                    common::single_label(ui, "Max weight\t", &format!("{} ref time, {} proof size", proposal.max_weight.ref_time, proposal.max_weight.proof_size));
+                    match proposal.timepoint {
+                        Some(timepoint) => common::single_label(ui, "Timepoint\t", &format!("block {}, extrinsic {}", timepoint.height, timepoint.index)),
+                        None => state::hit_info(ui, "\t\tnot approved on chain yet"),
+                    }
+                    common::single_label(ui, "Approvals needed", &proposal.approvals_needed().to_string());
+                    common::five_space(ui);
+                    ui.horizontal_wrapped(|ui| {
+                        if !proposal.is_approved_by(&me) && common::small_button(ui, "Approve").clicked() {
+                            action = Some(MultisigAction::Approve);
+                        }
+                        if common::small_button(ui, "Approve and execute").clicked() {
+                            action = Some(MultisigAction::Execute);
+                        }
+                        if proposal.timepoint.is_some() && common::small_button(ui, "Cancel operation").clicked() {
+                            action = Some(MultisigAction::Cancel);
+                        }
+                    });
+                    common::fifteen_space(ui);
+
+                    common::label(ui, "Pending operations of the multisig");
+                    ui.separator();
+                    if self.pending.is_empty() {
+                        state::hit_info(ui, "\t\tnone");
+                    }
+                    for pending in &self.pending {
+                        let call_hash = format!("0x{}", hex::encode(pending.call_hash));
+                        let this = if call_hash == proposal.call_hash { "  this proposal" } else { "" };
+                        let when = pending.multisig.when;
+                        common::single_label(ui, &format!("#{}-{}", when.height, when.index), &format!("{}  {} approvals{}", call_hash, pending.multisig.approvals.len(), this));
+                        common::single_label(ui, "", &format!("deposit {} by {}", self.properties.format_balance(pending.multisig.deposit), address::to_ss58(&pending.multisig.depositor, self.ss58_prefix)));
+                    }
+                    common::fifteen_space(ui);
+                }
+                if let Some(action) = action {
+                    if self.prepared.is_none() {
+                        self.prepare(ctx, state, action);
+                    }
+                }
+
+                let mut confirm = false;
+                let mut cancel = false;
+                if let Some(prepared) = &self.prepared {
+                    common::label(ui, "Confirm multisig call");
+                    ui.separator();
+                    common::single_label(ui, "Call", &prepared.description);
+                    common::single_label(ui, "Fee", &self.properties.format_balance(prepared.fee.total_fee()));
+                    ui.horizontal(|ui| {
+                        confirm = common::small_button(ui, "Confirm").clicked();
+                        cancel = common::small_button(ui, "Cancel").clicked();
+                    });
+                    common::thirty_space(ui);
+                }
+                if confirm {
+                    self.confirm(ctx);
+                } else if cancel {
+                    self.prepared = None;
+                    self.status = ViewStatus::Normal;
+                }
+
+                if !self.tx_list.is_empty() {
+                    common::label(ui, "Submitted");
+                    ui.separator();
+                }
+                for item in &self.tx_list {
+                    common::hyperlink_to(ui, &format!("Hash: {}", item.hash));
+                    let outcome = match &item.outcome {
+                        Some(TxOutcome::Success) => "  success".to_string(),
+                        Some(TxOutcome::Failed(error)) => format!("  failed: {}", error),
+                        None => String::new(),
+                    };
+                    common::single_label(ui, "Status:", &format!("{:?}{}", item.status, outcome));
+                    common::five_space(ui);
+                }
+            });
+            if common::right_bottom_button(ui, "Refresh") {
+                if let Some(proposal) = self.proposal.clone() {
+                    self.refresh(ctx, proposal);
+                }
+            }
+            self.bottom_status_bar.set_view(ui, &self.status);
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test() {}
+}
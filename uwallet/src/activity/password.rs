@@ -11,6 +11,7 @@ use coreui::{
     eframe,
 };
 use coreui::lifecycle::start_act;
+use utils::aes::Keystore;
 use crate::{view::{common,state}};
 
 
@@ -34,8 +35,23 @@ impl PasswordActivity {
     }
 
     fn check_password(&self, state: &AppState) -> Result<String> {
+        if let Some(data) = state.get_value("KEYSTORE") {
+            let keystore = Keystore::from_hex(&data)?;
+            let phrase = String::from_utf8(keystore.unlock(&self.password)?)?;
+            state.set_session_value("PHRASE".to_owned(), phrase);
+            return Ok("Ok".to_string());
+        }
+        // wallets created before the keystore kept the password and phrase in plain text
         if let Some(password) = state.get_value("PWD") {
             if self.password == password {
+                state.remove_value("PWD");
+                if let Some(phrase) = state.remove_value("PHRASE") {
+                    let keystore = Keystore::seal(&self.password, phrase.as_bytes())?;
+                    state.set_value("KEYSTORE".to_owned(), keystore.to_hex());
+                    state.set_session_value("PHRASE".to_owned(), phrase);
+                } else {
+                    state.set_session_value("PWD".to_owned(), self.password.clone());
+                }
                 return Ok("Ok".to_string());
             }
         }
@@ -46,12 +62,13 @@ impl PasswordActivity {
         if self.password != self.confirm_pwd || self.password == "" {
             return Err(anyhow!("password not match"));
         }
-        state.set_value("PWD".to_owned(), self.password.clone());
+        // only kept in memory until the phrase is sealed into the keystore
+        state.set_session_value("PWD".to_owned(), self.password.clone());
         return Ok("Ok".to_string());
     }
 
     fn navigate_phrase(&self, state: &AppState) {
-        if state.exists("PHRASE") {
+        if state.get_session_value("PHRASE").is_some() {
             start_act(ActName::new("home")).unwrap();;
         } else {
             start_act(ActName::new("phrase")).unwrap();
@@ -134,7 +151,7 @@ impl PasswordActivity {
 
 impl IActivity for PasswordActivity {
     fn on_create(&mut self,ctx: &egui::Context, state: &AppState) {
-        if state.exists("PWD") {
+        if state.exists("KEYSTORE") || state.exists("PWD") {
             self.new_password = false;
         } else {
             self.new_password = true;
@@ -2,10 +2,10 @@ use std::sync::Arc;
 use std::sync::mpsc::Sender;
 use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bip39::{Language, Mnemonic, MnemonicType};
 
-use log::debug;
+use log::{debug, error};
 use tokio::time;
 use coreui::{
     executor::{Executor, EXECUTOR},
@@ -17,7 +17,10 @@ use coreui::{
     eframe,
 };
 use coreui::lifecycle::start_act;
+use polkadot::keys::CryptoScheme;
+use utils::aes::Keystore;
 
+use crate::account::{self, Account};
 use crate::view::{common, state};
 
 
@@ -25,6 +28,7 @@ use crate::view::{common, state};
 pub struct PhraseActivity {
     phrase: String,
     import_ui: bool,
+    scheme: CryptoScheme,
 }
 
 impl PhraseActivity {
@@ -32,6 +36,7 @@ impl PhraseActivity {
         Self {
             phrase: Default::default(),
             import_ui: false,
+            scheme: CryptoScheme::default(),
         }
     }
     pub fn generate_phrase(&mut self) {
@@ -43,8 +48,24 @@ impl PhraseActivity {
         if self.phrase == "" {
             return;
         }
-        state.set_value("PHRASE".to_owned(), self.phrase.clone());
-        start_act(ActName::new("home")).unwrap();
+        match self.seal_phrase(state) {
+            Ok(_) => {
+                start_act(ActName::new("home")).unwrap();
+            }
+            Err(e) => {
+                error!("seal phrase error: {}", e);
+            }
+        }
+    }
+
+    fn seal_phrase(&self, state: &AppState) -> Result<()> {
+        let password = state.get_session_value("PWD").ok_or(anyhow!("password not set"))?;
+        let keystore = Keystore::seal(&password, self.phrase.as_bytes())?;
+        state.set_value("KEYSTORE".to_owned(), keystore.to_hex());
+        account::set_accounts(state, &[Account::new("Account", self.scheme)]);
+        state.set_session_value("PHRASE".to_owned(), self.phrase.clone());
+        state.remove_session_value("PWD");
+        Ok(())
     }
 }
 
@@ -79,7 +100,9 @@ impl IActivity for PhraseActivity {
                                 self.phrase = "".to_string();
                             }
                         });
-                        common::thirty_space(ui);
+                        common::fifteen_space(ui);
+                        common::scheme_selector(ui, &mut self.scheme);
+                        common::fifteen_space(ui);
                         common::simple_input_label(ui, "Phrase:", "please input your phrase", &mut self.phrase);
                     });
                 });
@@ -97,7 +120,9 @@ impl IActivity for PhraseActivity {
                                 }
                             });
                         });
-                        common::thirty_space(ui);
+                        common::fifteen_space(ui);
+                        common::scheme_selector(ui, &mut self.scheme);
+                        common::fifteen_space(ui);
                         common::label(ui, &self.phrase);
                     });
                 });
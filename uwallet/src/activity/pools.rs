@@ -0,0 +1,390 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender};
+
+use log::debug;
+
+use coreui::{
+    executor::{Executor, EXECUTOR},
+    lifecycle::ActName,
+    state::AppState,
+    IActivity,
+    egui,
+    IView,
+    eframe,
+};
+use coreui::lifecycle::start_act;
+
+use codec::{Decode, Encode};
+use polkadot::client::Client;
+use polkadot::pools::{BondedPool, PoolAction, PoolMembership, PoolState};
+use polkadot::rpc::types::SystemProperties;
+use polkadot::tracker::{TrackedTx, TxOutcome};
+use polkadot::utility::PreparedCall;
+
+use crate::{account, network};
+use crate::view::{common, state};
+use crate::view::state::{BottomStatusBar, DataModel, ViewStatus};
+
+
+const POOLS: i32 = 0;
+const MEMBERSHIP: i32 = 1;
+const PREPARED: i32 = 2;
+const TX_STATUS: i32 = 3;
+
+
+pub struct PoolsActivity {
+    client: Arc<Client>,
+    bottom_status_bar: BottomStatusBar,
+    status_sender: Sender<ViewStatus>,
+    status_receiver: Receiver<ViewStatus>,
+    status: ViewStatus,
+    /// Every pool of the network, `None` until read.
+    pools: Option<Vec<BondedPool>>,
+    /// Pool of the current account, `None` when it is in no pool.
+    membership: Option<PoolMembership>,
+    /// Filters the pool browser by id or name.
+    search: String,
+    /// For join, bond extra and unbond.
+    amount: String,
+    /// Signed call waiting for the user to accept the fee.
+    prepared: Option<PreparedCall>,
+    /// Pool calls submitted in this session, newest last.
+    tx_list: Vec<TrackedTx>,
+    properties: SystemProperties,
+}
+
+impl PoolsActivity {
+    pub fn new(ctx: egui::Context, client: Arc<Client>) -> PoolsActivity {
+        let (status_sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
+        Self {
+            client,
+            bottom_status_bar: BottomStatusBar::new(ctx),
+            status_sender,
+            status_receiver: receiver,
+            status: ViewStatus::Normal,
+            pools: None,
+            membership: None,
+            search: String::new(),
+            amount: String::new(),
+            prepared: None,
+            tx_list: vec![],
+            properties: SystemProperties::default(),
+        }
+    }
+
+    pub fn navigate(&mut self, key: ActName) {
+        start_act(key).unwrap();
+    }
+
+    fn load_pools(&mut self, ctx: &egui::Context) {
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.nomination_pools().await {
+                Ok(pools) => sender.send(ViewStatus::Success(DataModel { data_type: POOLS, data: pools.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn load_membership(&mut self, ctx: &egui::Context, state: &AppState) {
+        // nothing to show while the wallet is locked
+        let member = match account::signer(state) {
+            Ok((member, _)) => member,
+            Err(_) => return,
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.pool_membership(&member).await {
+                Ok(membership) => sender.send(ViewStatus::Success(DataModel { data_type: MEMBERSHIP, data: membership.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Sign `action` for the current account and estimate its fee, to confirm like a transfer.
+    fn prepare(&mut self, ctx: &egui::Context, state: &AppState, action: PoolAction) {
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        debug!("prepare pool {}", action.call_name());
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.prepare_pool(scheme, seed, from, action).await {
+                Ok(prepared) => sender.send(ViewStatus::Success(DataModel { data_type: PREPARED, data: prepared.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn confirm(&mut self, ctx: &egui::Context) {
+        let prepared = match self.prepared.take() {
+            Some(prepared) => prepared,
+            None => return,
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let update_sender = sender.clone();
+            let update_ctx = ctx.clone();
+            let on_update = move |tx: &TrackedTx| {
+                update_sender.send(ViewStatus::Success(DataModel { data_type: TX_STATUS, data: tx.encode() })).unwrap();
+                update_ctx.request_repaint();
+            };
+            if let Err(e) = client.track(prepared.extrinsic.clone(), on_update).await {
+                sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// The typed amount, errors shown in the status bar.
+    fn amount(&self) -> Option<u128> {
+        match self.properties.parse_balance(&self.amount) {
+            Ok(amount) => Some(amount),
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                None
+            }
+        }
+    }
+}
+
+/// `5.00%` of a perbill.
+fn percent(perbill: u32) -> String {
+    format!("{:.2}%", perbill as f64 / 10_000_000.0)
+}
+
+impl IActivity for PoolsActivity {
+    fn on_create(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_create");
+    }
+
+    fn on_resume(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_resume");
+        self.properties = network::properties(state);
+        self.load_pools(ctx);
+        self.load_membership(ctx, state);
+    }
+
+    fn on_pause(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_pause");
+        self.bottom_status_bar.stop();
+    }
+
+    fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
+        if home {
+            self.navigate(ActName::new("home"));
+        } else if transfer {
+            self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
+        } else if setting {
+            self.navigate(ActName::new("setting"));
+        }
+        let mut reload = false;
+        if let Ok(data) = self.status_receiver.try_recv() {
+            self.status = data.clone();
+            match &data {
+                ViewStatus::Success(data) if data.data_type == POOLS => {
+                    if let Ok(pools) = Vec::<BondedPool>::decode(&mut data.data.as_slice()) {
+                        self.pools = Some(pools);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == MEMBERSHIP => {
+                    if let Ok(membership) = Option::<PoolMembership>::decode(&mut data.data.as_slice()) {
+                        self.membership = membership;
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == PREPARED => {
+                    if let Ok(prepared) = PreparedCall::decode(&mut data.data.as_slice()) {
+                        self.prepared = Some(prepared);
+                    }
+                }
+                ViewStatus::Success(data) => {
+                    if let Ok(tx) = TrackedTx::decode(&mut data.data.as_slice()) {
+                        // points and rewards changed once the call is in a block
+                        reload = tx.outcome.is_some() && tx.status.is_final();
+                        match self.tx_list.iter_mut().find(|item| item.hash == tx.hash) {
+                            Some(item) => *item = tx,
+                            None => self.tx_list.push(tx),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if reload {
+            self.load_membership(ctx, state);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            common::five_space(ui);
+            common::title(ui, "Nomination pools");
+            common::five_space(ui);
+            ui.separator();
+            common::five_space(ui);
+
+            let units = &self.properties;
+            common::simple_input_label(ui, "\t\tAmount \t\t\t", &format!("input amount in {}", units.symbol()), &mut self.amount);
+            if let Err(e) = units.parse_balance(&self.amount) {
+                if !self.amount.trim().is_empty() {
+                    state::error(ui, &format!("\t\t{}", e));
+                }
+            }
+            common::ten_space(ui);
+
+            let mut action = None;
+            let mut join = None;
+            match &self.membership {
+                Some(membership) => {
+                    let pool = &membership.pool;
+                    common::label(ui, "Your pool");
+                    ui.separator();
+                    common::single_label(ui, "Pool\t\t", &format!("#{} {}  {}", pool.id, pool.name, pool.state));
+                    common::single_label(ui, "Points\t\t", &format!("{} of {}", units.format_balance(membership.member.points), units.format_balance(pool.points)));
+                    common::single_label(ui, "Pending rewards", &units.format_balance(membership.pending_rewards));
+                    if let Some(reward_pool) = &membership.reward_pool {
+                        common::single_label(ui, "Pool paid out\t", &units.format_balance(reward_pool.total_rewards_claimed));
+                    }
+                    for (unlock_era, points) in membership.unlocking() {
+                        let left = membership.active_era.map(|era| format!(", {} eras left", unlock_era.saturating_sub(era))).unwrap_or_default();
+                        common::single_label(ui, "Unbonding\t", &format!("{} at era {}{}", units.format_balance(points), unlock_era, left));
+                    }
+                    common::single_label(ui, "Redeemable\t", &units.format_balance(membership.redeemable()));
+                    common::five_space(ui);
+                    ui.horizontal_wrapped(|ui| {
+                        if membership.pending_rewards > 0 {
+                            if common::small_button(ui, "Claim rewards").clicked() {
+                                action = Some("claim_payout");
+                            }
+                            if common::small_button(ui, "Bond rewards").clicked() {
+                                action = Some("bond_rewards");
+                            }
+                        }
+                        if pool.state == PoolState::Open && common::small_button(ui, "Bond extra").clicked() {
+                            action = Some("bond_extra");
+                        }
+                        if common::small_button(ui, "Unbond").clicked() {
+                            action = Some("unbond");
+                        }
+                        if membership.redeemable() > 0 && common::small_button(ui, "Withdraw").clicked() {
+                            action = Some("withdraw_unbonded");
+                        }
+                    });
+                    common::fifteen_space(ui);
+                }
+                None => state::hit_info(ui, "\t\tnot a member of a pool, join an open one below"),
+            }
+
+            common::label(ui, "Pools");
+            ui.separator();
+            common::simple_input_label(ui, "\t\tSearch \t\t\t", "pool id or name", &mut self.search);
+            let search = self.search.trim().to_lowercase();
+            match &self.pools {
+                Some(pools) => {
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        let shown = pools.iter().filter(|pool| search.is_empty() || pool.id.to_string() == search || pool.name.to_lowercase().contains(&search));
+                        for pool in shown {
+                            ui.horizontal_wrapped(|ui| {
+                                let commission = pool.commission.map(|perbill| format!("  commission {}", percent(perbill))).unwrap_or_default();
+                                common::single_label(ui, &format!("#{}", pool.id), &format!("{}  {}  {} members  {} bonded{}", pool.name, pool.state, pool.member_counter, units.format_balance(pool.points), commission));
+                                if self.membership.is_none() && pool.state == PoolState::Open && common::ssmall_button(ui, "Join").clicked() {
+                                    join = Some(pool.id);
+                                }
+                            });
+                        }
+                    });
+                }
+                None => state::hit_info(ui, "\t\treading pools"),
+            }
+
+            let num_slashing_spans = self.membership.as_ref().map(|membership| membership.slashing_spans).unwrap_or(0);
+            let action = match (action, join) {
+                (_, Some(pool_id)) => self.amount().map(|amount| PoolAction::Join { amount, pool_id }),
+                (Some("claim_payout"), _) => Some(PoolAction::ClaimPayout),
+                (Some("bond_rewards"), _) => Some(PoolAction::BondRewards),
+                (Some("bond_extra"), _) => self.amount().map(|amount| PoolAction::BondExtra { amount }),
+                (Some("unbond"), _) => self.amount().map(|points| PoolAction::Unbond { points }),
+                (Some("withdraw_unbonded"), _) => Some(PoolAction::WithdrawUnbonded { num_slashing_spans }),
+                _ => None,
+            };
+            if let Some(action) = action {
+                if self.prepared.is_none() {
+                    self.prepare(ctx, state, action);
+                }
+            }
+            common::thirty_space(ui);
+
+            let mut confirm = false;
+            let mut cancel = false;
+            if let Some(prepared) = &self.prepared {
+                common::label(ui, "Confirm pool call");
+                ui.separator();
+                common::single_label(ui, "Call", &prepared.description);
+                common::single_label(ui, "Fee", &self.properties.format_balance(prepared.fee.total_fee()));
+                ui.horizontal(|ui| {
+                    confirm = common::small_button(ui, "Confirm").clicked();
+                    cancel = common::small_button(ui, "Cancel").clicked();
+                });
+                common::thirty_space(ui);
+            }
+            if confirm {
+                self.confirm(ctx);
+            } else if cancel {
+                self.prepared = None;
+                self.status = ViewStatus::Normal;
+            }
+
+            if !self.tx_list.is_empty() {
+                common::label(ui, "Submitted");
+                ui.separator();
+            }
+            for item in &self.tx_list {
+                common::hyperlink_to(ui, &format!("Hash: {}", item.hash));
+                let outcome = match &item.outcome {
+                    Some(TxOutcome::Success) => "  success".to_string(),
+                    Some(TxOutcome::Failed(error)) => format!("  failed: {}", error),
+                    None => String::new(),
+                };
+                common::single_label(ui, "Status:", &format!("{:?}{}", item.status, outcome));
+                common::five_space(ui);
+            }
+            if common::right_bottom_button(ui, "Refresh") {
+                self.load_pools(ctx);
+                self.load_membership(ctx, state);
+            }
+            self.bottom_status_bar.set_view(ui, &self.status);
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test() {}
+}
@@ -19,14 +19,23 @@ use coreui::{
 };
 use coreui::lifecycle::start_act;
 
+use polkadot::client::Client;
+use polkadot::rpc::failover::EndpointHealth;
+
+use crate::{history, network};
 use crate::view::{common, state};
-use crate::view::state::{BottomStatusBar, ViewStatus};
+use crate::view::state::{BottomStatusBar, DataModel, ViewStatus};
+
+const HEALTH: i32 = 0;
 
 
 
 pub struct SettingActivity {
     balance: String,
+    /// One endpoint per line.
     endpoint: String,
+    health: Vec<EndpointHealth>,
+    client: Arc<Client>,
     bottom_status_bar: BottomStatusBar,
     status_sender: Sender<ViewStatus>,
     status_receiver: Receiver<ViewStatus>,
@@ -36,11 +45,13 @@ pub struct SettingActivity {
 }
 
 impl SettingActivity {
-    pub fn new(ctx:egui::Context) -> SettingActivity {
+    pub fn new(ctx:egui::Context, client: Arc<Client>) -> SettingActivity {
         let (sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
         Self {
             balance: "11231231231231231".to_string(),
             endpoint: "".to_string(),
+            health: vec![],
+            client,
             status_sender: sender,
             status_receiver: receiver,
             bottom_status_bar: BottomStatusBar::new(ctx),
@@ -52,6 +63,45 @@ impl SettingActivity {
     pub fn navigate(&mut self, key: ActName) {
       start_act(key).unwrap();
     }
+
+    fn check_health(&mut self, ctx: &egui::Context) {
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let sender = self.status_sender.clone();
+        let ctx = ctx.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let health = client.transport().check_health().await;
+            match serde_json::to_vec(&health) {
+                Ok(data) => sender.send(ViewStatus::Success(DataModel { data_type: HEALTH, data })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn save_endpoints(&mut self, ctx: &egui::Context, state: &AppState) {
+        match network::parse_endpoints(&self.endpoint) {
+            Ok(endpoints) => {
+                network::set_endpoints(state, &endpoints);
+                self.client.set_endpoints(endpoints);
+                self.hit_info = "Info: ** RPC endpoints saved **".to_string();
+                self.check_health(ctx);
+            }
+            Err(e) => self.hit_info = format!("Error: {}", e),
+        }
+    }
+}
+
+fn health_line(health: &EndpointHealth) -> String {
+    let state = match (&health.error, health.synced) {
+        (Some(e), _) => format!("down: {}", e),
+        (None, true) => "synced".to_string(),
+        (None, false) => "not synced".to_string(),
+    };
+    let latency = health.latency.map(|latency| format!("{} ms", latency.as_millis())).unwrap_or("-".to_string());
+    let best = health.best.map(|best| format!("#{}", best)).unwrap_or("-".to_string());
+    let peers = health.health.as_ref().map(|health| health.peers.to_string()).unwrap_or("-".to_string());
+    format!("{}  {}  best {}  peers {}", state, latency, best, peers)
 }
 
 impl IActivity for SettingActivity {
@@ -62,6 +112,8 @@ impl IActivity for SettingActivity {
     fn on_resume(&mut self,ctx: &egui::Context, state: &AppState) {
         debug!("on_resume");
         self.hit_info = "".to_string();
+        self.endpoint = network::endpoints(state).join("\n");
+        self.check_health(ctx);
     }
 
     fn on_pause(&mut self,ctx: &egui::Context, state: &AppState) {
@@ -70,11 +122,17 @@ impl IActivity for SettingActivity {
     }
 
     fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
-        let (home, transfer, setting) = common::left_menu(ctx);
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
         if home {
             self.navigate(ActName::new("home"));
         } else if transfer {
             self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
         } else if setting {
             self.navigate(ActName::new("setting"));
         }
@@ -82,7 +140,18 @@ impl IActivity for SettingActivity {
             if let Ok(mut data) = self.status_receiver.try_recv() {
                 self.status = data.clone();
                 match &data {
-                    ViewStatus::Success(data) => {}
+                    ViewStatus::Success(data) if data.data_type == HEALTH => {
+                        if let Ok(health) = serde_json::from_slice::<Vec<EndpointHealth>>(&data.data) {
+                            self.health = health;
+                        }
+                        // chain info and history of the old chain are of no use on another one
+                        if let Some(genesis_hash) = self.client.transport().genesis_hash() {
+                            if network::set_genesis_hash(state, &genesis_hash) {
+                                history::clear_history(state);
+                                self.hit_info = "Info: ** Another chain, network info and history reset **".to_string();
+                            }
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -96,19 +165,40 @@ impl IActivity for SettingActivity {
                 ui.vertical_centered(|ui| {
                     common::fifteen_space(ui);
                     if common::small_button(ui, "\t\t\t\t\t\t\t\t\tBakUp\t\t\t\t\t\t\t\t\t").clicked() {
-                        if let Some(phrase) = state.get_value("PHRASE") {
+                        if let Some(phrase) = state.get_session_value("PHRASE") {
                             utils::copy_to_clipboard(&phrase).unwrap();
                             self.hit_info = "Info: ** Phrase Copied to clipboard **".to_string();
                         }
                     };
-                    //common::simple_input_label(ui, "\t\tAdd RPC Endpoint:\t", "input endpoint", &mut self.endpoint);
                     common::fifteen_space(ui);
-
-                    // ui.separator();
-                    // common::fifteen_space(ui);
-                    // common::small_button(ui, "\t\t\t\t\t\t\t\t\tSetRPC\t\t\t\t\t\t\t\t\t");
-                    // common::fifteen_space(ui);
-                    //ui.separator();
+                    ui.separator();
+                    common::fifteen_space(ui);
+                    common::label(ui, "RPC endpoints, one per line");
+                    common::five_space(ui);
+                    ui.add(egui::TextEdit::multiline(&mut self.endpoint).text_color(egui::Color32::GRAY).desired_rows(3).hint_text("wss://rpc.polkadot.io"));
+                    common::five_space(ui);
+                    ui.horizontal_wrapped(|ui| {
+                        if common::ssmall_button(ui, "save").clicked() {
+                            self.save_endpoints(ctx, state);
+                        }
+                        if common::ssmall_button(ui, "check").clicked() {
+                            self.check_health(ctx);
+                        }
+                    });
+                    common::five_space(ui);
+                    if let Some(info) = network::chain_info(state) {
+                        common::single_label(ui, "Network:\t", &format!("{} on {}, SS58 prefix {}", info.label(), info.name, info.properties.ss58_prefix()));
+                    }
+                    // best first, the one calls go to
+                    for health in &self.health {
+                        common::single_label(ui, &format!("{}\t", health.endpoint), &health_line(health));
+                    }
+                    if let Some(served) = self.client.transport().last_served() {
+                        let failed_over = if served.failed_over > 0 { format!(", after {} failed", served.failed_over) } else { "".to_string() };
+                        common::single_label(ui, "Last call:\t", &format!("{} by {} in {} ms{}", served.method, served.endpoint, served.latency.as_millis(), failed_over));
+                    }
+                    common::fifteen_space(ui);
+                    ui.separator();
 
                     common::fifteen_space(ui);
                     if common::small_button(ui, "\t\t\t\t\t\t\t\t\t\tExit\t\t\t\t\t\t\t\t\t\t").clicked() {
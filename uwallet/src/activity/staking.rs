@@ -0,0 +1,648 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender};
+
+use log::debug;
+
+use coreui::{
+    executor::{Executor, EXECUTOR},
+    lifecycle::ActName,
+    state::AppState,
+    IActivity,
+    egui,
+    IView,
+    eframe,
+};
+use coreui::lifecycle::start_act;
+
+use codec::{Decode, Encode};
+use polkadot::address;
+use polkadot::client::Client;
+use polkadot::payout::PayoutReport;
+use polkadot::rpc::types::SystemProperties;
+use polkadot::staking::{RewardDestination, StakingAction, StakingInfo};
+use polkadot::session::RotatedKeys;
+use polkadot::tracker::{self, TrackedTx, TxOutcome};
+use polkadot::utility::PreparedCall;
+
+use crate::{account, network};
+use crate::view::{common, state};
+use crate::view::state::{BottomStatusBar, DataModel, ViewStatus};
+
+
+const INFO: i32 = 0;
+const PREPARED: i32 = 1;
+const TX_STATUS: i32 = 2;
+const PAYOUTS: i32 = 3;
+const ROTATED: i32 = 4;
+const KEYS_PREPARED: i32 = 5;
+const NEXT_KEYS: i32 = 6;
+
+
+pub struct StakingActivity {
+    client: Arc<Client>,
+    bottom_status_bar: BottomStatusBar,
+    status_sender: Sender<ViewStatus>,
+    status_receiver: Receiver<ViewStatus>,
+    status: ViewStatus,
+    /// Of the current account, `None` until read.
+    info: Option<StakingInfo>,
+    /// For bond, bond extra, unbond and rebond.
+    amount: String,
+    /// Validators to nominate, one address per line.
+    targets: String,
+    payee: RewardDestination,
+    /// Signed call waiting for the user to accept the fee.
+    prepared: Option<PreparedCall>,
+    /// Staking calls submitted in this session, newest last.
+    tx_list: Vec<TrackedTx>,
+    /// Validator stashes to claim payouts for, one address per line.
+    validators: String,
+    /// Sign the payouts and estimate their fee without submitting them.
+    dry_run: bool,
+    /// Of the last payout check or claim.
+    report: Option<PayoutReport>,
+    /// Validator node to rotate session keys on.
+    operator_endpoint: String,
+    /// Keys of the last rotation, to register with `set_keys`.
+    rotated: Option<RotatedKeys>,
+    /// Hash of the submitted `set_keys`, its updates go to the session log.
+    keys_hash: Option<String>,
+    /// Each step of a key rotation and what it returned, for the operator to audit.
+    session_log: Vec<String>,
+    properties: SystemProperties,
+    ss58_prefix: u16,
+}
+
+impl StakingActivity {
+    pub fn new(ctx: egui::Context, client: Arc<Client>) -> StakingActivity {
+        let (status_sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
+        Self {
+            client,
+            bottom_status_bar: BottomStatusBar::new(ctx),
+            status_sender,
+            status_receiver: receiver,
+            status: ViewStatus::Normal,
+            info: None,
+            amount: String::new(),
+            targets: String::new(),
+            payee: RewardDestination::Staked,
+            prepared: None,
+            tx_list: vec![],
+            validators: String::new(),
+            dry_run: true,
+            report: None,
+            operator_endpoint: String::new(),
+            rotated: None,
+            keys_hash: None,
+            session_log: vec![],
+            properties: SystemProperties::default(),
+            ss58_prefix: 0,
+        }
+    }
+
+    pub fn navigate(&mut self, key: ActName) {
+        start_act(key).unwrap();
+    }
+
+    fn load_info(&mut self, ctx: &egui::Context, state: &AppState) {
+        // nothing to show while the wallet is locked
+        let stash = match account::signer(state) {
+            Ok((stash, _)) => stash,
+            Err(_) => return,
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.staking_info(&stash).await {
+                Ok(info) => sender.send(ViewStatus::Success(DataModel { data_type: INFO, data: info.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Sign `action` for the current account and estimate its fee, to confirm like a transfer.
+    fn prepare(&mut self, ctx: &egui::Context, state: &AppState, action: StakingAction) {
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        debug!("prepare staking {}", action.call_name());
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.prepare_staking(scheme, seed, from, action).await {
+                Ok(prepared) => sender.send(ViewStatus::Success(DataModel { data_type: PREPARED, data: prepared.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn confirm(&mut self, ctx: &egui::Context) {
+        let prepared = match self.prepared.take() {
+            Some(prepared) => prepared,
+            None => return,
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let update_sender = sender.clone();
+            let update_ctx = ctx.clone();
+            let on_update = move |tx: &TrackedTx| {
+                update_sender.send(ViewStatus::Success(DataModel { data_type: TX_STATUS, data: tx.encode() })).unwrap();
+                update_ctx.request_repaint();
+            };
+            if let Err(e) = client.track(prepared.extrinsic.clone(), on_update).await {
+                sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// The typed amount, errors shown in the status bar.
+    fn amount(&self) -> Option<u128> {
+        match self.properties.parse_balance(&self.amount) {
+            Ok(amount) => Some(amount),
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                None
+            }
+        }
+    }
+
+    /// Find the unclaimed eras of the validators, and with `claim` pay them out from the current
+    /// account, only signed for a dry run.
+    fn payouts(&mut self, ctx: &egui::Context, state: &AppState, claim: bool) {
+        let stashes = match self.addresses(&self.validators) {
+            Some(stashes) if !stashes.is_empty() => stashes,
+            Some(_) => {
+                self.status_sender.send(ViewStatus::Fail("no validator stash entered".to_string())).unwrap();
+                return;
+            }
+            None => return,
+        };
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        let dry_run = self.dry_run;
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let report = if claim {
+                client.payout_stakers(scheme, seed, from, &stashes, dry_run).await
+            } else {
+                client.unclaimed_payouts(&stashes).await
+            };
+            match report {
+                Ok(report) => sender.send(ViewStatus::Success(DataModel { data_type: PAYOUTS, data: report.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Generate new session keys on the operator's node, the wallet's own endpoints are only
+    /// asked to decode them.
+    fn rotate_keys(&mut self, ctx: &egui::Context, state: &AppState) {
+        let endpoint = match network::parse_endpoints(&self.operator_endpoint) {
+            Ok(endpoints) if endpoints.len() == 1 => endpoints[0].clone(),
+            Ok(_) => {
+                self.status_sender.send(ViewStatus::Fail("enter a single operator endpoint".to_string())).unwrap();
+                return;
+            }
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        network::set_operator_endpoint(state, &endpoint);
+        self.session_log.push(format!("author_rotateKeys on {}", endpoint));
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.rotate_keys(&endpoint).await {
+                Ok(rotated) => sender.send(ViewStatus::Success(DataModel { data_type: ROTATED, data: rotated.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Sign `Session::set_keys` for the rotated keys, confirmed like the staking calls.
+    fn set_keys(&mut self, ctx: &egui::Context, state: &AppState) {
+        let keys = match &self.rotated {
+            Some(rotated) => rotated.keys.clone(),
+            None => return,
+        };
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.prepare_set_keys(scheme, seed, from, keys, vec![]).await {
+                Ok(prepared) => sender.send(ViewStatus::Success(DataModel { data_type: KEYS_PREPARED, data: prepared.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Read `Session::NextKeys` of the current account to see the keys were registered.
+    fn check_next_keys(&mut self, ctx: &egui::Context, state: &AppState) {
+        let stash = match account::signer(state) {
+            Ok((stash, _)) => stash,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        self.session_log.push(format!("Session::NextKeys of {}", stash));
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            match client.session_next_keys(&stash).await {
+                Ok(keys) => sender.send(ViewStatus::Success(DataModel { data_type: NEXT_KEYS, data: keys.encode() })).unwrap(),
+                Err(e) => sender.send(ViewStatus::Fail(e.to_string())).unwrap(),
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Addresses of this network, one per line of `text`, errors shown in the status bar.
+    fn addresses(&self, text: &str) -> Option<Vec<String>> {
+        let mut targets = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match address::check_address(line, self.ss58_prefix) {
+                Ok(target) => targets.push(target.to_ss58(self.ss58_prefix)),
+                Err(e) => {
+                    self.status_sender.send(ViewStatus::Fail(format!("{}: {}", line, e))).unwrap();
+                    return None;
+                }
+            }
+        }
+        Some(targets)
+    }
+}
+
+impl IActivity for StakingActivity {
+    fn on_create(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_create");
+    }
+
+    fn on_resume(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_resume");
+        self.properties = network::properties(state);
+        self.ss58_prefix = network::ss58_prefix(state);
+        self.operator_endpoint = network::operator_endpoint(state);
+        self.load_info(ctx, state);
+    }
+
+    fn on_pause(&mut self, ctx: &egui::Context, state: &AppState) {
+        debug!("on_pause");
+        self.bottom_status_bar.stop();
+    }
+
+    fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
+        if home {
+            self.navigate(ActName::new("home"));
+        } else if transfer {
+            self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
+        } else if setting {
+            self.navigate(ActName::new("setting"));
+        }
+        let mut reload = false;
+        let mut check_keys = false;
+        if let Ok(data) = self.status_receiver.try_recv() {
+            self.status = data.clone();
+            match &data {
+                ViewStatus::Success(data) if data.data_type == INFO => {
+                    if let Ok(info) = StakingInfo::decode(&mut data.data.as_slice()) {
+                        self.info = Some(info);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == PREPARED => {
+                    if let Ok(prepared) = PreparedCall::decode(&mut data.data.as_slice()) {
+                        self.prepared = Some(prepared);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == ROTATED => {
+                    if let Ok(rotated) = RotatedKeys::decode(&mut data.data.as_slice()) {
+                        self.session_log.push(format!("  keys 0x{}", hex::encode(&rotated.keys)));
+                        for key in &rotated.decoded {
+                            self.session_log.push(format!("  {} ({}) 0x{}", key.name, key.key_type, hex::encode(&key.public)));
+                        }
+                        self.rotated = Some(rotated);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == KEYS_PREPARED => {
+                    if let Ok(prepared) = PreparedCall::decode(&mut data.data.as_slice()) {
+                        self.session_log.push(format!("signed {}", prepared.description));
+                        self.session_log.push(format!("  fee {}", self.properties.format_balance(prepared.fee.total_fee())));
+                        self.keys_hash = tracker::extrinsic_hash(&prepared.extrinsic).ok();
+                        self.prepared = Some(prepared);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == NEXT_KEYS => {
+                    if let Ok(keys) = Option::<Vec<u8>>::decode(&mut data.data.as_slice()) {
+                        let rotated = self.rotated.as_ref().map(|rotated| &rotated.keys);
+                        let line = match keys {
+                            Some(keys) if Some(&keys) == rotated => "  registered, matches the rotated keys".to_string(),
+                            Some(keys) => format!("  0x{}, not the rotated keys", hex::encode(keys)),
+                            None => "  no keys registered".to_string(),
+                        };
+                        self.session_log.push(line);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == PAYOUTS => {
+                    if let Ok(report) = PayoutReport::decode(&mut data.data.as_slice()) {
+                        self.report = Some(report);
+                    }
+                }
+                ViewStatus::Success(data) => {
+                    if let Ok(tx) = TrackedTx::decode(&mut data.data.as_slice()) {
+                        // the ledger changed once the call is in a block
+                        reload = tx.outcome.is_some() && tx.status.is_final();
+                        if self.keys_hash.as_ref() == Some(&tx.hash) {
+                            let outcome = match &tx.outcome {
+                                Some(TxOutcome::Success) => "  success".to_string(),
+                                Some(TxOutcome::Failed(error)) => format!("  failed: {}", error),
+                                None => String::new(),
+                            };
+                            self.session_log.push(format!("set_keys {} {:?}{}", tx.hash, tx.status, outcome));
+                            check_keys = tx.outcome == Some(TxOutcome::Success) && tx.status.is_final();
+                        }
+                        match self.tx_list.iter_mut().find(|item| item.hash == tx.hash) {
+                            Some(item) => *item = tx,
+                            None => self.tx_list.push(tx),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if reload {
+            self.load_info(ctx, state);
+        }
+        if check_keys {
+            self.check_next_keys(ctx, state);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            common::five_space(ui);
+            common::title(ui, "Staking");
+            common::five_space(ui);
+            ui.separator();
+            common::five_space(ui);
+
+            let units = &self.properties;
+            let mut action = None;
+            match &self.info {
+                Some(info) => {
+                    let era = info.active_era.map(|era| era.to_string()).unwrap_or("-".to_string());
+                    common::single_label(ui, "Active era\t", &format!("{}, unbonding takes {} eras", era, info.bonding_duration));
+                    common::single_label(ui, "Bonded\t\t", &units.format_balance(info.bonded()));
+                    for (unlock_era, value) in info.unlocking() {
+                        let left = info.active_era.map(|era| format!(", {} eras left", unlock_era.saturating_sub(era))).unwrap_or_default();
+                        common::single_label(ui, "Unlocking\t", &format!("{} at era {}{}", units.format_balance(value), unlock_era, left));
+                    }
+                    common::single_label(ui, "Redeemable\t", &units.format_balance(info.redeemable()));
+                    let payee = match &info.payee {
+                        RewardDestination::Account(account) => format!("Account {}", address::to_ss58(account, self.ss58_prefix)),
+                        payee => payee.to_string(),
+                    };
+                    common::single_label(ui, "Rewards to\t", &payee);
+                    match &info.nominations {
+                        Some(nominations) => {
+                            common::single_label(ui, "Nominating\t", &format!("{} validators since era {}", nominations.targets.len(), nominations.submitted_in));
+                            for target in &nominations.targets {
+                                common::single_label(ui, "", &address::to_ss58(target, self.ss58_prefix));
+                            }
+                        }
+                        None => common::single_label(ui, "Nominating\t", "-"),
+                    }
+                    common::fifteen_space(ui);
+                    ui.separator();
+                    common::five_space(ui);
+
+                    common::simple_input_label(ui, "\t\tAmount \t\t\t", &format!("input amount in {}", units.symbol()), &mut self.amount);
+                    if let Err(e) = units.parse_balance(&self.amount) {
+                        if !self.amount.trim().is_empty() {
+                            state::error(ui, &format!("\t\t{}", e));
+                        }
+                    }
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(egui::RichText::new("\t\tRewards to\t\t").size(15.0).color(egui::Color32::GRAY));
+                        for payee in [RewardDestination::Staked, RewardDestination::Stash, RewardDestination::None] {
+                            let label = payee.to_string();
+                            ui.radio_value(&mut self.payee, payee, egui::RichText::new(label).size(14.0).color(egui::Color32::GRAY));
+                        }
+                    });
+                    common::five_space(ui);
+                    ui.horizontal_wrapped(|ui| {
+                        if !info.is_bonded() {
+                            if common::small_button(ui, "Bond").clicked() {
+                                action = Some("bond");
+                            }
+                        } else {
+                            for (label, name) in [("Bond extra", "bond_extra"), ("Unbond", "unbond"), ("Rebond", "rebond"), ("Set payee", "set_payee")] {
+                                if common::small_button(ui, label).clicked() {
+                                    action = Some(name);
+                                }
+                            }
+                            if info.redeemable() > 0 && common::small_button(ui, "Withdraw").clicked() {
+                                action = Some("withdraw_unbonded");
+                            }
+                        }
+                    });
+                    if info.is_bonded() {
+                        common::ten_space(ui);
+                        common::label(ui, "Validators to nominate, one address per line");
+                        ui.add(egui::TextEdit::multiline(&mut self.targets).desired_rows(3).hint_text("validator address"));
+                        ui.horizontal_wrapped(|ui| {
+                            if common::small_button(ui, "Nominate").clicked() {
+                                action = Some("nominate");
+                            }
+                            if info.nominations.is_some() && common::small_button(ui, "Chill").clicked() {
+                                action = Some("chill");
+                            }
+                        });
+                    }
+                }
+                None => state::hit_info(ui, "\t\treading the staking ledger"),
+            }
+            let slashing_spans = self.info.as_ref().map(|info| info.slashing_spans).unwrap_or(0);
+            let action = match action {
+                Some("bond") => self.amount().map(|value| StakingAction::Bond { value, payee: self.payee.clone() }),
+                Some("bond_extra") => self.amount().map(|value| StakingAction::BondExtra { value }),
+                Some("unbond") => self.amount().map(|value| StakingAction::Unbond { value }),
+                Some("rebond") => self.amount().map(|value| StakingAction::Rebond { value }),
+                Some("set_payee") => Some(StakingAction::SetPayee { payee: self.payee.clone() }),
+                Some("withdraw_unbonded") => Some(StakingAction::WithdrawUnbonded { num_slashing_spans: slashing_spans }),
+                Some("nominate") => self.addresses(&self.targets).map(|targets| StakingAction::Nominate { targets }),
+                Some("chill") => Some(StakingAction::Chill),
+                _ => None,
+            };
+            if let Some(action) = action {
+                if self.prepared.is_none() {
+                    self.prepare(ctx, state, action);
+                }
+            }
+            common::thirty_space(ui);
+
+            common::label(ui, "Validator payouts");
+            ui.separator();
+            common::label(ui, "Validator stashes, one address per line");
+            ui.add(egui::TextEdit::multiline(&mut self.validators).desired_rows(3).hint_text("validator stash address"));
+            let mut check = false;
+            let mut claim = false;
+            ui.horizontal_wrapped(|ui| {
+                check = common::small_button(ui, "Find unclaimed").clicked();
+                claim = common::small_button(ui, "Claim").clicked();
+                ui.checkbox(&mut self.dry_run, egui::RichText::new("Dry run, sign and estimate only").size(14.0).color(egui::Color32::GRAY));
+            });
+            if check || claim {
+                self.payouts(ctx, state, claim);
+            }
+            if let Some(report) = &self.report {
+                let units = &self.properties;
+                common::single_label(ui, "Eras checked	", &format!("{} to {}", report.first_era, report.active_era.saturating_sub(1)));
+                for payout in &report.payouts {
+                    let eras = payout.unclaimed.iter()
+                        .map(|unclaimed| if unclaimed.pages > 1 { format!("{} ({} pages)", unclaimed.era, unclaimed.pages) } else { unclaimed.era.to_string() })
+                        .collect::<Vec<_>>();
+                    let eras = if eras.is_empty() { "all claimed".to_string() } else { format!("unclaimed eras {}", eras.join(", ")) };
+                    common::single_label(ui, &payout.stash, &eras);
+                }
+                if let Some(prepared) = &report.prepared {
+                    common::single_label(ui, "Batch		", &format!("{} payouts, fee {}", report.items.len(), units.format_balance(prepared.fee.total_fee())));
+                }
+                if report.is_dry_run() {
+                    state::hit_info(ui, "\t\tdry run, nothing was submitted");
+                }
+                if let Some(tx) = &report.tx {
+                    let outcome = match &tx.outcome {
+                        Some(TxOutcome::Success) => "  success".to_string(),
+                        Some(TxOutcome::Failed(error)) => format!("  failed: {}", error),
+                        None => String::new(),
+                    };
+                    common::hyperlink_to(ui, &format!("Hash: {}", tx.hash));
+                    common::single_label(ui, "Status:", &format!("{:?}{}", tx.status, outcome));
+                    for (stash, era, result) in report.results() {
+                        let result = result.map(|result| result.to_string()).unwrap_or("pending".to_string());
+                        common::single_label(ui, "", &format!("era {} of {}  {}", era, stash, result));
+                    }
+                    common::single_label(ui, "Claimed		", &format!("{} of {} payouts", report.claimed(), report.items.len()));
+                }
+            }
+            common::thirty_space(ui);
+
+            common::label(ui, "Session keys");
+            ui.separator();
+            common::simple_input_label(ui, "\t\tValidator node\t", network::DEFAULT_OPERATOR_ENDPOINT, &mut self.operator_endpoint);
+            let mut rotate = false;
+            let mut set_keys = false;
+            let mut check = false;
+            ui.horizontal_wrapped(|ui| {
+                rotate = common::small_button(ui, "Rotate keys").clicked();
+                if self.rotated.is_some() {
+                    set_keys = common::small_button(ui, "Set keys").clicked();
+                }
+                check = common::small_button(ui, "Check next keys").clicked();
+            });
+            if rotate {
+                self.rotate_keys(ctx, state);
+            } else if set_keys && self.prepared.is_none() {
+                self.set_keys(ctx, state);
+            } else if check {
+                self.check_next_keys(ctx, state);
+            }
+            for line in &self.session_log {
+                common::single_label(ui, "", line);
+            }
+            common::thirty_space(ui);
+
+            let mut confirm = false;
+            let mut cancel = false;
+            if let Some(prepared) = &self.prepared {
+                common::label(ui, "Confirm staking call");
+                ui.separator();
+                common::single_label(ui, "Call", &prepared.description);
+                common::single_label(ui, "Fee", &self.properties.format_balance(prepared.fee.total_fee()));
+                ui.horizontal(|ui| {
+                    confirm = common::small_button(ui, "Confirm").clicked();
+                    cancel = common::small_button(ui, "Cancel").clicked();
+                });
+                common::thirty_space(ui);
+            }
+            if confirm {
+                self.confirm(ctx);
+            } else if cancel {
+                self.prepared = None;
+                self.status = ViewStatus::Normal;
+            }
+
+            if !self.tx_list.is_empty() {
+                common::label(ui, "Submitted");
+                ui.separator();
+            }
+            for item in &self.tx_list {
+                common::hyperlink_to(ui, &format!("Hash: {}", item.hash));
+                let outcome = match &item.outcome {
+                    Some(TxOutcome::Success) => "  success".to_string(),
+                    Some(TxOutcome::Failed(error)) => format!("  failed: {}", error),
+                    None => String::new(),
+                };
+                common::single_label(ui, "Status:", &format!("{:?}{}", item.status, outcome));
+                common::five_space(ui);
+            }
+            if common::right_bottom_button(ui, "Refresh") {
+                self.load_info(ctx, state);
+            }
+            self.bottom_status_bar.set_view(ui, &self.status);
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test() {}
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 use std::time::Duration;
@@ -17,25 +18,62 @@ use coreui::{
 };
 use coreui::lifecycle::start_act;
 
+use codec::{Decode, Encode};
+use polkadot::address::{self, AddressError};
 use polkadot::client::Client;
+use polkadot::history::{Direction, History, INITIAL_LOOKBACK};
+use polkadot::rpc::types::SystemProperties;
+use polkadot::tracker::{self, TrackedTx, TxOutcome};
+use polkadot::transfer::{self, PreparedTransfer, TransferKind};
+use polkadot::utility::{BatchMode, ItemResult};
 
 
 
+use crate::{account, history, network};
 use crate::view::{common, state};
 use crate::view::state::{BottomStatusBar, DataModel, ViewStatus};
 
 
+const TX_STATUS: i32 = 0;
+const PREPARED: i32 = 1;
+/// Progress of a history scan, JSON of the `History`.
+const HISTORY: i32 = 2;
+/// The last update of a scan.
+const HISTORY_DONE: i32 = 3;
+
+/// Blocks fetched per scan round, the history is saved after each.
+const SCAN_CHUNK: u32 = 100;
+const PAGE_SIZE: usize = 10;
+
 
 pub struct TransferActivity {
     amount: String,
     dest_address: String,
+    kind: TransferKind,
     bottom_status_bar: BottomStatusBar,
     status_sender: Sender<ViewStatus>,
     status_receiver: Receiver<ViewStatus>,
     status: ViewStatus,
     client: Arc<Client>,
-    tx_list: Vec<String>,
-
+    /// Transfers submitted in this session with their live status, newest last.
+    tx_list: Vec<TrackedTx>,
+    /// Signed transfer waiting for the user to accept the fee.
+    prepared: Option<PreparedTransfer>,
+    /// Transfers in and out of the wallet accounts, found by scanning blocks.
+    history: Option<History>,
+    history_page: usize,
+    scanning: bool,
+    /// Token symbol and decimals amounts are shown with.
+    properties: SystemProperties,
+    /// Destinations must be addresses of this network.
+    ss58_prefix: u16,
+    /// Send to every line of `recipients` in one `Utility` batch.
+    multiple: bool,
+    /// One `address amount` per line.
+    recipients: String,
+    batch_mode: BatchMode,
+    /// Recipients of the submitted batches by extrinsic hash, to show the result of each.
+    batches: HashMap<String, Vec<(String, u128)>>,
 }
 
 impl TransferActivity {
@@ -43,34 +81,123 @@ impl TransferActivity {
         let (status_sender, receiver) = std::sync::mpsc::channel::<ViewStatus>();
         Self {
             client,
-            amount: "1234567891".to_string(),
-            dest_address: "14dp76EwTctDZmX8bgJV3jC6KsnCCpjwzvjMpm4tc2AkJN2L".to_string(),
+            amount: "".to_string(),
+            dest_address: "".to_string(),
+            kind: TransferKind::KeepAlive,
             status_sender,
             status_receiver: receiver,
             bottom_status_bar: BottomStatusBar::new(ctx),
             status: ViewStatus::Normal,
             tx_list: vec![],
+            prepared: None,
+            history: None,
+            history_page: 0,
+            scanning: false,
+            properties: SystemProperties::default(),
+            ss58_prefix: 0,
+            multiple: false,
+            recipients: String::new(),
+            batch_mode: BatchMode::BatchAll,
+            batches: HashMap::new(),
         }
     }
 
-    pub fn transfer(&mut self, ctx:&egui::Context,state: &AppState) {
-        let mut from = String::new();
-        let mut seed = String::new();
-        if let Some(phrase) = state.get_value("PHRASE") {
-            from = polkadot::keys::Key::address_from_phrase(&phrase, None);
-            seed = format!("0x{}", polkadot::keys::Key::generate_seed(&phrase, None));
+    /// Scan new finalized blocks for transfers of the wallet accounts, resuming from the saved
+    /// checkpoint. A new history starts `INITIAL_LOOKBACK` blocks before the finalized head.
+    pub fn scan_history(&mut self, ctx: &egui::Context, state: &AppState) {
+        if self.scanning {
+            return;
         }
+        let phrase = match state.get_session_value("PHRASE") {
+            Some(phrase) => phrase,
+            None => return,
+        };
+        let prefix = network::ss58_prefix(state);
+        let addresses: Vec<String> = account::accounts(state).iter().filter_map(|account| account.address(&phrase, prefix).ok()).collect();
+        let history = self.history.clone();
+        self.scanning = true;
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let mut history = match history {
+                Some(history) => history,
+                None => {
+                    let finalized = async {
+                        let hash = client.finalize_head().await?;
+                        Ok::<u32, polkadot::Error>(client.header(Some(hash)).await?.block_number()?)
+                    }.await;
+                    match finalized {
+                        Ok(number) => History::new(vec![], number.saturating_sub(INITIAL_LOOKBACK)),
+                        Err(e) => {
+                            sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                            sender.send(ViewStatus::Success(DataModel { data_type: HISTORY_DONE, data: vec![] })).unwrap();
+                            ctx.request_repaint();
+                            return;
+                        }
+                    }
+                }
+            };
+            for address in &addresses {
+                history.add_account(address);
+            }
+            loop {
+                match client.scan_history(&mut history, SCAN_CHUNK).await {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if let Ok(data) = serde_json::to_vec(&history) {
+                            sender.send(ViewStatus::Success(DataModel { data_type: HISTORY, data })).unwrap();
+                        }
+                        ctx.request_repaint();
+                    }
+                    Err(e) => {
+                        sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                        break;
+                    }
+                }
+            }
+            let data = serde_json::to_vec(&history).unwrap_or_default();
+            sender.send(ViewStatus::Success(DataModel { data_type: HISTORY_DONE, data })).unwrap();
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn transfer(&mut self, ctx:&egui::Context,state: &AppState) {
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        let amount = match self.properties.parse_balance(&self.amount) {
+            Ok(amount) => amount,
+            // everything is sent, whatever was typed
+            Err(_) if matches!(self.kind, TransferKind::All { .. }) => 0,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        // raw public keys are sent as addresses of this network
+        let address = match address::check_address(&self.dest_address, self.ss58_prefix) {
+            Ok(address) => address.to_ss58(self.ss58_prefix),
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
         debug!("start transfer");
         self.status_sender.send(ViewStatus::Loading).unwrap();
         let ctx = ctx.clone();
         let sender = self.status_sender.clone();
         let client = self.client.clone();
-        let address = self.dest_address.clone();
-        let amount = self.amount.clone().parse::<u128>().unwrap();
+        let kind = self.kind;
         EXECUTOR.spawn(async move {
-            match client.transfer(seed, from, address, amount).await {
-                Ok(result) => {
-                    sender.send(ViewStatus::Success(DataModel { data_type: 0, data: result })).unwrap();
+            match client.prepare_transfer(scheme, seed, from, address, kind, amount).await {
+                Ok(prepared) => {
+                    sender.send(ViewStatus::Success(DataModel { data_type: PREPARED, data: prepared.encode() })).unwrap();
                 }
                 Err(e) => {
                     sender.send(ViewStatus::Fail(e.to_string())).unwrap();
@@ -80,6 +207,74 @@ impl TransferActivity {
         });
     }
 
+    /// Sign the transfers to every recipient line as one batch, to confirm like a transfer.
+    pub fn transfer_batch(&mut self, ctx: &egui::Context, state: &AppState) {
+        let scheme = account::current_account(state).scheme;
+        let (from, seed) = match account::signer(state) {
+            Ok(signer) => signer,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        let recipients = match transfer::parse_recipients(&self.recipients, self.properties.decimals(), self.ss58_prefix) {
+            Ok(recipients) => recipients,
+            Err(e) => {
+                self.status_sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                return;
+            }
+        };
+        debug!("start batch transfer to {} recipients", recipients.len());
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        let (kind, mode) = (self.kind, self.batch_mode);
+        EXECUTOR.spawn(async move {
+            match client.prepare_batch(scheme, seed, from, recipients, kind, mode).await {
+                Ok(prepared) => {
+                    sender.send(ViewStatus::Success(DataModel { data_type: PREPARED, data: prepared.encode() })).unwrap();
+                }
+                Err(e) => {
+                    sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+
+    pub fn confirm(&mut self, ctx: &egui::Context) {
+        let prepared = match self.prepared.take() {
+            Some(prepared) if !prepared.is_blocked() => prepared,
+            _ => return,
+        };
+        if prepared.batch.is_some() {
+            if let Ok(hash) = tracker::extrinsic_hash(&prepared.extrinsic) {
+                self.batches.insert(hash, prepared.recipients.clone());
+            }
+        }
+        self.status_sender.send(ViewStatus::Loading).unwrap();
+        let ctx = ctx.clone();
+        let sender = self.status_sender.clone();
+        let client = self.client.clone();
+        EXECUTOR.spawn(async move {
+            let update_sender = sender.clone();
+            let update_ctx = ctx.clone();
+            let on_update = move |tx: &TrackedTx| {
+                update_sender.send(ViewStatus::Success(DataModel { data_type: TX_STATUS, data: tx.encode() })).unwrap();
+                update_ctx.request_repaint();
+            };
+            match client.track(prepared.extrinsic.clone(), on_update).await {
+                Ok(_) => {}
+                Err(e) => {
+                    sender.send(ViewStatus::Fail(e.to_string())).unwrap();
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
 
     pub fn navigate(&mut self, key: ActName) {
         start_act(key).unwrap();
@@ -93,6 +288,12 @@ impl IActivity for TransferActivity {
 
     fn on_resume(&mut self,ctx: &egui::Context, state: &AppState) {
         debug!("on_resume");
+        self.properties = network::properties(state);
+        self.ss58_prefix = network::ss58_prefix(state);
+        if self.history.is_none() {
+            self.history = history::history(state);
+        }
+        self.scan_history(ctx, state);
     }
 
     fn on_pause(&mut self,ctx: &egui::Context, state: &AppState) {
@@ -101,19 +302,44 @@ impl IActivity for TransferActivity {
     }
 
     fn set_view(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame, state: &AppState) {
-        let (home, transfer, setting) = common::left_menu(ctx);
+        let (home, transfer, staking, pools, multisig, setting) = common::left_menu(ctx);
         if home {
             self.navigate(ActName::new("home"));
         } else if transfer {
             self.navigate(ActName::new("transfer"));
+        } else if staking {
+            self.navigate(ActName::new("staking"));
+        } else if pools {
+            self.navigate(ActName::new("pools"));
+        } else if multisig {
+            self.navigate(ActName::new("multisig"));
         } else if setting {
             self.navigate(ActName::new("setting"));
         }
         if let Ok(mut data) = self.status_receiver.try_recv() {
             self.status = data.clone();
             match &data {
+                ViewStatus::Success(data) if data.data_type == PREPARED => {
+                    if let Ok(prepared) = PreparedTransfer::decode(&mut data.data.as_slice()) {
+                        self.prepared = Some(prepared);
+                    }
+                }
+                ViewStatus::Success(data) if data.data_type == HISTORY || data.data_type == HISTORY_DONE => {
+                    if data.data_type == HISTORY_DONE {
+                        self.scanning = false;
+                    }
+                    if let Ok(scanned) = serde_json::from_slice::<History>(&data.data) {
+                        history::set_history(state, &scanned);
+                        self.history = Some(scanned);
+                    }
+                }
                 ViewStatus::Success(data) => {
-                    self.tx_list.push(String::from_utf8(data.data.clone()).unwrap())
+                    if let Ok(tx) = TrackedTx::decode(&mut data.data.as_slice()) {
+                        match self.tx_list.iter_mut().find(|item| item.hash == tx.hash) {
+                            Some(item) => *item = tx,
+                            None => self.tx_list.push(tx),
+                        }
+                    }
                 }
                 _ => {}
             }
@@ -124,23 +350,199 @@ impl IActivity for TransferActivity {
             common::five_space(ui);
             ui.separator();
             common::five_space(ui);
-            common::simple_input_label(ui, "\t\tDestAddress\t", "input address", &mut self.dest_address);
-            common::ten_space(ui);
-            common::simple_input_label(ui, "\t\tAmount \t\t\t", "input amount", &mut self.amount);
+            ui.checkbox(&mut self.multiple, egui::RichText::new("\t\tMultiple recipients, sent as one batch").size(14.0).color(egui::Color32::GRAY));
+            common::five_space(ui);
+            let ready = if self.multiple {
+                ui.label(egui::RichText::new("\t\tRecipients, one address and amount per line").size(15.0).color(egui::Color32::GRAY));
+                ui.add(egui::TextEdit::multiline(&mut self.recipients).hint_text(format!("address amount in {}", self.properties.symbol())).desired_rows(4));
+                // checked on every frame, so while typing
+                let recipients = transfer::parse_recipients(&self.recipients, self.properties.decimals(), self.ss58_prefix);
+                match &recipients {
+                    Ok(recipients) => {
+                        let total = recipients.iter().fold(0u128, |total, (_, amount)| total.saturating_add(*amount));
+                        state::hit_info(ui, &format!("\t\t{} recipients, {} in total", recipients.len(), self.properties.format_balance(total)));
+                    }
+                    Err(e) if !self.recipients.trim().is_empty() => state::error(ui, &format!("\t\t{}", e)),
+                    Err(_) => {}
+                }
+                common::five_space(ui);
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new("\t\tBatch\t\t").size(15.0).color(egui::Color32::GRAY));
+                    for mode in BatchMode::all() {
+                        ui.radio_value(&mut self.batch_mode, mode, egui::RichText::new(mode.to_string()).size(14.0).color(egui::Color32::GRAY));
+                    }
+                });
+                // transfer_all sends everything to one account
+                if let TransferKind::All { .. } = self.kind {
+                    self.kind = TransferKind::KeepAlive;
+                }
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new("\t\tSend as\t\t").size(15.0).color(egui::Color32::GRAY));
+                    for kind in [TransferKind::KeepAlive, TransferKind::AllowDeath] {
+                        ui.radio_value(&mut self.kind, kind, egui::RichText::new(kind.to_string()).size(14.0).color(egui::Color32::GRAY));
+                    }
+                });
+                recipients.is_ok()
+            } else {
+                common::simple_input_label(ui, "\t\tDestAddress\t", "input address or 0x public key", &mut self.dest_address);
+                let destination = address::check_address(&self.dest_address, self.ss58_prefix);
+                let mut reencoded = None;
+                match &destination {
+                    Err(e @ AddressError::WrongNetwork { reencoded: address, .. }) => {
+                        ui.horizontal_wrapped(|ui| {
+                            state::error(ui, &format!("\t\t{}", e));
+                            if common::ssmall_button(ui, "use this network's address").clicked() {
+                                reencoded = Some(address.clone());
+                            }
+                        });
+                    }
+                    Err(e) if !self.dest_address.trim().is_empty() => state::error(ui, &format!("\t\t{}", e)),
+                    _ => {}
+                }
+                if let Some(address) = reencoded {
+                    self.dest_address = address;
+                }
+                common::ten_space(ui);
+                common::simple_input_label(ui, "\t\tAmount \t\t\t", &format!("input amount in {}", self.properties.symbol()), &mut self.amount);
+                // checked on every frame, so while typing
+                let amount = self.properties.parse_balance(&self.amount);
+                match &amount {
+                    Err(e) if !self.amount.trim().is_empty() => state::error(ui, &format!("\t\t{}", e)),
+                    Ok(plancks) => state::hit_info(ui, &format!("\t\t{} plancks", plancks)),
+                    Err(_) => {}
+                }
+                common::five_space(ui);
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(egui::RichText::new("\t\tSend as\t\t").size(15.0).color(egui::Color32::GRAY));
+                    for kind in TransferKind::all() {
+                        ui.radio_value(&mut self.kind, kind, egui::RichText::new(kind.to_string()).size(14.0).color(egui::Color32::GRAY));
+                    }
+                });
+                let send_all = matches!(self.kind, TransferKind::All { .. });
+                if send_all {
+                    state::hit_info(ui, "\t\tthe amount is ignored, everything transferable is sent");
+                }
+                destination.is_ok() && (amount.is_ok() || send_all)
+            };
             common::fifteen_space(ui);
             ui.separator();
             common::thirty_space(ui);
 
+            let mut confirm = false;
+            let mut cancel = false;
+            if let Some(prepared) = &self.prepared {
+                let fee = &prepared.fee;
+                common::label(ui, "Confirm transfer");
+                ui.separator();
+                let units = &self.properties;
+                let about = if matches!(prepared.kind, TransferKind::All { .. }) { "about " } else { "" };
+                match prepared.batch {
+                    Some(mode) => {
+                        common::single_label(ui, "Call", &format!("Utility.{} of {} {} transfers", mode.call_name(), prepared.recipients.len(), prepared.kind));
+                        for (to, amount) in &prepared.recipients {
+                            common::single_label(ui, "", &format!("{}  to {}", units.format_balance(*amount), to));
+                        }
+                    }
+                    None => common::single_label(ui, "Call", &prepared.kind.to_string()),
+                }
+                common::single_label(ui, "Amount + Fee = Total", &format!("{}{} + {} = {}", about, units.format_balance(prepared.amount), units.format_balance(fee.total_fee()), units.format_balance(prepared.total())));
+                if let Some(inclusion) = &fee.details.inclusion_fee {
+                    common::single_label(ui, "Fee details", &format!("base {} + length {} + weight {} + tip {} (plancks)", inclusion.base_fee, inclusion.len_fee, inclusion.adjusted_weight_fee, fee.details.tip));
+                }
+                common::single_label(ui, "Weight", &format!("{} ({:?})", fee.info.weight.ref_time, fee.info.class));
+                for issue in &prepared.issues {
+                    let message = issue.message(|plancks| units.format_balance(plancks));
+                    if issue.is_blocking() {
+                        state::error(ui, &format!("Error: {}", message));
+                    } else {
+                        state::hit_info(ui, &format!("Warning: {}", message));
+                    }
+                }
+                common::five_space(ui);
+                ui.horizontal(|ui| {
+                    // the runtime would reject it, only warnings can be accepted
+                    if !prepared.is_blocked() {
+                        let label = if prepared.issues.is_empty() { "Confirm" } else { "Confirm anyway" };
+                        confirm = common::small_button(ui, label).clicked();
+                    }
+                    cancel = common::small_button(ui, "Cancel").clicked();
+                });
+                common::thirty_space(ui);
+            }
+            if confirm {
+                self.confirm(ctx);
+            } else if cancel {
+                self.prepared = None;
+                self.status = ViewStatus::Normal;
+            }
+
             if self.tx_list.len() > 0 {
-                common::label(ui, "Transaction history");
+                common::label(ui, "Submitted");
                 ui.separator();
             }
             for item in &self.tx_list {
-                common::hyperlink_to(ui, &format!("Hash: {}",item));
+                common::hyperlink_to(ui, &format!("Hash: {}", item.hash));
+                let mut status = format!("{:?}", item.status);
+                if let (Some(block), Some(index)) = (&item.block_hash, item.index) {
+                    status.push_str(&format!("  block {} #{}", block, index));
+                }
+                match &item.outcome {
+                    Some(TxOutcome::Success) => status.push_str("  success"),
+                    Some(TxOutcome::Failed(error)) => status.push_str(&format!("  failed: {}", error)),
+                    None => {}
+                }
+                common::single_label(ui, "Status:", &status);
+                // one line per transfer of a batch, in call order
+                if let Some(recipients) = self.batches.get(&item.hash) {
+                    for (i, (to, amount)) in recipients.iter().enumerate() {
+                        let result = item.items.get(i).map(ItemResult::to_string).unwrap_or_else(|| "pending".to_string());
+                        common::single_label(ui, "", &format!("#{} {} to {}  {}", i, self.properties.format_balance(*amount), to, result));
+                    }
+                }
                 common::five_space(ui);
             }
-            if common::right_bottom_button(ui, "Submit") {
-                self.transfer(ctx,state);
+
+            let mut scan = false;
+            if let Some(history) = &self.history {
+                common::ten_space(ui);
+                ui.horizontal(|ui| {
+                    common::label(ui, "Transaction history");
+                    let progress = if self.scanning { format!("scanning, at block #{}", history.checkpoint) } else { format!("up to block #{}", history.checkpoint.saturating_sub(1)) };
+                    common::single_label(ui, "", &progress);
+                    scan = !self.scanning && common::ssmall_button(ui, "refresh").clicked();
+                });
+                ui.separator();
+                let page = history.page(None, self.history_page, PAGE_SIZE);
+                for entry in &page.entries {
+                    let (direction, party) = match entry.direction {
+                        Direction::In => ("In ", "from"),
+                        Direction::Out => ("Out", "to"),
+                    };
+                    let time = entry.timestamp.map(history::format_timestamp).unwrap_or_else(|| format!("#{}", entry.block_number));
+                    let fee = entry.fee.map(|fee| format!("  fee {}", self.properties.format_balance(fee))).unwrap_or_default();
+                    common::single_label(ui, &format!("{}  {}", time, direction), &format!("{}  {} {}{}", self.properties.format_balance(entry.amount), party, entry.counterparty, fee));
+                }
+                if page.pages > 1 {
+                    ui.horizontal(|ui| {
+                        if self.history_page > 0 && common::ssmall_button(ui, "prev").clicked() {
+                            self.history_page -= 1;
+                        }
+                        common::single_label(ui, "", &format!("{} / {}", page.page + 1, page.pages));
+                        if page.page + 1 < page.pages && common::ssmall_button(ui, "next").clicked() {
+                            self.history_page += 1;
+                        }
+                    });
+                }
+            }
+            if scan {
+                self.scan_history(ctx, state);
+            }
+            if self.prepared.is_none() && ready && common::right_bottom_button(ui, "Submit") {
+                if self.multiple {
+                    self.transfer_batch(ctx, state);
+                } else {
+                    self.transfer(ctx,state);
+                }
             }
             self.bottom_status_bar.set_view(ui, &self.status);
         });
@@ -0,0 +1,56 @@
+use coreui::state::AppState;
+use polkadot::history::History;
+
+/// Transfer history of the wallet accounts, persisted as `HISTORY` with its scan checkpoint.
+pub fn history(state: &AppState) -> Option<History> {
+    state.get_value("HISTORY").and_then(|data| serde_json::from_str::<History>(&data).ok())
+}
+
+pub fn set_history(state: &AppState, history: &History) {
+    if let Ok(data) = serde_json::to_string(history) {
+        state.set_value("HISTORY".to_owned(), data);
+    }
+}
+
+/// Drop the history with its checkpoint, it belongs to the chain it was scanned on.
+pub fn clear_history(state: &AppState) {
+    state.remove_value("HISTORY");
+}
+
+/// `YYYY-MM-DD hh:mm` in UTC of a block timestamp in milliseconds.
+pub fn format_timestamp(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+    // civil date from days since 1970-01-01, Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, rest / 3_600, rest % 3_600 / 60)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let state = AppState::new();
+        assert_eq!(history(&state), None);
+        let history_value = History::new(vec!["14E5nqKAp3oAJcmzgZhUD2RcptBeUBScxKHgJKU4HPNcKVf3".to_string()], 42);
+        set_history(&state, &history_value);
+        assert_eq!(history(&state), Some(history_value));
+        clear_history(&state);
+        assert_eq!(history(&state), None);
+
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1_700_000_000_000), "2023-11-14 22:13");
+        assert_eq!(format_timestamp(951_782_400_000), "2000-02-29 00:00");
+    }
+}
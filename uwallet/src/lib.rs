@@ -13,15 +13,21 @@ use coreui::{
 
 use crate::{activity::{
     home::HomeActivity,
+    multisig::MultisigActivity,
     password::PasswordActivity,
     phrase::PhraseActivity,
+    pools::PoolsActivity,
     setting::SettingActivity,
+    staking::StakingActivity,
     transfer::TransferActivity,
     welcome::WelcomeActivity,
 }};
 
 mod activity;
 mod view;
+mod account;
+mod network;
+mod history;
 
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -50,20 +56,22 @@ impl eframe::App for WalletApp {
 impl WalletApp {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        //https://rpc.polkadot.io
-        let client = Arc::new(polkadot::client::Client::new(String::from("http://127.0.0.1:9933")));
         let executor = Arc::new(Executor::new());
         let mut app_state = AppState::new();
         if let Some(storage) = cc.storage {
-            app_state = AppState(eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default());
+            app_state = AppState::from_data(eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default());
         }
+        let client = Arc::new(polkadot::client::Client::new(network::endpoints(&app_state)));
         let mut app = coreui::app::App::new(cc.egui_ctx.clone(), app_state);
         app.boot_act(&ActName::new("welcome"), WelcomeActivity::new());
         //app.boot_act(&ActName::new("temp"), template::TemplateActivity::new());
         app.register(&ActName::new("password"), PasswordActivity::new());
         app.register(&ActName::new("phrase"), PhraseActivity::new());
         app.register(&ActName::new("transfer"), TransferActivity::new(cc.egui_ctx.clone(), client.clone()));
-        app.register(&ActName::new("setting"), SettingActivity::new(cc.egui_ctx.clone()));
+        app.register(&ActName::new("staking"), StakingActivity::new(cc.egui_ctx.clone(), client.clone()));
+        app.register(&ActName::new("pools"), PoolsActivity::new(cc.egui_ctx.clone(), client.clone()));
+        app.register(&ActName::new("multisig"), MultisigActivity::new(cc.egui_ctx.clone(), client.clone()));
+        app.register(&ActName::new("setting"), SettingActivity::new(cc.egui_ctx.clone(), client.clone()));
         app.register(&ActName::new("home"), HomeActivity::new(cc.egui_ctx.clone(), client.clone()));
         Self {
             app
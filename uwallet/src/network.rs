@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+
+use coreui::state::AppState;
+use polkadot::rpc::types::{ChainInfo, SystemProperties};
+
+/// Used until the user saves their own list: a local node first, public ones as fallback.
+pub const DEFAULT_ENDPOINTS: &[&str] = &["ws://127.0.0.1:9944", "wss://rpc.polkadot.io"];
+
+/// A validator's own node, where session keys are generated. It has to allow unsafe methods.
+pub const DEFAULT_OPERATOR_ENDPOINT: &str = "http://127.0.0.1:9944";
+
+/// RPC endpoints persisted as `ENDPOINTS`.
+pub fn endpoints(state: &AppState) -> Vec<String> {
+    if let Some(data) = state.get_value("ENDPOINTS") {
+        if let Ok(endpoints) = serde_json::from_str::<Vec<String>>(&data) {
+            if !endpoints.is_empty() {
+                return endpoints;
+            }
+        }
+    }
+    DEFAULT_ENDPOINTS.iter().map(|endpoint| endpoint.to_string()).collect()
+}
+
+pub fn set_endpoints(state: &AppState, endpoints: &[String]) {
+    if let Ok(data) = serde_json::to_string(endpoints) {
+        state.set_value("ENDPOINTS".to_owned(), data);
+    }
+}
+
+/// Node session keys are rotated on, persisted as `OPERATOR_ENDPOINT`. Not one of the
+/// `endpoints`, which may be public nodes.
+pub fn operator_endpoint(state: &AppState) -> String {
+    state.get_value("OPERATOR_ENDPOINT").unwrap_or(DEFAULT_OPERATOR_ENDPOINT.to_string())
+}
+
+pub fn set_operator_endpoint(state: &AppState, endpoint: &str) {
+    state.set_value("OPERATOR_ENDPOINT".to_owned(), endpoint.to_string());
+}
+
+/// What the node last reported about its chain, persisted as `CHAIN`.
+pub fn chain_info(state: &AppState) -> Option<ChainInfo> {
+    state.get_value("CHAIN").and_then(|data| serde_json::from_str::<ChainInfo>(&data).ok())
+}
+
+pub fn set_chain_info(state: &AppState, info: &ChainInfo) {
+    if let Ok(data) = serde_json::to_string(info) {
+        state.set_value("CHAIN".to_owned(), data);
+    }
+}
+
+/// Genesis hash of the chain the endpoints serve, persisted as `GENESIS`.
+pub fn genesis_hash(state: &AppState) -> Option<String> {
+    state.get_value("GENESIS")
+}
+
+/// Remember which chain the endpoints serve. Returns whether it is another one than before,
+/// its stored chain info is then dropped.
+pub fn set_genesis_hash(state: &AppState, hash: &str) -> bool {
+    let changed = genesis_hash(state).map_or(false, |stored| stored != hash);
+    if changed {
+        state.remove_value("CHAIN");
+    }
+    state.set_value("GENESIS".to_owned(), hash.to_string());
+    changed
+}
+
+pub fn properties(state: &AppState) -> SystemProperties {
+    chain_info(state).map(|info| info.properties).unwrap_or_default()
+}
+
+/// Addresses are encoded with the prefix of the chain, Polkadot's until a node was asked.
+pub fn ss58_prefix(state: &AppState) -> u16 {
+    chain_info(state).map(|info| info.properties.ss58_prefix()).unwrap_or(0)
+}
+
+/// One endpoint per line, blank lines are skipped.
+pub fn parse_endpoints(text: &str) -> Result<Vec<String>> {
+    let mut endpoints = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let supported = ["http://", "https://", "ws://", "wss://"].iter().any(|scheme| line.starts_with(scheme));
+        if !supported {
+            return Err(anyhow!("{} is not an http(s) or ws(s) url", line));
+        }
+        if !endpoints.iter().any(|endpoint| endpoint == line) {
+            endpoints.push(line.to_string());
+        }
+    }
+    if endpoints.is_empty() {
+        return Err(anyhow!("at least one endpoint is needed"));
+    }
+    Ok(endpoints)
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let state = AppState::new();
+        assert_eq!(endpoints(&state), vec!["ws://127.0.0.1:9944", "wss://rpc.polkadot.io"]);
+        let list = parse_endpoints(" wss://a.example\n\nhttp://127.0.0.1:9933\nwss://a.example").unwrap();
+        assert_eq!(list, vec!["wss://a.example", "http://127.0.0.1:9933"]);
+        set_endpoints(&state, &list);
+        assert_eq!(endpoints(&state), list);
+        assert!(parse_endpoints("127.0.0.1:9944").is_err());
+        assert!(parse_endpoints("\n").is_err());
+
+        assert_eq!(operator_endpoint(&state), DEFAULT_OPERATOR_ENDPOINT);
+        set_operator_endpoint(&state, "ws://10.0.0.2:9944");
+        assert_eq!(operator_endpoint(&state), "ws://10.0.0.2:9944");
+
+        assert_eq!(ss58_prefix(&state), 0);
+        let info = ChainInfo { chain: "Kusama".to_string(), name: "Parity Polkadot".to_string(), properties: SystemProperties { ss58_format: Some(2), token_decimals: Some(12), token_symbol: Some("KSM".to_string()) } };
+        set_chain_info(&state, &info);
+        assert_eq!(chain_info(&state), Some(info));
+        assert_eq!(ss58_prefix(&state), 2);
+        assert_eq!(properties(&state).format_balance(1_500_000_000_000), "1.5 KSM");
+
+        assert!(!set_genesis_hash(&state, "0xb0a8"));
+        assert!(!set_genesis_hash(&state, "0xb0a8"));
+        assert_eq!(chain_info(&state), Some(info));
+        assert!(set_genesis_hash(&state, "0x91b1"));
+        assert_eq!(genesis_hash(&state).as_deref(), Some("0x91b1"));
+        assert_eq!(chain_info(&state), None);
+    }
+}
@@ -2,10 +2,14 @@ use eframe::emath::Vec2;
 use log::debug;
 
 use coreui::{eframe, egui, egui::Ui};
+use polkadot::keys::CryptoScheme;
 
-pub fn left_menu(ctx: &egui::Context) -> (bool, bool, bool) {
+pub fn left_menu(ctx: &egui::Context) -> (bool, bool, bool, bool, bool, bool) {
     let mut home = false;
     let mut transfer = false;
+    let mut staking = false;
+    let mut pools = false;
+    let mut multisig = false;
     let mut setting = false;
     egui::SidePanel::left("side_panel").show(ctx, |ui| {
         five_space(ui);
@@ -25,13 +29,31 @@ pub fn left_menu(ctx: &egui::Context) -> (bool, bool, bool) {
         five_space(ui);
         ui.separator();
         five_space(ui);
+        if ui.button(egui::RichText::new("Staking").size(15.0).color(egui::Color32::LIGHT_BLUE)).clicked() {
+            staking = true;
+        }
+        five_space(ui);
+        ui.separator();
+        five_space(ui);
+        if ui.button(egui::RichText::new("Pools").size(15.0).color(egui::Color32::LIGHT_BLUE)).clicked() {
+            pools = true;
+        }
+        five_space(ui);
+        ui.separator();
+        five_space(ui);
+        if ui.button(egui::RichText::new("Multisig").size(15.0).color(egui::Color32::LIGHT_BLUE)).clicked() {
+            multisig = true;
+        }
+        five_space(ui);
+        ui.separator();
+        five_space(ui);
         if ui.button(egui::RichText::new("Setting").size(15.0).color(egui::Color32::LIGHT_BLUE)).clicked() {
             setting = true;
         }
         five_space(ui);
         ui.separator();
     });
-    (home, transfer, setting)
+    (home, transfer, staking, pools, multisig, setting)
 }
 
 
@@ -71,6 +93,16 @@ pub fn simple_input_label(ui: &mut Ui, title: &str, hit: &str, text: &mut String
 }
 
 
+pub fn scheme_selector(ui: &mut Ui, scheme: &mut CryptoScheme) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new("Scheme:").size(15.0).color(egui::Color32::GRAY));
+        for item in CryptoScheme::all() {
+            ui.radio_value(scheme, item, egui::RichText::new(item.to_string()).size(14.0).color(egui::Color32::GRAY));
+        }
+    });
+}
+
+
 pub fn title(ui: &mut Ui, title: &str) {
     ui.vertical_centered(|ui| {
         ui.heading(egui::RichText::new(title).size(25.0).color(egui::Color32::WHITE));